@@ -15,11 +15,11 @@ self.addEventListener('install', function (e) {
   );
 });
 
-/* Serve cached content when offline */
+/* Serve cached content only when offline, so a reload always picks up a new deployment */
 self.addEventListener('fetch', function (e) {
   e.respondWith(
-    caches.match(e.request).then(function (response) {
-      return response || fetch(e.request);
+    fetch(e.request).catch(function () {
+      return caches.match(e.request);
     })
   );
 });
@@ -1,15 +1,58 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
 use eframe::App;
-use common::packets::c2s::{AddNickname, AskForPersonProfile, DeleteNickname, RequestKind, VoteNickname};
-use common::packets::s2c::{ClassList, PersonProfileResponse};
+use serde::de::DeserializeOwned;
+use common::packets::c2s::{AddComment, AddNickname, ChangeDisplayName, AskForClassSummary, AskForHistory, AskForLeaderboard, AskForNicknameHistory, AskForPersonProfile, AskForSuggestions, AskForVoteSummary, AskForWhoAmI, AskForWordStats, BatchVotes, ChangePassword, ClientError, DeleteComment, DeleteNickname, RequestKind, Subscribe, TransferNickname, VoteNickname};
+use common::packets::s2c::{Capabilities, ClassList, ClassSummary, Leaderboard, Locked, NicknameHistory, PasswordChange, PersonProfileResponse, ProfilHistory, Push, Suggestions, VoteCount, VoteSummary, WhoAmI, WordStats};
+use crate::avatars;
+use crate::class_board::ClassBoard;
 use crate::class_selector::ClassSelector;
+use crate::class_summary;
+use crate::confetti::Confetti;
+use common::deep_link::DeepLink;
+use common::language::Language;
+use common::version::BuildInfo;
+use common::REQUEST_ID_HEADER;
+use crate::deep_link;
+use crate::credentials::{self, CredentialStore};
 use crate::editor_selector::EditorSelector;
-use crate::person_selector::{Action, PersonSelector};
+use crate::etags::{self, SharedETags};
+use crate::language;
+use crate::leaderboard::{self, LeaderboardViewer};
+use crate::onboarding::{Completed, Onboarding};
+use crate::onboarding;
+use crate::offline::{Connectivity, Mutation, Offline};
+use crate::panic_report;
+use crate::password_form::PasswordForm;
+use crate::person_selector::{with_reference, Action, PersonSelector};
+use crate::presentation::Presentation;
+use crate::push::PushChannel;
+use crate::resume::Resume;
+use crate::stats_viewer::StatsViewer;
+use crate::update_check;
+
+const SUMMARY_SIZE: usize = 3; //propositions per participant loaded with the class, the others come when the person is selected
 
 enum IncomingPacket {
+    Capabilities(Capabilities),
     ClassList(ClassList),
+    ClassSummary(ClassSummary),
     PersonProfileResponse(PersonProfileResponse),
+    PublicProfiles(PersonProfileResponse),
+    VoteSummary(VoteSummary),
+    ProfilHistory(ProfilHistory),
+    NicknameHistory(NicknameHistory),
+    WordStats(WordStats),
+    Leaderboard(Leaderboard),
+    Suggestions(Suggestions),
+    WhoAmI(Option<WhoAmI>),
+    Locked(Locked), //answer of /whoami for a profil locked after too many failed logins
+    PasswordChange(PasswordChange),
+    Avatar(String, egui::ColorImage), //hash and pixels, decoded on the fetch thread
+    Offline, //a request didn't reach the server
+    Unsent(Mutation), //a change that didn't reach the server, queued until it answers again
 }
 
 pub struct HttpApp {
@@ -18,105 +61,635 @@ pub struct HttpApp {
     editor_selector: EditorSelector,
     class_selector: ClassSelector,
     person_selector: PersonSelector,
+    presentation: Presentation,
+    stats_viewer: StatsViewer,
+    class_board: ClassBoard,
+    leaderboard: LeaderboardViewer,
+    confetti: Confetti,
+    proposed: BTreeSet<(String, String)>, //(name, nickname) proposed during this session, celebrated when they take the lead
+    leading: BTreeSet<(String, String)>,
+    public_profiles: BTreeMap<String, BTreeMap<String, VoteCount>>, //what the presentation mode may show, the class threshold applied
+    pending_link: Option<DeepLink>, //link the page was opened with, applied once the classes are known
+    current_link: DeepLink,
+    capabilities: Option<Capabilities>,
+    class_summary: Option<ClassSummary>,
+    onboarding: Option<Onboarding>, //first launch wizard, none once completed
+    resume: Resume,
+    display_name: String, //typed in "Mon compte", sent with "Changer"
+    password_form: PasswordForm,
+    push: PushChannel,
+    credentials: CredentialStore,
+    language: Option<Language>, //of the server errors, sent as Accept-Language
+    etags: SharedETags, //shared with the fetch callbacks, which run on background threads on native
+    offline: Offline, //what was shown last and the changes waiting for the network
+    crash: Option<ClientError>, //the panic that stopped the panels, shown until "Recharger l'état"
+    server: String, //base url of the server, empty on the web where requests are relative to the page
     ctx: egui::Context,
 }
 
 impl HttpApp {
 
-    fn fetch<T>(&self, request: ehttp::Request, deserializer: T)
-        where T: Send + 'static + FnOnce(String) -> Option<IncomingPacket>
+    fn url(&self, endpoint: &str) -> String {
+        if self.server.is_empty() {
+            endpoint.to_string()
+        } else {
+            format!("{}/{}", self.server.trim_end_matches('/'), endpoint)
+        }
+    }
+
+    fn fetch<P>(&self, request: ehttp::Request, wrap: fn(P) -> IncomingPacket)
+        where P: DeserializeOwned + 'static
+    {
+        self.fetch_or_queue(request, wrap, None);
+    }
+
+    //the response is parsed in the fetch callback, which runs on a background thread on native,
+    //only the parsed packet reaches the egui thread through the channel; a change lost by the network comes back as unsent
+    fn fetch_or_queue<P>(&self, mut request: ehttp::Request, wrap: fn(P) -> IncomingPacket, unsent: Option<Mutation>)
+        where P: DeserializeOwned + 'static
     {
+        language::apply(&mut request, self.language);
+        let key = etags::key(&request);
+        if let Some(tag) = self.etags.lock().expect("Failed to lock etags").tag(key) {
+            request.headers.insert("If-None-Match", tag);
+        }
+        let etags = self.etags.clone();
         let new_sender = self.sender.clone();
         let ctx = self.ctx.clone();
+        let url = request.url.clone();
+        let reachable = self.offline.reachable();
 
         ehttp::fetch(request, move |response| {
-            let response = response.map(|result| String::from_utf8(result.bytes));
-            if let Ok(Ok(response)) = response {
-                let packet = deserializer(response);
-                if let Some(packet) = packet {
-                    let _ = new_sender.send(packet).expect("Failed to send packet");
+            let Ok(response) = response else {
+                reachable.store(false, Ordering::Relaxed);
+                new_sender.send(unsent.map_or(IncomingPacket::Offline, IncomingPacket::Unsent)).expect("Failed to send packet");
+                ctx.request_repaint();
+                return;
+            };
+            if !reachable.swap(true, Ordering::Relaxed) {
+                ctx.request_repaint(); //an unchanged answer repaints nothing, the queue waits for a frame
+            }
+            //an unchanged answer to what is already shown is dropped before egui repaints for nothing
+            let Some(bytes) = etags.lock().expect("Failed to lock etags").answered(&url, key, &response) else {
+                return;
+            };
+            let request_id = response.headers.get(REQUEST_ID_HEADER).unwrap_or("-");
+            match serde_json::from_slice::<P>(&bytes) {
+                Ok(packet) => {
+                    new_sender.send(wrap(packet)).expect("Failed to send packet");
                     ctx.request_repaint();
                 }
+                Err(e) => log::error!("Failed to parse the response of {} (status {}, request {}): {}", url, response.status, request_id, e),
             }
         });
     }
 
-    fn request_class_list(&mut self) {
-        let request = ehttp::Request::get("class_list");
-        self.fetch(request, |response| {
-            let class_list: ClassList = serde_json::from_str(&response).expect("Failed to parse class list");
-            Some(IncomingPacket::ClassList(class_list))
+    //both builds are shown so a client left over from a previous deployment is easy to spot
+    fn display_footer(&self, ctx: &egui::Context) {
+        let client = BuildInfo::current();
+        egui::TopBottomPanel::bottom("footer").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if !self.offline.is_online() {
+                    let waiting = match self.offline.queued() {
+                        0 => String::new(),
+                        queued => format!(", {} modification(s) en attente", queued),
+                    };
+                    ui.small(egui::RichText::new(format!("hors ligne, dernières données connues{}", waiting)).color(egui::Color32::from_rgb(255, 180, 0)));
+                }
+                ui.small(format!("client {}", client));
+                match self.capabilities.as_ref().map(|c| &c.server) {
+                    Some(server) if server.git_hash != client.git_hash => {
+                        ui.small(egui::RichText::new(format!("serveur {}", server)).color(egui::Color32::from_rgb(255, 100, 100)));
+                    }
+                    Some(server) => {
+                        ui.small(format!("serveur {}", server));
+                    }
+                    None => {}
+                }
+            });
         });
     }
 
-    const PROFILE_RESPONSE_HANDLER: fn(String) -> Option<IncomingPacket> = |response| {
-        let person_profile_response: PersonProfileResponse = serde_json::from_str(&response).expect("Failed to parse person profile response");
-        Some(IncomingPacket::PersonProfileResponse(person_profile_response))
-    };
+    fn request_capabilities(&mut self) {
+        let request = ehttp::Request::get(self.url("capabilities"));
+        self.fetch(request, IncomingPacket::Capabilities);
+    }
+
+    fn request_class_list(&mut self) {
+        let request = ehttp::Request::get(self.url("class_list"));
+        self.fetch(request, IncomingPacket::ClassList);
+    }
 
     fn request_person_profile(&mut self, ask_for_person_profile: AskForPersonProfile) {
-        let request = ehttp::Request::json("person_profile", &ask_for_person_profile).expect("Failed to create request");
-        self.fetch(request, Self::PROFILE_RESPONSE_HANDLER);
+        let request = ehttp::Request::json(self.url("person_profile"), &ask_for_person_profile).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::PersonProfileResponse);
+    }
+
+    fn request_public_profiles(&mut self, class: &str) {
+        let ask_for_person_profile = AskForPersonProfile { class: class.to_string(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), kind: RequestKind::Public };
+        let request = ehttp::Request::json(self.url("person_profile"), &ask_for_person_profile).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::PublicProfiles);
+    }
+
+    //the server answers 401 to a refused login and 423 to a locked one, which the generic fetch would only log
+    fn request_whoami(&mut self, ask_for_whoami: AskForWhoAmI) {
+        let mut request = ehttp::Request::json(self.url("whoami"), &ask_for_whoami).expect("Failed to create request");
+        language::apply(&mut request, self.language);
+        let sender = self.sender.clone();
+        let ctx = self.ctx.clone();
+        ehttp::fetch(request, move |response| {
+            let identity = match response {
+                Ok(response) if response.status == 401 => None,
+                Ok(response) if response.status == 423 => {
+                    if let Ok(locked) = serde_json::from_slice::<Locked>(&response.bytes) {
+                        sender.send(IncomingPacket::Locked(locked)).expect("Failed to send packet");
+                        ctx.request_repaint();
+                    }
+                    return;
+                }
+                Ok(response) if response.ok => match serde_json::from_slice::<WhoAmI>(&response.bytes) {
+                    Ok(identity) => Some(identity),
+                    Err(e) => {
+                        log::error!("Failed to parse the response of whoami: {}", e);
+                        return;
+                    }
+                },
+                _ => return,
+            };
+            sender.send(IncomingPacket::WhoAmI(identity)).expect("Failed to send packet");
+            ctx.request_repaint();
+        });
+    }
+
+    fn request_vote_summary(&mut self, ask_for_vote_summary: AskForVoteSummary) {
+        let request = ehttp::Request::json(self.url("my_vote_summary"), &ask_for_vote_summary).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::VoteSummary);
+    }
+
+    fn request_class_summary(&mut self, ask_for_class_summary: AskForClassSummary) {
+        let request = ehttp::Request::json(self.url("class_summary"), &ask_for_class_summary).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::ClassSummary);
+    }
+
+    fn request_word_stats(&mut self, ask_for_word_stats: AskForWordStats) {
+        let request = ehttp::Request::json(self.url("word_stats"), &ask_for_word_stats).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::WordStats);
+    }
+
+    fn request_leaderboard(&mut self, only: Option<String>) {
+        let Some(class) = self.class_selector.get_selected().map(str::to_string) else {
+            return;
+        };
+        let ask_for_leaderboard = AskForLeaderboard { class, editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), only, top: leaderboard::TOP };
+        let request = ehttp::Request::json(self.url("leaderboard"), &ask_for_leaderboard).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::Leaderboard);
+    }
+
+    fn request_suggestions(&mut self, ask_for_suggestions: AskForSuggestions) {
+        let request = ehttp::Request::json(self.url("suggest"), &ask_for_suggestions).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::Suggestions);
+    }
+
+    fn request_history(&mut self, ask_for_history: AskForHistory) {
+        let request = ehttp::Request::json(self.url("profil_history"), &ask_for_history).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::ProfilHistory);
+    }
+
+    fn request_nickname_history(&mut self, ask_for_nickname_history: AskForNicknameHistory) {
+        let request = ehttp::Request::json(self.url("nickname_history"), &ask_for_nickname_history).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::NicknameHistory);
+    }
+
+    //the changes answered with the profiles, queued when the network loses them
+    fn send(&mut self, mutation: Mutation) {
+        let request = mutation.request(|endpoint| self.url(endpoint));
+        self.fetch_or_queue(request, IncomingPacket::PersonProfileResponse, Some(mutation));
+    }
+
+    fn batch_votes(&mut self, batch_votes: BatchVotes) {
+        self.person_selector.apply_batch(&batch_votes);
+        self.send(Mutation::Batch(batch_votes));
+    }
+
+    fn transfer_nickname(&mut self, transfer_nickname: TransferNickname) {
+        let request = ehttp::Request::json(self.url("transfer_nickname"), &transfer_nickname).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::NicknameHistory);
     }
 
     fn propose_nickname(&mut self, add_nickname: AddNickname) {
-        let request = ehttp::Request::json("add_nickname", &add_nickname).expect("Failed to create request");
-        self.fetch(request, Self::PROFILE_RESPONSE_HANDLER);
+        self.send(Mutation::Propose(add_nickname));
+    }
+
+    fn add_comment(&mut self, add_comment: AddComment) {
+        let request = ehttp::Request::json(self.url("add_comment"), &add_comment).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::PersonProfileResponse);
+    }
+
+    fn delete_comment(&mut self, delete_comment: DeleteComment) {
+        let request = ehttp::Request::json(self.url("delete_comment"), &delete_comment).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::PersonProfileResponse);
     }
 
     fn delete_nickname(&mut self, delete_nickname: DeleteNickname) {
-        let request = ehttp::Request::json("delete_nickname", &delete_nickname).expect("Failed to create request");
-        self.fetch(request, Self::PROFILE_RESPONSE_HANDLER);
+        self.send(Mutation::Delete(delete_nickname));
+    }
+
+    fn change_display_name(&mut self, change_display_name: ChangeDisplayName) {
+        let request = ehttp::Request::json(self.url("change_display_name"), &change_display_name).expect("Failed to create request");
+        self.fetch(request, IncomingPacket::PersonProfileResponse);
+    }
+
+    //a refused login comes back as 401 like for whoami, shown in the form instead of waiting forever
+    fn change_password(&mut self, change_password: ChangePassword) {
+        let mut request = ehttp::Request::json(self.url("change_password"), &change_password).expect("Failed to create request");
+        language::apply(&mut request, self.language);
+        let sender = self.sender.clone();
+        let ctx = self.ctx.clone();
+        ehttp::fetch(request, move |response| {
+            let refused = |error: &str, response: &ehttp::Response| PasswordChange {
+                error: Some(error.to_string()),
+                request_id: response.headers.get(REQUEST_ID_HEADER).map(str::to_string),
+            };
+            let change = match response {
+                Ok(response) if response.status == 401 => refused("identifiants refusés", &response),
+                Ok(response) if response.ok => match serde_json::from_slice::<PasswordChange>(&response.bytes) {
+                    Ok(change) => change,
+                    Err(e) => {
+                        log::error!("Failed to parse the response of change_password: {}", e);
+                        refused("réponse du serveur illisible", &response)
+                    }
+                },
+                Ok(response) => refused("le serveur a refusé la demande", &response),
+                Err(_) => PasswordChange { error: Some("serveur injoignable".to_string()), request_id: None },
+            };
+            sender.send(IncomingPacket::PasswordChange(change)).expect("Failed to send packet");
+            ctx.request_repaint();
+        });
     }
 
     fn vote_nickname(&mut self, vote_nickname: VoteNickname) {
-        let request = ehttp::Request::json("vote_nickname", &vote_nickname).expect("Failed to create request");
-        self.fetch(request, Self::PROFILE_RESPONSE_HANDLER);
+        let mode = self.person_selector.vote_mode(&vote_nickname.class);
+        self.person_selector.apply_vote(&vote_nickname, mode);
+        self.send(Mutation::Vote(vote_nickname));
+    }
+
+    //not through fetch: the answer is a png, not json
+    fn request_avatar(&self, hash: String) {
+        let request = ehttp::Request::get(self.url(&format!("avatar/{}", hash)));
+        let sender = self.sender.clone();
+        let ctx = self.ctx.clone();
+        ehttp::fetch(request, move |response| {
+            let Some(image) = response.ok().filter(|r| r.ok).and_then(|r| avatars::decode(&r.bytes)) else {
+                return;
+            };
+            sender.send(IncomingPacket::Avatar(hash, image)).expect("Failed to send packet");
+            ctx.request_repaint();
+        });
+    }
+
+    //the pushes go through the same handling as the answers, a profile pushed is a partial response
+    fn check_pushes(&mut self, ctx: &egui::Context) {
+        for push in self.push.poll(ctx, &self.server) {
+            let packet = match push {
+                Push::Classes(class_list) => IncomingPacket::ClassList(class_list),
+                Push::Profiles(profiles) => IncomingPacket::PersonProfileResponse(profiles),
+            };
+            self.sender.send(packet).expect("Failed to send packet");
+        }
+        if let Some(class) = self.class_selector.get_selected() {
+            self.push.subscribe(Subscribe { class: class.to_string(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
+        }
     }
 
     fn check_incoming(&mut self) {
         let mut refresh_profiles = false;
+        let mut profiles_updated = false;
+        let mut summary_loaded = false;
+        let mut password_changed = false;
+        let mut unreachable = false;
         for message in self.incoming_message.try_iter() {
             match message {
+                IncomingPacket::Capabilities(capabilities) => self.capabilities = Some(capabilities),
                 IncomingPacket::ClassList(class_list) => {
+                    self.offline.remember_classes(&class_list);
+                    self.person_selector.set_locale(&class_list.locale);
+                    self.person_selector.set_vote_modes(class_list.vote_modes.clone());
+                    self.person_selector.set_proposition_cap(class_list.proposition_cap);
                     self.class_selector.set_classes(class_list);
+                    if let Some(link) = self.pending_link.take() {
+                        if self.class_selector.select(&link.class) {
+                            self.person_selector.selected = link.profil.unwrap_or_default();
+                        }
+                    }
                     refresh_profiles = true;
                 }
-                IncomingPacket::PersonProfileResponse(person_profile_response) => self.person_selector.set_persons(person_profile_response),
+                IncomingPacket::ClassSummary(class_summary) => self.class_summary = Some(class_summary),
+                IncomingPacket::PersonProfileResponse(person_profile_response) => {
+                    summary_loaded |= !person_profile_response.partial_response;
+                    if let Some(class) = self.class_selector.get_selected() {
+                        self.offline.remember_profiles(class, &person_profile_response);
+                    }
+                    self.class_board.set_board(&person_profile_response);
+                    self.person_selector.set_persons(person_profile_response);
+                    for hash in self.person_selector.missing_avatars() {
+                        self.request_avatar(hash);
+                    }
+                    profiles_updated = true;
+                }
+                IncomingPacket::PublicProfiles(public_profiles) => self.public_profiles = public_profiles.profiles,
+                IncomingPacket::VoteSummary(vote_summary) => self.person_selector.set_vote_summary(vote_summary),
+                IncomingPacket::ProfilHistory(history) => self.person_selector.set_history(history),
+                IncomingPacket::NicknameHistory(history) => self.person_selector.set_nickname_history(history),
+                IncomingPacket::WordStats(stats) => self.stats_viewer.set_stats(stats),
+                IncomingPacket::Leaderboard(leaderboard) => self.leaderboard.set_leaderboard(leaderboard),
+                IncomingPacket::Suggestions(suggestions) => self.person_selector.set_suggestions(suggestions),
+                IncomingPacket::WhoAmI(identity) => {
+                    let current = identity.filter(|i| Some(i.class.as_str()) == self.class_selector.get_selected() && i.name == self.editor_selector.get_name());
+                    if current.as_ref().is_some_and(|i| i.must_change_password) {
+                        self.password_form.force();
+                    }
+                    self.editor_selector.set_accepted(current.is_some());
+                }
+                IncomingPacket::Locked(locked) => self.editor_selector.set_locked(locked.locked_until),
+                IncomingPacket::PasswordChange(change) => {
+                    if let Some(new_password) = self.password_form.answered(change.error.map(|e| with_reference(e, change.request_id))) {
+                        self.editor_selector.set(self.editor_selector.get_name().to_string(), new_password);
+                        password_changed = true;
+                    }
+                }
+                IncomingPacket::Avatar(hash, image) => self.person_selector.avatars.set(&self.ctx, hash, image),
+                IncomingPacket::Offline => unreachable = true,
+                IncomingPacket::Unsent(mutation) => {
+                    self.offline.queue(mutation);
+                    unreachable = true;
+                }
             }
         }
 
+        if profiles_updated {
+            self.check_leads();
+        }
+
+        if unreachable {
+            self.show_last_known();
+        }
+
+        if let (true, Some(class)) = (password_changed, self.class_selector.get_selected().map(str::to_string)) {
+            self.check_login(&class);
+        }
+
+        //the class only comes with a summary, the selected person needs their full list
+        if let (true, Some(class)) = (summary_loaded && !self.person_selector.selected.is_empty(), self.class_selector.get_selected()) {
+            self.request_person_profile(AskForPersonProfile {
+                class: class.to_string(),
+                editor: self.editor_selector.get_name().to_string(),
+                password: self.editor_selector.get_password().to_string(),
+                kind: RequestKind::Custom(vec![self.person_selector.selected.clone()]),
+            });
+        }
+
         if refresh_profiles && self.person_selector.is_empty() {
             if let Some(selected) = self.class_selector.get_selected() {
-                self.request_person_profile(AskForPersonProfile { class: selected.to_string(), editor: "".to_string(), password: "".to_string(), kind: RequestKind::All })
+                let class = selected.to_string();
+                self.request_person_profile(AskForPersonProfile { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), kind: RequestKind::Top(SUMMARY_SIZE) });
+                self.request_class_summary(AskForClassSummary { class, editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
             }
         }
     }
 
-    pub fn new(ctx: &eframe::CreationContext) -> Self {
+    //without the server the panels show what it answered last, once per class, handled on the next frame like an answer
+    fn show_last_known(&mut self) {
+        if self.class_selector.classes().is_empty() {
+            if let Some(class_list) = self.offline.classes() {
+                self.sender.send(IncomingPacket::ClassList(class_list)).expect("Failed to send packet");
+            }
+        }
+        if let Some(profiles) = self.class_selector.get_selected().map(str::to_string).and_then(|class| self.offline.profiles(&class)) {
+            self.sender.send(IncomingPacket::PersonProfileResponse(profiles)).expect("Failed to send packet");
+        }
+        self.ctx.request_repaint();
+    }
+
+    //the changes queued while offline go first, in their order, then everything shown is asked for again
+    fn reconnect(&mut self) {
+        for mutation in self.offline.take_queue(self.editor_selector.get_name(), self.editor_selector.get_password()) {
+            self.send(mutation);
+        }
+        self.refresh();
+    }
+
+    fn check_leads(&mut self) {
+        let mut celebrate = false;
+        for (name, nickname) in &self.proposed {
+            let Some(nicknames) = self.person_selector.persons.get(name) else {
+                continue;
+            };
+            let score = nicknames.get(nickname).map_or(0.0, |v| v.score());
+            let leads = score > 0.0 && nicknames.iter().all(|(n, v)| n == nickname || v.score() < score);
+            if leads {
+                celebrate |= self.leading.insert((name.clone(), nickname.clone()));
+            } else {
+                self.leading.remove(&(name.clone(), nickname.clone()));
+            }
+        }
+        if celebrate {
+            self.confetti.burst(&self.ctx);
+        }
+    }
+
+    pub fn new(cc: &eframe::CreationContext) -> Self {
 
-        let ctx = ctx.egui_ctx.clone();
+        let ctx = cc.egui_ctx.clone();
+        let completed = cc.storage.and_then(|s| eframe::get_value::<bool>(s, onboarding::COMPLETED_KEY)).unwrap_or(false);
+        let (credentials, saved_login) = CredentialStore::load(cc.storage);
+        let language = cc.storage.and_then(|s| eframe::get_value::<Option<Language>>(s, language::KEY)).flatten();
+        let offline = Offline::load(cc.storage);
+        let server = if cfg!(target_arch = "wasm32") {
+            String::new()
+        } else {
+            cc.storage.and_then(|s| eframe::get_value::<String>(s, onboarding::SERVER_KEY)).unwrap_or_else(|| "http://localhost:8080".to_string())
+        };
 
         let (sender, incoming_message) = mpsc::channel();
         let mut this = Self {
             incoming_message,
             sender,
-            editor_selector: EditorSelector::new(),
+            editor_selector: saved_login.map_or_else(EditorSelector::new, EditorSelector::restore),
             class_selector: ClassSelector::new(),
             person_selector: PersonSelector::new(),
+            presentation: Presentation::new(),
+            stats_viewer: StatsViewer::new(),
+            class_board: ClassBoard::new(),
+            leaderboard: LeaderboardViewer::new(),
+            confetti: Confetti::new(),
+            proposed: BTreeSet::new(),
+            leading: BTreeSet::new(),
+            public_profiles: BTreeMap::new(),
+            pending_link: deep_link::read(),
+            current_link: DeepLink::default(),
+            capabilities: None,
+            class_summary: None,
+            onboarding: (!completed).then(|| Onboarding::new(&server)),
+            resume: Resume::new(),
+            display_name: String::new(),
+            password_form: PasswordForm::new(),
+            push: PushChannel::new(),
+            credentials,
+            language,
+            etags: SharedETags::default(),
+            offline,
+            crash: None,
+            server,
             ctx,
         };
+        this.request_capabilities();
         this.request_class_list();
         this
     }
-}
 
+    fn complete_onboarding(&mut self, completed: Completed) {
+        if !cfg!(target_arch = "wasm32") && completed.server != self.server {
+            self.server = completed.server;
+            self.etags.lock().expect("Failed to lock etags").clear();
+            self.push.reconnect();
+            self.request_capabilities();
+            self.request_class_list();
+        }
+        if !completed.name.is_empty() && !completed.password.is_empty() {
+            self.editor_selector.set(completed.name, completed.password);
+            if let Some(selected) = self.class_selector.get_selected() {
+                let class = selected.to_string();
+                self.request_person_profile(AskForPersonProfile { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), kind: RequestKind::Top(SUMMARY_SIZE) });
+                self.request_vote_summary(AskForVoteSummary { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
+                self.check_login(&class);
+            }
+        }
+        self.onboarding = None;
+    }
 
-impl App for HttpApp {
+    //the password may have been changed by an admin meanwhile
+    fn check_login(&mut self, class: &str) {
+        if self.editor_selector.is_filled() {
+            self.request_whoami(AskForWhoAmI { class: class.to_string(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
+        }
+    }
 
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    //everything shown may have changed while the page was suspended, the login is checked again by the answers
+    fn refresh(&mut self) {
+        self.push.reconnect();
+        self.request_capabilities();
+        self.request_class_list();
+        let Some(class) = self.class_selector.get_selected().map(str::to_string) else {
+            return;
+        };
+        //a partial update keeps the votes waiting in batch mode
+        let kind = if self.person_selector.is_empty() {
+            RequestKind::Top(SUMMARY_SIZE)
+        } else {
+            RequestKind::Custom(self.person_selector.persons.keys().cloned().collect())
+        };
+        self.request_person_profile(AskForPersonProfile { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), kind });
+        self.request_vote_summary(AskForVoteSummary { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
+        self.request_class_summary(AskForClassSummary { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
+        self.check_login(&class);
+        if self.presentation.active {
+            self.request_public_profiles(&class);
+        }
+    }
+
+    //what the crash keeps: the server, the login and the class and profil shown, the rest is asked for again
+    fn recover(&mut self) {
+        let (sender, incoming_message) = mpsc::channel(); //answers to the requests sent before are dropped
+        self.sender = sender;
+        self.incoming_message = incoming_message;
+        self.etags.lock().expect("Failed to lock etags").clear();
+        self.class_selector = ClassSelector::new();
+        self.person_selector = PersonSelector::new();
+        self.presentation = Presentation::new();
+        self.stats_viewer = StatsViewer::new();
+        self.class_board = ClassBoard::new();
+        self.leaderboard = LeaderboardViewer::new();
+        self.confetti = Confetti::new();
+        self.public_profiles.clear();
+        self.class_summary = None;
+        self.password_form = PasswordForm::new();
+        self.push = PushChannel::new();
+        self.pending_link = Some(self.current_link.clone()).filter(|l| !l.class.is_empty());
+        self.crash = None;
+        self.request_capabilities();
+        self.request_class_list();
+    }
+
+    fn display_crash(&mut self, ctx: &egui::Context) {
+        let Some(crash) = &self.crash else {
+            return;
+        };
+        let mut recover = false;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Une erreur est survenue");
+            ui.label("Elle a été signalée. Votre connexion et la page affichée sont gardées, le reste est rechargé depuis le serveur.");
+            ui.small(format!("{} ({})", crash.message, crash.location.as_deref().unwrap_or("?")));
+            recover = ui.button("Recharger l'état").clicked();
+        });
+        if recover {
+            self.recover();
+        }
+    }
 
+    fn update_link(&mut self) {
+        let Some(class) = self.class_selector.get_selected() else {
+            return;
+        };
+        let profil = Some(self.person_selector.selected.clone()).filter(|p| !p.is_empty());
+        if self.current_link.class != class || self.current_link.profil != profil {
+            self.current_link = DeepLink { class: class.to_string(), profil };
+            deep_link::write(&self.current_link);
+        }
+    }
+
+    fn update_panels(&mut self, ctx: &egui::Context) {
+
+        self.check_pushes(ctx);
         self.check_incoming();
+        if self.resume.update(ctx) {
+            self.refresh();
+        }
+        match self.offline.update(ctx) {
+            Connectivity::Unchanged => {}
+            Connectivity::Retry => self.request_class_list(),
+            Connectivity::Reconnected => self.reconnect(),
+        }
+        self.confetti.paint(ctx);
+
+        if let Some(onboarding) = &mut self.onboarding {
+            if let Some(completed) = onboarding.update(ctx) {
+                self.complete_onboarding(completed);
+            }
+            return;
+        }
+
+        if self.presentation.active {
+            let shown: Vec<(String, &BTreeMap<String, VoteCount>)> = self.person_selector.ordered()
+                .filter_map(|(name, _)| Some((self.person_selector.shown_name(name).to_string(), self.public_profiles.get(name)?)))
+                .collect();
+            let persons: Vec<_> = shown.iter().map(|(name, nicknames)| (name, *nicknames)).collect();
+            self.presentation.update(ctx, &persons);
+            return;
+        }
+
+        if let Some(new_password) = self.password_form.display(ctx) {
+            if let Some(class) = self.class_selector.get_selected().map(str::to_string) {
+                self.change_password(ChangePassword { class, editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), new_password });
+            }
+        }
+        if self.password_form.forced {
+            return; //nothing else until the new password is taken
+        }
+
+        if self.capabilities.as_ref().is_some_and(|c| update_check::is_outdated(&c.client)) {
+            update_check::display_banner(ctx);
+        }
+        self.display_footer(ctx);
 
         egui::CentralPanel::default().show(ctx, |ui| {
 
@@ -125,11 +698,61 @@ impl App for HttpApp {
                 //if ui.button("Rafraichir").clicked() { self.request_class_list(); } //refresh is totally silent now
 
                 let class_updated = self.class_selector.update(ui);
+                if self.class_selector.take_refresh() {
+                    self.request_class_list();
+                }
+                if !self.person_selector.is_empty() && ui.button("Mode présentation").clicked() {
+                    if let Some(class) = self.class_selector.get_selected().map(str::to_string) {
+                        self.public_profiles.clear();
+                        self.request_public_profiles(&class);
+                    }
+                    self.presentation.start(ctx);
+                }
+                if !self.person_selector.is_empty() && ui.button("Nuage de mots").clicked() {
+                    if let Some(class) = self.class_selector.get_selected().map(str::to_string) {
+                        self.request_word_stats(AskForWordStats { class, editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
+                        self.stats_viewer.open = true;
+                    }
+                }
+                if self.class_selector.get_selected().is_some() && ui.button("Tableau de classe").clicked() {
+                    self.class_board.open = true;
+                }
+                if self.class_selector.get_selected().is_some() && ui.button("Classement").clicked() {
+                    self.request_leaderboard(self.leaderboard.filter());
+                    self.leaderboard.open = true;
+                }
                 let editor_updated = self.editor_selector.update(ui);
+                if self.editor_selector.remember && credentials::keyring_available() {
+                    ui.checkbox(&mut self.credentials.use_keyring, "trousseau système")
+                        .on_hover_text("garde le mot de passe dans le trousseau du système plutôt que dans un fichier");
+                }
+                self.confetti.settings(ui);
+                language::selector(ui, &mut self.language);
+                if self.person_selector.can_modify() {
+                    ui.menu_button("Mon compte", |ui| {
+                        ui.label("nom affiché pour les autres, vide pour reprendre le vôtre");
+                        ui.text_edit_singleline(&mut self.display_name);
+                        if ui.button("Changer").clicked() {
+                            if let Some(class) = self.class_selector.get_selected().map(str::to_string) {
+                                self.change_display_name(ChangeDisplayName { class, editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), display_name: self.display_name.clone() });
+                            }
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        if ui.button("Changer le mot de passe").clicked() {
+                            self.password_form.open = true;
+                            ui.close_menu();
+                        }
+                    });
+                }
 
                 if class_updated || editor_updated {
                     if let Some(selected) = self.class_selector.get_selected() {
-                        self.request_person_profile(AskForPersonProfile { class: selected.to_string(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), kind: RequestKind::All })
+                        let class = selected.to_string();
+                        self.request_person_profile(AskForPersonProfile { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string(), kind: RequestKind::Top(SUMMARY_SIZE) });
+                        self.request_vote_summary(AskForVoteSummary { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
+                        self.request_class_summary(AskForClassSummary { class: class.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() }); //a login can see more than a guest
+                        self.check_login(&class);
                     }
                 }
             });
@@ -137,23 +760,101 @@ impl App for HttpApp {
             let requested_profiles = self.person_selector.display_name_selector(ui);
             if !requested_profiles.is_empty()
                 && self.class_selector.get_selected().is_some() {
+                let class = self.class_selector.get_selected().unwrap().to_string();
+                self.request_history(AskForHistory { class: class.clone(), name: self.person_selector.selected.clone(), editor: self.editor_selector.get_name().to_string(), password: self.editor_selector.get_password().to_string() });
                 self.request_person_profile(AskForPersonProfile{
-                    class: self.class_selector.get_selected().unwrap().to_string(),
+                    class,
                     editor: self.editor_selector.get_name().to_string(),
                     password: self.editor_selector.get_password().to_string(),
                     kind: RequestKind::Custom(requested_profiles),
                 })
             }
 
-            let action = self.person_selector.update_nickname_selector(ui, self.class_selector.get_selected(), &self.editor_selector.get_name(), &self.editor_selector.get_password());
+            let nobody_selected = !self.person_selector.persons.contains_key(&self.person_selector.selected);
+            if let (true, Some(summary)) = (nobody_selected, &self.class_summary) {
+                if self.class_selector.get_selected() == Some(summary.class.as_str()) {
+                    class_summary::display(ui, summary, self.person_selector.ordered().map(|(name, _)| name));
+                }
+            }
+
+            let action = self.person_selector.update_nickname_selector(ui, self.class_selector.get_selected(), self.editor_selector.get_name(), self.editor_selector.get_password());
             match action {
-                Action::Propose(add_nickname) => self.propose_nickname(add_nickname),
+                Action::Propose(add_nickname) => {
+                    self.proposed.insert((add_nickname.name.clone(), add_nickname.nickname.trim().to_string()));
+                    self.propose_nickname(add_nickname)
+                }
+                Action::ApplyVotes(batch_votes) => {
+                    self.confetti.burst(ctx);
+                    self.batch_votes(batch_votes)
+                }
                 Action::Delete(delete_nickname) => self.delete_nickname(delete_nickname),
-                Action::Vote(vote_nickname) => self.vote_nickname(vote_nickname),
+                Action::History(ask_for_nickname_history) => self.request_nickname_history(ask_for_nickname_history),
+                Action::Suggest(ask_for_suggestions) => self.request_suggestions(ask_for_suggestions),
+                Action::Comment(add_comment) => self.add_comment(add_comment),
+                Action::DeleteComment(delete_comment) => self.delete_comment(delete_comment),
+                Action::Vote(vote_nickname) => {
+                    self.confetti.burst(ctx);
+                    self.vote_nickname(vote_nickname)
+                }
                 _ => {}
             }
         });
 
+        let class = self.class_selector.get_selected().map(|c| c.to_string());
+        self.stats_viewer.display(ctx, class.as_deref());
+        let mode = class.as_deref().map(|c| self.person_selector.vote_mode(c)).unwrap_or_default();
+        match self.class_board.display(ctx, class.as_deref(), self.editor_selector.get_name(), self.editor_selector.get_password(), self.person_selector.can_modify(), mode) {
+            Action::Propose(add_nickname) => self.propose_nickname(add_nickname),
+            Action::Vote(vote_nickname) => {
+                self.confetti.burst(ctx);
+                self.vote_nickname(vote_nickname)
+            }
+            Action::Delete(delete_nickname) => self.delete_nickname(delete_nickname),
+            _ => {}
+        }
+        if let Some(only) = self.leaderboard.display(ctx, self.class_selector.classes()) {
+            self.request_leaderboard(only);
+        }
+        if let Some(transfer) = self.person_selector.display_nickname_history(ctx, class.as_deref(), self.editor_selector.get_name(), self.editor_selector.get_password()) {
+            self.transfer_nickname(transfer);
+        }
+
+        if self.pending_link.is_none() {
+            self.update_link();
+        }
+
+    }
+}
+
+
+impl App for HttpApp {
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, onboarding::COMPLETED_KEY, &self.onboarding.is_none());
+        eframe::set_value(storage, onboarding::SERVER_KEY, &self.server);
+        eframe::set_value(storage, language::KEY, &self.language);
+        self.credentials.save(storage, self.editor_selector.saved());
+        self.offline.save(storage);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.crash.is_some() {
+            self.display_crash(ctx);
+            return;
+        }
+        //a panel that panics, on a packet it didn't expect mostly, leaves an error screen instead of a dead window,
+        //on the web the panic stops the whole module and the page shows it instead, see main.rs
+        if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.update_panels(ctx))) {
+            let crash = panic_report::take_last().unwrap_or_else(|| ClientError {
+                message: payload.downcast_ref::<&str>().map(|s| s.to_string()).unwrap_or_else(|| "panic".to_string()),
+                location: None,
+                build: BuildInfo::current().to_string(),
+                page: String::new(),
+            });
+            panic_report::send(&self.url("client_error"), &crash);
+            self.crash = Some(crash);
+            ctx.request_repaint();
+        }
     }
 }
 
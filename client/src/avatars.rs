@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+use egui::{ColorImage, TextureHandle, TextureOptions};
+
+//textures of the avatars by hash, a hash names one image forever so each is fetched once
+#[derive(Default)]
+pub struct Avatars {
+    textures: BTreeMap<String, Option<TextureHandle>>, //none while it is fetched, or when it couldn't be decoded
+}
+
+//on the fetch thread, only the pixels go to the egui thread
+pub fn decode(bytes: &[u8]) -> Option<ColorImage> {
+    let image = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)
+        .inspect_err(|e| log::error!("Failed to decode an avatar: {}", e))
+        .ok()?
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Some(ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+impl Avatars {
+    //the hashes never asked for, marked as asked
+    pub fn missing<'a>(&mut self, hashes: impl Iterator<Item = &'a String>) -> Vec<String> {
+        let missing: Vec<String> = hashes.filter(|hash| !self.textures.contains_key(*hash)).cloned().collect();
+        for hash in &missing {
+            self.textures.insert(hash.clone(), None);
+        }
+        missing
+    }
+
+    pub fn set(&mut self, ctx: &egui::Context, hash: String, image: ColorImage) {
+        let texture = ctx.load_texture(format!("avatar-{}", hash), image, TextureOptions::LINEAR);
+        self.textures.insert(hash, Some(texture));
+    }
+
+    pub fn texture(&self, hash: &str) -> Option<&TextureHandle> {
+        self.textures.get(hash).and_then(Option::as_ref)
+    }
+}
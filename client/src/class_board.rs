@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use egui::RichText;
+use common::{Target, VoteMode};
+use common::packets::c2s::{AddNickname, DeleteNickname, VoteNickname};
+use common::packets::s2c::{PersonProfileResponse, VoteCount};
+use crate::person_selector::{with_reference, Action};
+
+//propositions for the class itself (its motto, its nickname), every response of the server carries them whole
+pub struct ClassBoard {
+    pub open: bool,
+    nicknames: BTreeMap<String, VoteCount>,
+    new_nickname: String,
+    error: Option<String>, //why the server refused the last modification of the board
+}
+
+impl ClassBoard {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            nicknames: BTreeMap::new(),
+            new_nickname: String::new(),
+            error: None,
+        }
+    }
+
+    //before the response goes to the person selector, which takes its error
+    pub fn set_board(&mut self, response: &PersonProfileResponse) {
+        self.nicknames = response.board.clone();
+        self.error = response.error.clone().map(|e| with_reference(e, response.request_id.clone()));
+    }
+
+    pub fn display(&mut self, ctx: &egui::Context, class: Option<&str>, editor_name: &str, password: &str, can_modify: bool, mode: VoteMode) -> Action {
+        let mut action = Action::None;
+        let Some(class) = class.filter(|_| self.open) else {
+            return action;
+        };
+
+        let mut open = true;
+        egui::Window::new(format!("Tableau de la classe {}", class))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("surnoms et devises pour toute la classe, seuls ses membres votent").color(egui::Color32::GRAY));
+                let target = Some(Target::Class(class.to_string()));
+                let vote = |nickname: &str, withdraw: bool| VoteNickname {
+                    class: class.to_string(),
+                    name: String::new(),
+                    nickname: nickname.to_string(),
+                    voter: editor_name.to_string(),
+                    password: password.to_string(),
+                    voter_class: None,
+                    withdraw,
+                    rank: None,
+                    target: target.clone(),
+                };
+
+                let mut sorted: Vec<(&String, &VoteCount)> = self.nicknames.iter().collect();
+                sorted.sort_by(|(_, a), (_, b)| b.score().total_cmp(&a.score()));
+                if sorted.is_empty() {
+                    ui.label("aucune proposition pour l'instant");
+                }
+                for (nickname, count) in sorted {
+                    ui.horizontal(|ui| {
+                        ui.label(nickname);
+                        ui.label(format!("{} votes", count.count));
+                        if !can_modify {
+                            return;
+                        }
+                        if count.contain_you {
+                            ui.label(RichText::new("votre vote").color(egui::Color32::GRAY));
+                            if !mode.is_single() && ui.button("Retirer").clicked() {
+                                action = Action::Vote(vote(nickname, true));
+                            }
+                        } else if count.protection.can_vote() && ui.button("Voter").clicked() {
+                            action = Action::Vote(vote(nickname, false));
+                        }
+                        if count.yours && count.protection.can_delete() && ui.button("Supprimer").clicked() {
+                            action = Action::Delete(DeleteNickname {
+                                class: class.to_string(),
+                                editor: editor_name.to_string(),
+                                password: password.to_string(),
+                                nickname: nickname.clone(),
+                                target: target.clone(),
+                            });
+                        }
+                    });
+                }
+
+                if can_modify {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.new_nickname);
+                        if ui.add_enabled(!self.new_nickname.trim().is_empty(), egui::Button::new("Proposer")).clicked() {
+                            action = Action::Propose(AddNickname {
+                                class: class.to_string(),
+                                editor: editor_name.to_string(),
+                                password: password.to_string(),
+                                name: String::new(),
+                                nickname: std::mem::take(&mut self.new_nickname),
+                                target: target.clone(),
+                            });
+                        }
+                    });
+                }
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+            });
+
+        if !open {
+            self.open = false;
+        }
+        action
+    }
+}
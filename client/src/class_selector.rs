@@ -1,27 +1,60 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
 use egui::Spinner;
+use web_time::{SystemTime, UNIX_EPOCH};
 use common::packets::s2c::ClassList;
+use common::time::format_unix_time;
 
 pub struct ClassSelector {
     classes: Vec<String>,
-    selected: usize
+    voting_opens: BTreeMap<String, u64>, //classes whose vote isn't open yet
+    voting_closes: BTreeMap<String, u64>, //classes with a deadline
+    read_only: bool, //the server refuses every change
+    selected: usize,
+    loaded: bool, //the server answered, an empty list is then really empty
+    refresh: bool,
 }
 
 impl ClassSelector {
     pub fn new() -> Self {
         Self {
             classes: Vec::new(),
-            selected: 0
+            voting_opens: BTreeMap::new(),
+            voting_closes: BTreeMap::new(),
+            read_only: false,
+            selected: 0,
+            loaded: false,
+            refresh: false,
         }
     }
 
     pub fn set_classes(&mut self, list: ClassList) {
         self.classes = list.names;
+        self.voting_opens = list.voting_opens;
+        self.voting_closes = list.voting_closes;
+        self.read_only = list.read_only;
+        self.loaded = true;
+    }
+
+    //the guidance screen asked for the list again
+    pub fn take_refresh(&mut self) -> bool {
+        std::mem::take(&mut self.refresh)
     }
 
     pub fn update(&mut self, ui: &mut egui::Ui) -> bool {
+        if self.classes.is_empty() && !self.loaded {
+            ui.horizontal(|ui| {
+                ui.add(Spinner::new());
+                ui.label("chargement des classes…");
+            });
+            return false;
+        }
         if self.classes.is_empty() {
-            ui.add(Spinner::new());
-            ui.label("Aucune classe disponible");
+            ui.label("Aucune classe disponible — demandez à l'organisateur d'en créer une.");
+            if ui.button("Réessayer").clicked() {
+                self.loaded = false;
+                self.refresh = true;
+            }
             return false;
         }
 
@@ -34,11 +67,51 @@ impl ClassSelector {
                 }
             });
         });
+        if let Some(opens) = self.get_selected().and_then(|class| self.voting_opens.get(class)) {
+            ui.label(egui::RichText::new(format!("le vote ouvre le {} UTC", format_unix_time(*opens))).color(egui::Color32::from_rgb(255, 180, 0)));
+        }
+        if let Some(closes) = self.get_selected().and_then(|class| self.voting_closes.get(class)) {
+            ui.label(egui::RichText::new(deadline(*closes)).color(egui::Color32::from_rgb(255, 180, 0)).strong());
+            ui.ctx().request_repaint_after(Duration::from_secs(1)); //the countdown ticks without input
+        }
+        if self.read_only {
+            ui.label(egui::RichText::new("le serveur est en lecture seule, les modifications sont refusées pour le moment").color(egui::Color32::from_rgb(255, 180, 0)));
+        }
 
-        return changed;
+        changed
+    }
+
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.classes.iter().position(|c| c == name) {
+            Some(i) => {
+                self.selected = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn classes(&self) -> &[String] {
+        &self.classes
     }
 
     pub fn get_selected(&self) -> Option<&str> {
         self.classes.get(self.selected).map(|s| s.as_str())
     }
-}
\ No newline at end of file
+}
+
+//"le vote ferme dans 2 j 03 h 15 min 08 s", the vote is closed once the deadline is past
+fn deadline(closes: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    if closes <= now {
+        return format!("le vote est clos depuis le {} UTC, les propositions et les votes sont figés", format_unix_time(closes));
+    }
+    let left = closes - now;
+    let (days, hours, minutes, seconds) = (left / 86_400, left / 3600 % 24, left / 60 % 60, left % 60);
+    let countdown = if days > 0 {
+        format!("{} j {:02} h {:02} min {:02} s", days, hours, minutes, seconds)
+    } else {
+        format!("{:02} h {:02} min {:02} s", hours, minutes, seconds)
+    };
+    format!("le vote ferme dans {} (le {} UTC)", countdown, format_unix_time(closes))
+}
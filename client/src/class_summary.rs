@@ -0,0 +1,48 @@
+use egui::RichText;
+use common::packets::s2c::ClassSummary;
+
+//shown in place of the nicknames while no participant is selected
+pub fn display<'a>(ui: &mut egui::Ui, summary: &ClassSummary, names: impl Iterator<Item = &'a String>) {
+    let participation = if summary.participants == 0 {
+        0.0
+    } else {
+        summary.voters as f32 / summary.participants as f32
+    };
+
+    ui.heading(&summary.class);
+    if summary.counts_hidden {
+        ui.label(format!("{} participants, {} surnoms proposés", summary.participants, summary.propositions));
+        ui.label(RichText::new("connectez-vous pour voir les votes").color(egui::Color32::GRAY));
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.label(format!("participation : {} / {}", summary.voters, summary.participants));
+        ui.add(egui::ProgressBar::new(participation).desired_width(150.0).show_percentage());
+    });
+    ui.label(format!("{} surnoms proposés", summary.propositions));
+    ui.add_space(8.0);
+
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new("class_summary").striped(true).show(ui, |ui| {
+            ui.heading("Participant");
+            ui.heading("En tête");
+            ui.heading("Votes");
+            ui.end_row();
+
+            for name in names {
+                ui.label(name);
+                match summary.leaders.get(name) {
+                    Some((nickname, count)) => {
+                        ui.label(nickname);
+                        ui.label(RichText::new(count.to_string()).color(egui::Color32::from_rgb(100, 100, 255)));
+                    }
+                    None => {
+                        ui.label(RichText::new("aucun vote").color(egui::Color32::GRAY));
+                        ui.label("");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    });
+}
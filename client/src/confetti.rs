@@ -0,0 +1,126 @@
+use egui::{Color32, Id, LayerId, Order, Pos2, Rect, Vec2};
+
+const PARTICLE_COUNT: usize = 80;
+const LIFETIME: f32 = 2.5; //seconds
+const GRAVITY: f32 = 600.0;
+const COLORS: [Color32; 5] = [
+    Color32::from_rgb(255, 100, 100),
+    Color32::from_rgb(100, 100, 255),
+    Color32::from_rgb(255, 200, 60),
+    Color32::from_rgb(90, 200, 120),
+    Color32::from_rgb(220, 120, 255),
+];
+
+struct Particle {
+    pos: Pos2,
+    vel: Vec2,
+    color: Color32,
+    age: f32,
+}
+
+pub struct Confetti {
+    pub enabled: bool,
+    pub sound: bool,
+    particles: Vec<Particle>,
+    seed: u32,
+}
+
+impl Confetti {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            sound: false,
+            particles: Vec::new(),
+            seed: 0x9E37_79B9,
+        }
+    }
+
+    //xorshift, good enough to scatter a few particles without pulling a rng crate in
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed % 10_000) as f32 / 10_000.0
+    }
+
+    pub fn settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.enabled, "Confettis");
+            if cfg!(target_arch = "wasm32") {
+                ui.add_enabled(self.enabled, egui::Checkbox::new(&mut self.sound, "Son"));
+            }
+        });
+    }
+
+    pub fn burst(&mut self, ctx: &egui::Context) {
+        if !self.enabled {
+            return;
+        }
+
+        let screen = ctx.screen_rect();
+        let origin = Pos2::new(screen.center().x, screen.bottom());
+        for _ in 0..PARTICLE_COUNT {
+            let angle = -std::f32::consts::FRAC_PI_2 + (self.random() - 0.5) * 1.2;
+            let speed = 500.0 + self.random() * 500.0;
+            let color = COLORS[(self.random() * COLORS.len() as f32) as usize % COLORS.len()];
+            self.particles.push(Particle {
+                pos: origin,
+                vel: Vec2::angled(angle) * speed,
+                color,
+                age: 0.0,
+            });
+        }
+
+        if self.sound {
+            play_sound();
+        }
+        ctx.request_repaint();
+    }
+
+    pub fn paint(&mut self, ctx: &egui::Context) {
+        if self.particles.is_empty() {
+            return;
+        }
+
+        let dt = ctx.input(|i| i.stable_dt).min(0.1);
+        let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("confetti")));
+        for particle in self.particles.iter_mut() {
+            particle.age += dt;
+            particle.vel.y += GRAVITY * dt;
+            particle.pos += particle.vel * dt;
+
+            let alpha = 1.0 - (particle.age / LIFETIME).clamp(0.0, 1.0);
+            let rect = Rect::from_center_size(particle.pos, Vec2::new(6.0, 10.0));
+            painter.rect_filled(rect, 1.0, particle.color.gamma_multiply(alpha));
+        }
+        self.particles.retain(|p| p.age < LIFETIME);
+        ctx.request_repaint();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn play_sound() {
+    //short rising beep with the web audio api, failures are silently ignored, it's only decoration
+    let play = || -> Option<()> {
+        let audio = web_sys::AudioContext::new().ok()?;
+        let oscillator = audio.create_oscillator().ok()?;
+        let gain = audio.create_gain().ok()?;
+        let now = audio.current_time();
+
+        oscillator.set_type(web_sys::OscillatorType::Triangle);
+        oscillator.frequency().set_value_at_time(520.0, now).ok()?;
+        oscillator.frequency().linear_ramp_to_value_at_time(1040.0, now + 0.15).ok()?;
+        gain.gain().set_value_at_time(0.2, now).ok()?;
+        gain.gain().linear_ramp_to_value_at_time(0.0, now + 0.3).ok()?;
+
+        oscillator.connect_with_audio_node(&gain).ok()?;
+        gain.connect_with_audio_node(&audio.destination()).ok()?;
+        oscillator.start().ok()?;
+        oscillator.stop_with_when(now + 0.3).ok()?;
+        Some(())
+    };
+    play();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn play_sound() {}
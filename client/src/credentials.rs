@@ -0,0 +1,105 @@
+//where a remembered login is kept: the os keyring on native when it works, eframe storage otherwise,
+//which is a plain file on native and the local storage of the page on the web
+use serde::{Deserialize, Serialize};
+
+pub const LOGIN_KEY: &str = "login";
+pub const KEYRING_KEY: &str = "use_keyring";
+#[cfg(not(target_arch = "wasm32"))]
+const SERVICE: &str = "sweat_voter";
+
+//what "se souvenir de moi" keeps between launches, there is no token to store in place of the password
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SavedLogin {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String, //empty when in_keyring
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub in_keyring: bool,
+}
+
+pub struct CredentialStore {
+    pub use_keyring: bool, //settings toggle, only shown on native
+    written: Option<SavedLogin>, //last login handed to save, the keyring is only touched when it or the toggle changes
+    written_keyring: bool,
+    stored: Option<SavedLogin>, //what eframe storage holds for it
+}
+
+impl CredentialStore {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> (Self, Option<SavedLogin>) {
+        let use_keyring = keyring_available() && storage.and_then(|s| eframe::get_value::<bool>(s, KEYRING_KEY)).unwrap_or(true);
+        let stored = storage.and_then(|s| eframe::get_value::<Option<SavedLogin>>(s, LOGIN_KEY)).flatten();
+        let login = stored.clone().map(|saved| if saved.in_keyring {
+            SavedLogin {
+                password: keyring_get(&saved.name).unwrap_or_default(), //asked again if the keyring lost it
+                in_keyring: false,
+                name: saved.name,
+            }
+        } else {
+            saved
+        });
+        (Self { use_keyring, written: login.clone(), written_keyring: use_keyring, stored }, login)
+    }
+
+    pub fn save(&mut self, storage: &mut dyn eframe::Storage, login: Option<SavedLogin>) {
+        if login != self.written || self.use_keyring != self.written_keyring {
+            if let Some(old) = self.stored.as_ref().filter(|s| s.in_keyring) {
+                keyring_delete(&old.name);
+            }
+            self.stored = login.clone().map(|login| self.protect(login));
+            self.written = login;
+            self.written_keyring = self.use_keyring;
+        }
+        eframe::set_value(storage, LOGIN_KEY, &self.stored); //written as none when not remembered, clearing an older one
+        eframe::set_value(storage, KEYRING_KEY, &self.use_keyring);
+    }
+
+    //falls back to eframe storage when the keyring refuses, the login is still remembered as asked
+    fn protect(&self, login: SavedLogin) -> SavedLogin {
+        if !self.use_keyring {
+            return login;
+        }
+        match keyring_set(&login.name, &login.password) {
+            Ok(()) => SavedLogin { name: login.name, password: String::new(), in_keyring: true },
+            Err(e) => {
+                log::warn!("Failed to store the login in the keyring, keeping it in the app storage: {}", e);
+                login
+            }
+        }
+    }
+}
+
+pub fn keyring_available() -> bool {
+    cfg!(not(target_arch = "wasm32"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn keyring_set(name: &str, password: &str) -> keyring::Result<()> {
+    keyring::Entry::new(SERVICE, name)?.set_password(password)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn keyring_get(name: &str) -> Option<String> {
+    keyring::Entry::new(SERVICE, name).and_then(|e| e.get_password())
+        .map_err(|e| log::warn!("Failed to read the login from the keyring: {}", e))
+        .ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn keyring_delete(name: &str) {
+    if let Err(e) = keyring::Entry::new(SERVICE, name).and_then(|e| e.delete_credential()) {
+        log::warn!("Failed to remove the login from the keyring: {}", e);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn keyring_set(_name: &str, _password: &str) -> Result<(), String> {
+    Err("no keyring on the web".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn keyring_get(_name: &str) -> Option<String> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn keyring_delete(_name: &str) {}
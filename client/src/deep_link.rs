@@ -0,0 +1,25 @@
+//reads and writes the page location, only the web build has one
+#![cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+
+use common::deep_link::DeepLink;
+
+#[cfg(target_arch = "wasm32")]
+pub fn read() -> Option<DeepLink> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    DeepLink::parse(&hash)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read() -> Option<DeepLink> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write(link: &DeepLink) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.location().set_hash(&link.to_fragment());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(_link: &DeepLink) {}
@@ -1,6 +1,12 @@
+use common::time::format_unix_time;
+use crate::credentials::SavedLogin;
+
 pub struct EditorSelector {
     name: String,
     password: String,
+    pub remember: bool, //unchecked, the login is forgotten when the app closes
+    accepted: Option<bool>, //answer of /whoami for the login typed, none until it comes
+    locked_until: Option<u64>, //unix seconds, the profil refuses every login until then
 }
 
 impl EditorSelector {
@@ -8,14 +14,65 @@ impl EditorSelector {
         Self {
             name: String::new(),
             password: String::new(),
+            accepted: None,
+            locked_until: None,
+            remember: false,
+        }
+    }
+
+    pub fn restore(saved: SavedLogin) -> Self {
+        Self {
+            name: saved.name,
+            password: saved.password,
+            accepted: None,
+            locked_until: None,
+            remember: true,
         }
     }
 
+    //none unless asked to remember
+    pub fn saved(&self) -> Option<SavedLogin> {
+        (self.remember && self.is_filled()).then(|| SavedLogin { name: self.name.clone(), password: self.password.clone(), in_keyring: false })
+    }
+
     pub fn update(&mut self, ui: &mut egui::Ui) -> bool {
         ui.label("Login");
-        let name_response = ui.add(egui::TextEdit::singleline(&mut self.name).hint_text("Nom Prénom").char_limit(30)).lost_focus();
-        let password_response = ui.add(egui::TextEdit::singleline(&mut self.password).hint_text("Mot de passe").char_limit(30)).lost_focus();
-        return (name_response || password_response) && !self.name.is_empty() && !self.password.is_empty()
+        let name_response = ui.add(egui::TextEdit::singleline(&mut self.name).hint_text("Nom Prénom").char_limit(30));
+        let password_response = ui.add(egui::TextEdit::singleline(&mut self.password).hint_text("Mot de passe").char_limit(30));
+        if name_response.changed() || password_response.changed() {
+            self.accepted = None;
+            self.locked_until = None;
+        }
+        ui.checkbox(&mut self.remember, "se souvenir de moi");
+        if let Some(until) = self.locked_until {
+            ui.label(egui::RichText::new(format!("compte temporairement verrouillé jusqu'à {} UTC", format_unix_time(until))).color(egui::Color32::from_rgb(255, 100, 100)))
+                .on_hover_text("trop de mots de passe erronés, réessayez plus tard ou demandez à l'administrateur");
+        }
+        match self.accepted {
+            Some(true) => { ui.label(egui::RichText::new("connecté").color(egui::Color32::from_rgb(90, 200, 120))); }
+            Some(false) => { ui.label(egui::RichText::new("identifiants refusés").color(egui::Color32::from_rgb(255, 100, 100))); }
+            None => {}
+        }
+        (name_response.lost_focus() || password_response.lost_focus()) && !self.name.is_empty() && !self.password.is_empty()
+    }
+
+    pub fn set(&mut self, name: String, password: String) {
+        self.name = name;
+        self.password = password;
+    }
+
+    pub fn set_accepted(&mut self, accepted: bool) {
+        self.accepted = Some(accepted);
+        self.locked_until = None;
+    }
+
+    pub fn set_locked(&mut self, until: u64) {
+        self.accepted = None; //the locked label says more than refused
+        self.locked_until = Some(until);
+    }
+
+    pub fn is_filled(&self) -> bool {
+        !self.name.is_empty() && !self.password.is_empty()
     }
 
     pub fn get_name(&self) -> &str {
@@ -0,0 +1,53 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+const MAX_KEPT: usize = 200; //answers remembered before starting over, a class has fewer participants than that
+const NOT_MODIFIED: u16 = 304;
+
+//the last answer of each request with its ETag, sent back as If-None-Match so an unchanged answer comes without a body
+#[derive(Default)]
+pub struct ETags {
+    answers: BTreeMap<u64, (String, Vec<u8>)>, //request -> tag and the body it came with
+    handled: BTreeMap<String, u64>, //url -> request whose answer was the last one handled
+}
+
+pub type SharedETags = Arc<Mutex<ETags>>;
+
+//two requests are the same when they go to the same url with the same body, the login is in the body
+pub fn key(request: &ehttp::Request) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    request.method.hash(&mut hasher);
+    request.url.hash(&mut hasher);
+    request.body.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl ETags {
+    pub fn tag(&self, key: u64) -> Option<&str> {
+        self.answers.get(&key).map(|(tag, _)| tag.as_str())
+    }
+
+    //the body to handle, none when it is the one already shown: an unchanged answer to the request handled last,
+    //the same answer after another request of the url (another class) is shown again from what was kept
+    pub fn answered(&mut self, url: &str, key: u64, response: &ehttp::Response) -> Option<Vec<u8>> {
+        let already_handled = self.handled.insert(url.to_string(), key) == Some(key);
+        if response.status == NOT_MODIFIED {
+            return self.answers.get(&key).filter(|_| !already_handled).map(|(_, body)| body.clone());
+        }
+        if let Some(tag) = response.headers.get("etag") {
+            if self.answers.len() >= MAX_KEPT {
+                self.answers.clear();
+            }
+            self.answers.insert(key, (tag.to_string(), response.bytes.clone()));
+        }
+        Some(response.bytes.clone())
+    }
+
+    //after a recovery nothing is shown anymore, every answer has to be handled again
+    pub fn clear(&mut self) {
+        self.answers.clear();
+        self.handled.clear();
+    }
+}
@@ -0,0 +1,23 @@
+use common::language::Language;
+
+pub const KEY: &str = "error_language";
+
+//none leaves Accept-Language to the browser, or to the server default on native
+pub fn selector(ui: &mut egui::Ui, selected: &mut Option<Language>) {
+    egui::ComboBox::from_id_salt("error_language")
+        .selected_text(selected.map_or("Langue auto", Language::label))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(selected, None, "Langue auto");
+            for language in Language::ALL {
+                ui.selectable_value(selected, Some(language), language.label());
+            }
+        })
+        .response
+        .on_hover_text("langue des messages d'erreur du serveur");
+}
+
+pub fn apply(request: &mut ehttp::Request, language: Option<Language>) {
+    if let Some(language) = language {
+        request.headers.insert("Accept-Language", language.tag());
+    }
+}
@@ -0,0 +1,115 @@
+use egui::RichText;
+use common::packets::s2c::Leaderboard;
+
+pub const TOP: usize = 20;
+
+#[derive(PartialEq, Clone, Copy)]
+enum Tab {
+    Nicknames,
+    Proposers,
+}
+
+//most voted propositions of every class or of one, and the participants whose propositions gathered the most votes
+pub struct LeaderboardViewer {
+    pub open: bool,
+    only: Option<String>, //class filter, none for every class
+    tab: Tab,
+    leaderboard: Option<Leaderboard>,
+}
+
+impl LeaderboardViewer {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            only: None,
+            tab: Tab::Nicknames,
+            leaderboard: None,
+        }
+    }
+
+    pub fn set_leaderboard(&mut self, leaderboard: Leaderboard) {
+        self.leaderboard = Some(leaderboard);
+    }
+
+    pub fn filter(&self) -> Option<String> {
+        self.only.clone()
+    }
+
+    //returns the filter to ask for again when it changed
+    pub fn display(&mut self, ctx: &egui::Context, classes: &[String]) -> Option<Option<String>> {
+        if !self.open {
+            return None;
+        }
+
+        let mut open = true;
+        let mut changed = false;
+        egui::Window::new("Classement")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(450.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("leaderboard_class")
+                        .selected_text(self.only.as_deref().unwrap_or("toutes les classes"))
+                        .show_ui(ui, |ui| {
+                            changed |= ui.selectable_value(&mut self.only, None, "toutes les classes").changed();
+                            for class in classes {
+                                changed |= ui.selectable_value(&mut self.only, Some(class.clone()), class).changed();
+                            }
+                        });
+                    ui.selectable_value(&mut self.tab, Tab::Nicknames, "Surnoms");
+                    ui.selectable_value(&mut self.tab, Tab::Proposers, "Meilleurs proposeurs");
+                });
+                ui.separator();
+
+                let Some(leaderboard) = self.leaderboard.as_ref().filter(|l| l.only == self.only) else {
+                    ui.spinner();
+                    return;
+                };
+                let every_class = self.only.is_none();
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("leaderboard").striped(true).show(ui, |ui| match self.tab {
+                        Tab::Nicknames => {
+                            if leaderboard.nicknames.is_empty() {
+                                ui.label("aucun surnom à afficher pour l'instant");
+                                return;
+                            }
+                            for (i, entry) in leaderboard.nicknames.iter().enumerate() {
+                                ui.label(format!("{}.", i + 1));
+                                ui.label(RichText::new(&entry.nickname).strong());
+                                ui.label(if every_class { format!("{} ({})", entry.name, entry.class) } else { entry.name.clone() });
+                                match entry.weight {
+                                    Some(weight) => {
+                                        ui.label(RichText::new(format!("{:.1} votes", weight)).color(egui::Color32::from_rgb(100, 100, 255)))
+                                            .on_hover_text(format!("{} votes, les plus anciens comptent moins", entry.votes));
+                                    }
+                                    None => {
+                                        ui.label(RichText::new(format!("{} votes", entry.votes)).color(egui::Color32::from_rgb(100, 100, 255)));
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        }
+                        Tab::Proposers => {
+                            if leaderboard.proposers.is_empty() {
+                                ui.label("aucun proposeur pour l'instant");
+                                return;
+                            }
+                            for (i, entry) in leaderboard.proposers.iter().enumerate() {
+                                ui.label(format!("{}.", i + 1));
+                                ui.label(if every_class { format!("{} ({})", entry.name, entry.class) } else { entry.name.clone() });
+                                ui.label(format!("{} surnoms", entry.propositions));
+                                ui.label(RichText::new(format!("{} votes", entry.votes)).color(egui::Color32::from_rgb(100, 100, 255)));
+                                ui.end_row();
+                            }
+                        }
+                    });
+                });
+            });
+
+        if !open {
+            self.open = false;
+        }
+        changed.then(|| self.only.clone())
+    }
+}
@@ -1,6 +1,24 @@
 mod app;
 mod person_selector;
 mod class_selector;
+mod class_summary;
 mod editor_selector;
+mod credentials;
+mod etags;
+mod avatars;
+mod deep_link;
+mod presentation;
+mod confetti;
+mod onboarding;
+mod update_check;
+mod stats_viewer;
+mod class_board;
+mod leaderboard;
+mod language;
+mod resume;
+mod password_form;
+mod push;
+mod offline;
+pub mod panic_report;
 
 pub use app::HttpApp;
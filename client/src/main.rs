@@ -5,6 +5,7 @@
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    client::panic_report::install();
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -31,6 +32,7 @@ fn main() {
 
     // Redirect `log` message to `console.log` and friends:
     eframe::WebLogger::init(log::LevelFilter::Debug).ok();
+    client::panic_report::install();
 
     let web_options = eframe::WebOptions::default();
 
@@ -51,7 +53,8 @@ fn main() {
             .dyn_into::<web_sys::HtmlCanvasElement>()
             .expect("the_canvas_id was not a HtmlCanvasElement");
 
-        let start_result = eframe::WebRunner::new()
+        let runner = eframe::WebRunner::new();
+        let start_result = runner
             .start(
                 canvas,
                 web_options,
@@ -63,7 +66,8 @@ fn main() {
         if let Some(loading_text) = document.get_element_by_id("loading_text") {
             match start_result {
                 Ok(_) => {
-                    loading_text.remove();
+                    loading_text.set_inner_html(""); //kept for the panic message
+                    watch_for_panic(runner, loading_text);
                 }
                 Err(e) => {
                     loading_text.set_inner_html(
@@ -74,4 +78,25 @@ fn main() {
             }
         }
     });
-}
\ No newline at end of file
+}
+//a panic stops the whole module on the web, the page then offers to load it again,
+//the login and the link of the page shown are kept by eframe storage and the address
+#[cfg(target_arch = "wasm32")]
+fn watch_for_panic(runner: eframe::WebRunner, element: web_sys::Element) {
+    use eframe::wasm_bindgen::closure::Closure;
+    use eframe::wasm_bindgen::JsCast as _;
+
+    let check = Closure::<dyn FnMut()>::new(move || {
+        if let (Some(summary), true) = (runner.panic_summary(), element.inner_html().is_empty()) {
+            let message = summary.message().replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+            element.set_inner_html(&format!(
+                "<p>Une erreur est survenue, elle a été signalée.</p><p><small>{}</small></p><button onclick=\"location.reload()\">Recharger l'état</button>",
+                message,
+            ));
+        }
+    });
+    if let Some(window) = web_sys::window() {
+        let _ = window.set_interval_with_callback_and_timeout_and_arguments_0(check.as_ref().unchecked_ref(), 1000);
+    }
+    check.forget();
+}
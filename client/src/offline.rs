@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use common::packets::c2s::{AddNickname, BatchVotes, DeleteNickname, VoteNickname};
+use common::packets::s2c::{ClassList, PersonProfileResponse};
+
+pub const KEY: &str = "offline_cache";
+const RETRY_SECS: f64 = 10.0; //between two tries to reach the server again
+const MAX_QUEUED: usize = 100; //changes kept while offline, the oldest are dropped past it
+
+//a change the network lost, sent again once the server answers; kept without the password,
+//the one of the login at that time is used, or the change is dropped if another login made it
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub enum Mutation {
+    Vote(VoteNickname),
+    Batch(BatchVotes),
+    Propose(AddNickname),
+    Delete(DeleteNickname),
+}
+
+impl Mutation {
+    pub fn request(&self, url: impl Fn(&str) -> String) -> ehttp::Request {
+        match self {
+            Mutation::Vote(vote) => ehttp::Request::json(url("vote_nickname"), vote),
+            Mutation::Batch(batch) => ehttp::Request::json(url("batch_votes"), batch),
+            Mutation::Propose(add) => ehttp::Request::json(url("add_nickname"), add),
+            Mutation::Delete(delete) => ehttp::Request::json(url("delete_nickname"), delete),
+        }.expect("Failed to create request")
+    }
+
+    //name the change logs in with
+    fn editor(&self) -> &str {
+        match self {
+            Mutation::Vote(v) => &v.voter,
+            Mutation::Batch(b) => &b.voter,
+            Mutation::Propose(a) => &a.editor,
+            Mutation::Delete(d) => &d.editor,
+        }
+    }
+
+    fn password_mut(&mut self) -> &mut String {
+        match self {
+            Mutation::Vote(v) => &mut v.password,
+            Mutation::Batch(b) => &mut b.password,
+            Mutation::Propose(a) => &mut a.password,
+            Mutation::Delete(d) => &mut d.password,
+        }
+    }
+}
+
+//what was shown last, saved with the app so a client started without network still has something to show
+#[derive(Deserialize, Serialize, Default)]
+pub struct Snapshot {
+    class_list: Option<ClassList>,
+    profiles: BTreeMap<String, PersonProfileResponse>, //class -> its participants, the partial answers merged in
+    queue: Vec<Mutation>,
+}
+
+pub enum Connectivity {
+    Unchanged,
+    Retry, //still offline, time to ask the server again
+    Reconnected, //the queue is to be sent and everything shown refreshed
+}
+
+pub struct Offline {
+    reachable: Arc<AtomicBool>, //written by the fetch callbacks, false after a network error, true after any answer
+    online: bool, //as the panels were last told
+    retry_at: f64,
+    shown: Option<String>, //class whose participants are on screen, from the server or from the snapshot
+    snapshot: Snapshot,
+}
+
+impl Offline {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        Self {
+            reachable: Arc::new(AtomicBool::new(true)),
+            online: true,
+            retry_at: 0.0,
+            shown: None,
+            snapshot: storage.and_then(|s| eframe::get_value(s, KEY)).unwrap_or_default(),
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, KEY, &self.snapshot);
+    }
+
+    pub fn reachable(&self) -> Arc<AtomicBool> {
+        self.reachable.clone()
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
+    pub fn queued(&self) -> usize {
+        self.snapshot.queue.len()
+    }
+
+    pub fn update(&mut self, ctx: &egui::Context) -> Connectivity {
+        let now = ctx.input(|i| i.time);
+        match (self.online, self.reachable.load(Ordering::Relaxed)) {
+            (false, true) => {
+                self.online = true;
+                Connectivity::Reconnected
+            }
+            (true, false) => {
+                self.online = false;
+                self.retry_at = now + RETRY_SECS;
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(RETRY_SECS));
+                Connectivity::Unchanged
+            }
+            (false, false) if now >= self.retry_at => {
+                self.retry_at = now + RETRY_SECS;
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(RETRY_SECS));
+                Connectivity::Retry
+            }
+            _ => Connectivity::Unchanged,
+        }
+    }
+
+    pub fn remember_classes(&mut self, class_list: &ClassList) {
+        self.snapshot.class_list = Some(class_list.clone());
+    }
+
+    pub fn remember_profiles(&mut self, class: &str, response: &PersonProfileResponse) {
+        self.shown = Some(class.to_string());
+        let mut response = response.clone();
+        response.error = None;
+        response.request_id = None;
+        response.receipts.clear();
+        match self.snapshot.profiles.get_mut(class) {
+            Some(kept) if response.partial_response => {
+                kept.profiles.extend(response.profiles);
+                kept.display_names.extend(response.display_names);
+                kept.avatars.extend(response.avatars);
+                kept.kinds.extend(response.kinds);
+                kept.board = response.board;
+                kept.prompts = response.prompts;
+            }
+            _ => {
+                response.partial_response = false;
+                self.snapshot.profiles.insert(class.to_string(), response);
+            }
+        }
+    }
+
+    //the class list kept, when the panels have none
+    pub fn classes(&self) -> Option<ClassList> {
+        self.snapshot.class_list.clone()
+    }
+
+    //the participants of class as last seen, once per class shown while offline
+    pub fn profiles(&mut self, class: &str) -> Option<PersonProfileResponse> {
+        if self.shown.as_deref() == Some(class) {
+            return None;
+        }
+        let kept = self.snapshot.profiles.get(class)?.clone();
+        self.shown = Some(class.to_string());
+        Some(kept)
+    }
+
+    pub fn queue(&mut self, mut mutation: Mutation) {
+        mutation.password_mut().clear();
+        if self.snapshot.queue.len() >= MAX_QUEUED {
+            self.snapshot.queue.remove(0);
+        }
+        self.snapshot.queue.push(mutation);
+    }
+
+    //the queued changes made by this login, in their order, with its password back
+    pub fn take_queue(&mut self, editor: &str, password: &str) -> Vec<Mutation> {
+        let (mine, others): (Vec<Mutation>, Vec<Mutation>) = std::mem::take(&mut self.snapshot.queue).into_iter()
+            .partition(|m| m.editor() == editor);
+        if !others.is_empty() {
+            log::warn!("{} changes made by another login dropped", others.len());
+        }
+        mine.into_iter()
+            .map(|mut m| {
+                *m.password_mut() = password.to_string();
+                m
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,120 @@
+use egui::RichText;
+
+pub const COMPLETED_KEY: &str = "onboarding_completed";
+pub const SERVER_KEY: &str = "server";
+
+#[derive(PartialEq, Clone, Copy)]
+enum Step {
+    Server, //native only, the web client talks to the server it was loaded from
+    Login,
+    Voting,
+    Permissions,
+}
+
+//what the wizard collected, applied by the app once the last step is validated
+pub struct Completed {
+    pub server: String,
+    pub name: String,
+    pub password: String,
+}
+
+//shown on the first launch only, the completed flag is kept in the eframe storage
+pub struct Onboarding {
+    step: Step,
+    server: String,
+    name: String,
+    password: String,
+}
+
+impl Onboarding {
+    pub fn new(server: &str) -> Self {
+        Self {
+            step: if cfg!(target_arch = "wasm32") { Step::Login } else { Step::Server },
+            server: server.to_string(),
+            name: String::new(),
+            password: String::new(),
+        }
+    }
+
+    fn next(&self) -> Option<Step> {
+        match self.step {
+            Step::Server => Some(Step::Login),
+            Step::Login => Some(Step::Voting),
+            Step::Voting => Some(Step::Permissions),
+            Step::Permissions => None,
+        }
+    }
+
+    fn previous(&self) -> Option<Step> {
+        match self.step {
+            Step::Server => None,
+            Step::Login if cfg!(target_arch = "wasm32") => None,
+            Step::Login => Some(Step::Server),
+            Step::Voting => Some(Step::Login),
+            Step::Permissions => Some(Step::Voting),
+        }
+    }
+
+    pub fn update(&mut self, ctx: &egui::Context) -> Option<Completed> {
+        let mut completed = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() * 0.15);
+                ui.heading("Bienvenue sur Sweat Voter");
+                ui.add_space(16.0);
+
+                match self.step {
+                    Step::Server => {
+                        ui.label("Adresse du serveur de votre classe");
+                        ui.add(egui::TextEdit::singleline(&mut self.server).hint_text("http://192.168.1.10:8080"));
+                    }
+                    Step::Login => {
+                        ui.label("Connectez-vous avec le nom et le mot de passe donnés par l'organisateur.");
+                        ui.label(RichText::new("vous pouvez aussi passer cette étape et seulement regarder les votes").color(egui::Color32::GRAY));
+                        ui.add(egui::TextEdit::singleline(&mut self.name).hint_text("Nom Prénom").char_limit(30));
+                        ui.add(egui::TextEdit::singleline(&mut self.password).hint_text("Mot de passe").password(true).char_limit(30));
+                    }
+                    Step::Voting => {
+                        ui.label("Choisissez une classe puis un participant pour voir les surnoms proposés.");
+                        ui.label("Vous pouvez proposer de nouveaux surnoms et voter pour votre préféré.");
+                        ui.label("Un seul vote par participant : voter pour un autre surnom déplace votre vote.");
+                    }
+                    Step::Permissions => {
+                        ui.label("Ce que vous pouvez faire :");
+                        ui.label("• proposer un surnom pour n'importe quel participant de votre classe");
+                        ui.label("• voter ou changer votre vote tant que le surnom n'est pas gelé");
+                        ui.label("• supprimer les surnoms proposés pour vous, s'ils ne sont pas protégés");
+                        ui.label("• transférer un surnom que vous avez proposé à un autre participant");
+                    }
+                }
+
+                ui.add_space(16.0);
+                ui.horizontal(|ui| {
+                    if let Some(previous) = self.previous() {
+                        if ui.button("Précédent").clicked() {
+                            self.step = previous;
+                        }
+                    }
+                    let ready = self.step != Step::Server || !self.server.trim().is_empty();
+                    match self.next() {
+                        Some(next) => {
+                            if ui.add_enabled(ready, egui::Button::new("Suivant")).clicked() {
+                                self.step = next;
+                            }
+                        }
+                        None => {
+                            if ui.button("Commencer").clicked() {
+                                completed = Some(Completed {
+                                    server: self.server.trim().to_string(),
+                                    name: std::mem::take(&mut self.name),
+                                    password: std::mem::take(&mut self.password),
+                                });
+                            }
+                        }
+                    }
+                });
+            });
+        });
+        completed
+    }
+}
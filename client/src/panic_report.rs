@@ -0,0 +1,49 @@
+use std::sync::Mutex;
+use common::packets::c2s::ClientError;
+use common::version::BuildInfo;
+
+//the last panic, the app reports it on native where it catches them, see HttpApp::update
+static LAST: Mutex<Option<ClientError>> = Mutex::new(None);
+
+//records the panics after the usual hook, on the web they are sent to the server's /client_error right away
+//since the app can't catch them there
+pub fn install() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let message = info.payload().downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic".to_string());
+        let error = ClientError {
+            message,
+            location: info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            build: BuildInfo::current().to_string(),
+            page: page(),
+        };
+        #[cfg(target_arch = "wasm32")]
+        send("client_error", &error);
+        if let Ok(mut last) = LAST.lock() {
+            *last = Some(error);
+        }
+    }));
+}
+
+pub fn take_last() -> Option<ClientError> {
+    LAST.lock().ok().and_then(|mut last| last.take())
+}
+
+pub fn send(url: &str, error: &ClientError) {
+    if let Ok(request) = ehttp::Request::json(url, error) {
+        ehttp::fetch(request, |_| {});
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn page() -> String {
+    web_sys::window().and_then(|w| w.location().href().ok()).unwrap_or_default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn page() -> String {
+    "native".to_string()
+}
@@ -0,0 +1,77 @@
+use egui::{Color32, RichText};
+
+//asks for a new password, from "Mon compte" or forced when the admin flagged the login
+pub struct PasswordForm {
+    pub open: bool,
+    pub forced: bool, //can't be closed, the server asked for it at login
+    new_password: String,
+    confirmation: String,
+    error: Option<String>,
+    sent: Option<String>, //waiting for the answer of the server, taken as the password once accepted
+}
+
+impl PasswordForm {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            forced: false,
+            new_password: String::new(),
+            confirmation: String::new(),
+            error: None,
+            sent: None,
+        }
+    }
+
+    pub fn force(&mut self) {
+        self.open = true;
+        self.forced = true;
+    }
+
+    //the new password once the server took it, the error stays shown otherwise
+    pub fn answered(&mut self, error: Option<String>) -> Option<String> {
+        let sent = self.sent.take()?;
+        self.error = error;
+        if self.error.is_some() {
+            return None;
+        }
+        *self = Self::new();
+        Some(sent)
+    }
+
+    //the new password to send, once typed twice the same
+    pub fn display(&mut self, ctx: &egui::Context) -> Option<String> {
+        if !self.open {
+            return None;
+        }
+
+        let mut open = true;
+        let mut send = None;
+        let window = egui::Window::new("Changer le mot de passe").collapsible(false).resizable(false);
+        let window = if self.forced { window.anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0]) } else { window.open(&mut open) };
+        window.show(ctx, |ui| {
+            if self.forced {
+                ui.label("Un nouveau mot de passe est demandé pour ce compte, choisissez-en un pour continuer.");
+            }
+            ui.add(egui::TextEdit::singleline(&mut self.new_password).password(true).hint_text("Nouveau mot de passe").char_limit(30));
+            ui.add(egui::TextEdit::singleline(&mut self.confirmation).password(true).hint_text("Confirmation").char_limit(30));
+            let same = self.new_password == self.confirmation;
+            if !same && !self.confirmation.is_empty() {
+                ui.label(RichText::new("les deux mots de passe sont différents").color(Color32::from_rgb(255, 100, 100)));
+            }
+            if let Some(error) = &self.error {
+                ui.label(RichText::new(error).color(Color32::from_rgb(255, 100, 100)));
+            }
+            if self.sent.is_some() {
+                ui.spinner();
+            } else if ui.add_enabled(same && !self.new_password.is_empty(), egui::Button::new("Changer")).clicked() {
+                self.sent = Some(self.new_password.clone());
+                send = self.sent.clone();
+            }
+        });
+
+        if !open {
+            *self = Self::new();
+        }
+        send
+    }
+}
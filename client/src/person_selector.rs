@@ -1,24 +1,102 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
 
 use egui::RichText;
-use common::packets::c2s::{AddNickname, DeleteNickname, VoteNickname};
-use common::packets::s2c::{PersonProfileResponse, VoteCount};
+use common::{author, is_anonymous, NicknameEventKind, Overflow, ProfilKind, PropositionCap, Protection, VoteMode};
+use common::collation::Collation;
+use common::packets::c2s::{AddComment, AddNickname, AskForNicknameHistory, AskForSuggestions, BatchVotes, DeleteComment, DeleteNickname, TransferNickname, VoteNickname, VoteOperation};
+use common::packets::s2c::{NicknameHistory, PersonProfileResponse, ProfilHistory, Suggestions, VoteCount, VoteReceipt, VoteSummary};
+use common::time::format_unix_time;
+use crate::avatars::Avatars;
+
+const PROMPT_SECS: f64 = 8.0; //time each question stays above the proposal field
+const AVATAR_SIDE: f32 = 18.0; //next to the names, about the height of a line
 
 pub struct PersonSelector {
     pub persons: BTreeMap<String, BTreeMap<String, VoteCount>>,
     pub selected: String,
     pub new_nickname: String,
     pub allow_to_modify: bool,
+    counts_hidden: bool, //guest access of the server without the vote counts
+    pub voted: BTreeSet<String>, //cached from /my_vote_summary, kept up to date by the profile responses
+    pub history: Option<ProfilHistory>, //nicknames of the selected person in the classes of other years
+    pub show_history: bool,
+    pub nickname_history: Option<NicknameHistory>, //timeline popup of a single proposition
+    transfer_to: String, //profil picked in the popup to hand the proposition over to
+    pub batch_mode: bool, //votes are kept here until "Appliquer", handy on slow connections
+    pending_votes: BTreeMap<String, Option<String>>, //name -> nickname to vote for, none to remove the vote
+    pub error: Option<String>, //why the server refused the last modification
+    loaded: bool, //a whole class was received, an empty list is then really empty
+    focus_new_nickname: bool,
+    suggestions: Option<Suggestions>, //existing spellings close to new_nickname
+    collation: Collation,
+    order: Vec<String>, //names of persons, sorted with the collation on the name shown
+    display_names: BTreeMap<String, String>, //profil name -> name chosen by the participant
+    prompts: Vec<String>, //questions of the class, one at a time above the proposal field
+    vote_modes: BTreeMap<String, VoteMode>, //classes not voting in single mode, from the class list
+    new_comments: BTreeMap<String, String>, //nickname -> comment being typed under it
+    receipts: BTreeMap<String, VoteReceipt>, //name -> what the server recorded at the last vote for them
+    only_mine: bool, //"mes votes", hides the propositions the editor doesn't support
+    proposition_cap: PropositionCap, //from the class list
+    kinds: BTreeMap<String, ProfilKind>, //profil name -> kind, the teachers and staff are left out of the list
+    avatar_hashes: BTreeMap<String, String>, //profil name -> hash of their avatar
+    pub avatars: Avatars,
+}
+
+
+fn give(vote: &mut VoteCount) {
+    if !vote.contain_you {
+        vote.contain_you = true;
+        vote.count += 1;
+        if let Some(weight) = &mut vote.weight {
+            *weight += 1.0; //a fresh vote counts fully
+        }
+    }
 }
 
+fn take_back(vote: &mut VoteCount) {
+    if vote.contain_you {
+        vote.contain_you = false;
+        vote.count = vote.count.saturating_sub(1);
+        vote.your_rank = None;
+        if let Some(weight) = &mut vote.weight {
+            *weight = (*weight - 1.0).max(0.0); //at most, the server tells how much it weighed with its answer
+        }
+    }
+}
+
+fn author_label(author: &str) -> &str {
+    if is_anonymous(author) { "anonyme" } else { author }
+}
+
+fn protection_label(level: Protection) -> &'static str {
+    match level {
+        Protection::Open => "ouvert",
+        Protection::VotesLocked => "protégé",
+        Protection::Locked => "gelé",
+    }
+}
 
 pub enum Action {
     Propose(AddNickname),
     Vote(VoteNickname),
+    ApplyVotes(BatchVotes),
     Delete(DeleteNickname),
+    History(AskForNicknameHistory),
+    Suggest(AskForSuggestions),
+    Comment(AddComment),
+    DeleteComment(DeleteComment),
     None,
 }
 
+//the id of the refused request goes with the message, to give when reporting it
+pub fn with_reference(error: String, request_id: Option<String>) -> String {
+    match request_id {
+        Some(id) => format!("{} (réf. {})", error, id),
+        None => error,
+    }
+}
+
 impl PersonSelector {
     pub fn new() -> Self {
         Self {
@@ -26,6 +104,30 @@ impl PersonSelector {
             selected: String::new(),
             new_nickname: String::new(),
             allow_to_modify: false,
+            counts_hidden: false,
+            voted: BTreeSet::new(),
+            history: None,
+            show_history: false,
+            nickname_history: None,
+            transfer_to: String::new(),
+            batch_mode: false,
+            pending_votes: BTreeMap::new(),
+            error: None,
+            loaded: false,
+            focus_new_nickname: false,
+            suggestions: None,
+            collation: Collation::default(),
+            order: Vec::new(),
+            display_names: BTreeMap::new(),
+            prompts: Vec::new(),
+            vote_modes: BTreeMap::new(),
+            new_comments: BTreeMap::new(),
+            receipts: BTreeMap::new(),
+            only_mine: false,
+            proposition_cap: PropositionCap::default(),
+            kinds: BTreeMap::new(),
+            avatar_hashes: BTreeMap::new(),
+            avatars: Avatars::default(),
         }
     }
 
@@ -33,18 +135,247 @@ impl PersonSelector {
         self.persons.is_empty()
     }
 
-    pub fn set_persons(&mut self, person_profile_response: PersonProfileResponse) {
+    pub fn set_persons(&mut self, mut person_profile_response: PersonProfileResponse) {
+        self.error = person_profile_response.error.take().map(|e| with_reference(e, person_profile_response.request_id.take()));
+        for receipt in std::mem::take(&mut person_profile_response.receipts) {
+            self.receipts.insert(receipt.name.clone(), receipt);
+        }
         match person_profile_response {
-            PersonProfileResponse { allowed_to_modify, profiles, partial_response: true,  } => { //the server only updated some participants
+            PersonProfileResponse { allowed_to_modify, profiles, partial_response: true, counts_hidden, display_names, prompts, kinds, avatars, .. } => { //the server only updated some participants
+                for (name, nicknames) in &profiles {
+                    if nicknames.values().any(|v| v.contain_you) {
+                        self.voted.insert(name.clone());
+                    } else {
+                        self.voted.remove(name);
+                    }
+                }
                 self.persons.extend(profiles);
+                self.display_names.extend(display_names);
+                self.kinds.extend(kinds);
+                self.avatar_hashes.extend(avatars);
+                self.prompts = prompts;
                 self.allow_to_modify = allowed_to_modify;
+                self.counts_hidden = counts_hidden;
             }
-            PersonProfileResponse { allowed_to_modify, profiles, .. } => { // the server sent the whole list in one go
+            PersonProfileResponse { allowed_to_modify, profiles, counts_hidden, display_names, prompts, kinds, avatars, .. } => { // the server sent the whole list in one go
                 self.persons = profiles; // we replace the whole list, and **do not** keep the old values
+                self.display_names = display_names;
+                self.kinds = kinds;
+                self.avatar_hashes = avatars;
+                self.prompts = prompts;
                 self.allow_to_modify = allowed_to_modify;
+                self.counts_hidden = counts_hidden;
+                self.pending_votes.clear(); //another class or editor, the pending votes were not theirs
+                self.receipts.clear();
+                self.loaded = true;
+            }
+        }
+
+        if !self.allow_to_modify {
+            self.voted.clear();
+        }
+        self.update_order();
+    }
+
+    pub fn set_vote_modes(&mut self, vote_modes: BTreeMap<String, VoteMode>) {
+        self.vote_modes = vote_modes;
+    }
+
+    pub fn set_proposition_cap(&mut self, cap: PropositionCap) {
+        self.proposition_cap = cap;
+    }
+
+    pub fn set_locale(&mut self, locale: &str) {
+        if !locale.is_empty() {
+            self.collation = Collation::new(locale);
+            self.update_order();
+        }
+    }
+
+    //only the students are proposed for, the others log in to vote or read
+    fn update_order(&mut self) {
+        let mut order: Vec<String> = self.persons.keys()
+            .filter(|name| self.kinds.get(*name).copied().unwrap_or_default().is_target())
+            .cloned()
+            .collect();
+        order.sort_by(|a, b| self.collation.compare(self.shown_name(a), self.shown_name(b)));
+        self.order = order;
+    }
+
+    //shows a vote before the server answers, its answer replaces the counts anyway; the ranks are left to it
+    pub fn apply_vote(&mut self, vote: &VoteNickname, mode: VoteMode) {
+        let Some(name) = vote.target().profil().map(str::to_string) else {
+            return; //the class board has its own counts
+        };
+        let Some(nicknames) = self.persons.get_mut(&name) else {
+            return;
+        };
+        for (nickname, count) in nicknames.iter_mut() {
+            if vote.withdraw && *nickname == vote.nickname || !vote.withdraw && mode.is_single() && *nickname != vote.nickname {
+                take_back(count);
+            } else if !vote.withdraw && *nickname == vote.nickname {
+                give(count);
             }
         }
+        self.update_voted(&name);
+    }
+
+    //the same for the votes applied together, each one replaces the vote for its participant
+    pub fn apply_batch(&mut self, batch: &BatchVotes) {
+        for operation in &batch.operations {
+            let Some(nicknames) = self.persons.get_mut(&operation.name) else {
+                continue;
+            };
+            for (nickname, count) in nicknames.iter_mut() {
+                if operation.nickname.as_ref() == Some(nickname) {
+                    give(count);
+                } else {
+                    take_back(count);
+                }
+            }
+            self.update_voted(&operation.name);
+        }
+    }
+
+    fn update_voted(&mut self, name: &str) {
+        if self.persons.get(name).is_some_and(|nicknames| nicknames.values().any(|v| v.contain_you)) {
+            self.voted.insert(name.to_string());
+        } else {
+            self.voted.remove(name);
+        }
+    }
+
+    //the avatars to fetch after a response
+    pub fn missing_avatars(&mut self) -> Vec<String> {
+        self.avatars.missing(self.avatar_hashes.values())
+    }
+
+    pub fn vote_mode(&self, class: &str) -> VoteMode {
+        self.vote_modes.get(class).copied().unwrap_or_default()
+    }
+
+    //the profil name stays the key everywhere, only what is drawn changes
+    pub fn can_modify(&self) -> bool {
+        self.allow_to_modify
+    }
+
+    pub fn shown_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.display_names.get(name).map_or(name, String::as_str)
+    }
+
+    //persons in display order
+    pub fn ordered(&self) -> impl Iterator<Item = (&String, &BTreeMap<String, VoteCount>)> {
+        self.order.iter().filter_map(|name| self.persons.get_key_value(name))
+    }
+
+    pub fn set_vote_summary(&mut self, vote_summary: VoteSummary) {
+        self.voted = vote_summary.voted;
+    }
+
+    pub fn set_history(&mut self, history: ProfilHistory) {
+        if history.name == self.selected {
+            self.history = Some(history);
+        }
+    }
+
+    fn display_history(&self, ui: &mut egui::Ui) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        egui::ScrollArea::both().show(ui, |ui| {
+            for entry in &history.entries {
+                ui.heading(format!("{} ({})", entry.name, entry.class));
+                egui::Grid::new(("history", &entry.class)).striped(true).show(ui, |ui| {
+                    for (nickname, count) in &entry.nicknames {
+                        ui.label(nickname);
+                        ui.label(if history.counts_hidden { "–".to_string() } else { count.to_string() });
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(8.0);
+            }
+        });
+    }
+
+    pub fn set_suggestions(&mut self, suggestions: Suggestions) {
+        self.suggestions = Some(suggestions);
+    }
+
+    pub fn set_nickname_history(&mut self, mut history: NicknameHistory) {
+        history.error = history.error.take().map(|e| with_reference(e, history.request_id.take()));
+        self.nickname_history = Some(history);
+    }
+
+    //the author of the proposition can also hand it over to someone else from here
+    pub fn display_nickname_history(&mut self, ctx: &egui::Context, class: Option<&str>, editor_name: &str, password: &str) -> Option<TransferNickname> {
+        let history = self.nickname_history.as_ref()?;
+        let author = author(&history.events);
+        let can_transfer = self.allow_to_modify && author == Some(editor_name);
+
+        let mut transfer = None;
+        let mut open = true;
+        egui::Window::new(format!("Historique de \"{}\"", history.nickname))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!("surnom de {}", history.name));
+                if let Some(author) = author {
+                    ui.label(format!("auteur : {}", author_label(author)));
+                }
+                if history.events.is_empty() {
+                    ui.label("aucun évènement enregistré");
+                }
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    egui::Grid::new("nickname_history").striped(true).show(ui, |ui| {
+                        for event in &history.events {
+                            ui.label(format!("{} UTC", format_unix_time(event.time)));
+                            ui.label(match &event.kind {
+                                NicknameEventKind::Created { by } => format!("proposé par {}", author_label(by)),
+                                NicknameEventKind::Frozen { by } => format!("gelé par {}", by),
+                                NicknameEventKind::Unfrozen { by } => format!("dégelé par {}", by),
+                                NicknameEventKind::Protection { by, level } => format!("{} par {}", protection_label(*level), by),
+                                NicknameEventKind::Transferred { by, to } => format!("transféré à {} par {}", author_label(to), author_label(by)),
+                                NicknameEventKind::VoteCount { count } => format!("{} votes", count),
+                                NicknameEventKind::Merged { by, from } => format!("\"{}\" fusionné ici par {}", from, by),
+                                NicknameEventKind::Deleted { by } => format!("supprimé par {}", author_label(by)),
+                                NicknameEventKind::Restored { by } => format!("restauré par {}", by),
+                            });
+                            ui.end_row();
+                        }
+                    });
+                });
 
+                if let (true, Some(class)) = (can_transfer, class) {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("transfer_to")
+                            .selected_text(self.transfer_to.as_str())
+                            .show_ui(ui, |ui| {
+                                for name in self.order.iter().filter(|n| n.as_str() != editor_name) {
+                                    ui.selectable_value(&mut self.transfer_to, name.clone(), self.display_names.get(name).unwrap_or(name).as_str());
+                                }
+                            });
+                        if ui.add_enabled(!self.transfer_to.is_empty(), egui::Button::new("Transférer")).clicked() {
+                            transfer = Some(TransferNickname {
+                                class: class.to_string(),
+                                editor: editor_name.to_string(),
+                                password: password.to_string(),
+                                name: history.name.clone(),
+                                nickname: history.nickname.clone(),
+                                to: std::mem::take(&mut self.transfer_to),
+                            });
+                        }
+                    });
+                }
+                if let Some(error) = &history.error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                }
+            });
+
+        if !open {
+            self.nickname_history = None;
+        }
+        transfer
     }
 
     pub fn display_name_selector(&mut self, ui: &mut egui::Ui) -> Vec<String> {
@@ -53,11 +384,25 @@ impl PersonSelector {
         egui::SidePanel::left("left_panel").resizable(true).show_inside(ui, |ui| {
             egui::ScrollArea::vertical().show(ui, |ui| {
                 ui.heading("Participants");
+                if self.loaded && self.order.is_empty() {
+                    ui.label("Aucun participant dans cette classe pour l'instant.");
+                    return;
+                }
                 ui.label("choisissez un participant pour voir les surnoms");
-                for name in self.persons.keys() {
-                    if ui.selectable_value(&mut self.selected, name.clone(), name.as_str()).changed() { //really consider switching all theses for cow
-                        profile_requested.push(name.clone());
-                    }
+                for name in &self.order {
+                    ui.horizontal(|ui| {
+                        if let Some(texture) = self.avatar_hashes.get(name).and_then(|hash| self.avatars.texture(hash)) {
+                            ui.add(egui::Image::new(texture).fit_to_exact_size(egui::vec2(AVATAR_SIDE, AVATAR_SIDE)).rounding(AVATAR_SIDE / 2.0));
+                        }
+                        let shown = self.display_names.get(name).unwrap_or(name).as_str();
+                        if ui.selectable_value(&mut self.selected, name.clone(), shown).changed() { //really consider switching all theses for cow
+                            profile_requested.push(name.clone());
+                        }
+                        if self.allow_to_modify && !self.voted.contains(name) {
+                            ui.label(RichText::new("•").color(egui::Color32::from_rgb(255, 180, 0)))
+                                .on_hover_text("vous n'avez pas encore voté pour cette personne");
+                        }
+                    });
                 }
             });
         });
@@ -67,15 +412,72 @@ impl PersonSelector {
     pub fn update_nickname_selector(&mut self, ui: &mut egui::Ui, class: Option<&str>, editor_name: &str, password: &str) -> Action {
         let mut action = Action::None;
         if let (Some(class), Some(nicknames)) = (class, self.persons.get(&self.selected)) {
+            let mode = self.vote_modes.get(class).copied().unwrap_or_default();
+
+            let has_history = self.history.as_ref().is_some_and(|h| h.name == self.selected && !h.entries.is_empty());
+            if has_history {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.show_history, false, "Surnoms");
+                    ui.selectable_value(&mut self.show_history, true, "Historique");
+                });
+                if self.show_history {
+                    self.display_history(ui);
+                    return action;
+                }
+            }
+
+            if self.allow_to_modify {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.only_mine, "mes votes")
+                        .on_hover_text("n'afficher que les surnoms pour lesquels vous avez voté");
+                    ui.checkbox(&mut self.batch_mode, "Votes groupés");
+                    if !self.pending_votes.is_empty() {
+                        if ui.button(format!("Appliquer mes votes ({})", self.pending_votes.len())).clicked() {
+                            action = Action::ApplyVotes(BatchVotes {
+                                class: class.to_string(),
+                                voter: editor_name.to_string(),
+                                password: password.to_string(),
+                                operations: std::mem::take(&mut self.pending_votes).into_iter()
+                                    .map(|(name, nickname)| VoteOperation { name, nickname })
+                                    .collect(),
+                            });
+                        }
+                        if ui.button("Annuler").clicked() {
+                            self.pending_votes.clear();
+                        }
+                    }
+                });
+            }
 
             egui::ScrollArea::both().show(ui, |ui| {
+                if nicknames.is_empty() {
+                    ui.label(format!("Aucun surnom pour {} — soyez le premier à proposer !", self.selected));
+                    if !self.allow_to_modify {
+                        ui.label(RichText::new("connectez-vous avec votre nom et mot de passe pour proposer un surnom").color(egui::Color32::GRAY));
+                    } else if ui.button("Proposer un surnom").clicked() {
+                        self.focus_new_nickname = true;
+                    }
+                    ui.add_space(8.0);
+                }
+
                 egui::Grid::new("nicknames").striped(true).show(ui, |ui| {
                     ui.heading("Surnoms");
                     ui.heading("Votes");
                     ui.end_row();
 
-                    for (nickname, vote) in nicknames.iter() {
-                        ui.label(nickname);
+                    let only_mine = self.only_mine && self.allow_to_modify;
+                    let mut sorted: Vec<(&String, &VoteCount)> = nicknames.iter().filter(|(_, v)| !only_mine || v.contain_you).collect();
+                    sorted.sort_by(|(a, _), (b, _)| self.collation.compare(a, b));
+                    for (nickname, vote) in sorted {
+                        if ui.add(egui::Label::new(nickname).sense(egui::Sense::click())).on_hover_text("cliquer pour voir l'historique").clicked() {
+                            action = Action::History(AskForNicknameHistory {
+                                class: class.to_string(),
+                                name: self.selected.clone(),
+                                nickname: nickname.clone(),
+                                editor: editor_name.to_string(),
+                                password: password.to_string(),
+                            });
+                        }
 
                         let color = if vote.contain_you {
                             egui::Color32::from_rgb(255, 100, 100)
@@ -83,45 +485,215 @@ impl PersonSelector {
                             egui::Color32::from_rgb(100, 100, 255)
                         };
 
-                        ui.label(RichText::new(vote.count.to_string())
-                            .color(color));
+                        match vote.weight.filter(|_| !self.counts_hidden) {
+                            Some(weight) => {
+                                ui.label(RichText::new(format!("{:.1}", weight)).color(color))
+                                    .on_hover_text(format!("{} votes, les plus anciens comptent moins", vote.count));
+                            }
+                            None => {
+                                let count = if self.counts_hidden { "–".to_string() } else { vote.count.to_string() };
+                                ui.label(RichText::new(count)
+                                    .color(color));
+                            }
+                        }
+
+                        if !vote.protection.can_vote() {
+                            ui.label(RichText::new("gelé").color(egui::Color32::GRAY))
+                                .on_hover_text("ce surnom est en cours de vérification");
+                            ui.end_row();
+                            continue;
+                        }
 
-                        if self.allow_to_modify
+                        let pending = self.pending_votes.get(&self.selected);
+                        if pending == Some(&Some(nickname.clone())) {
+                            ui.label(RichText::new("en attente").color(egui::Color32::GRAY));
+                        } else if self.batch_mode && vote.contain_you && pending.is_none() {
+                            if ui.button("Retirer").clicked() {
+                                self.pending_votes.insert(self.selected.clone(), None);
+                            }
+                        } else if !mode.is_single() && !self.batch_mode && vote.contain_you && self.allow_to_modify {
+                            ui.horizontal(|ui| {
+                                let vote_for = |withdraw, rank| Action::Vote(VoteNickname {
+                                    class: class.to_string(),
+                                    name: self.selected.clone(),
+                                    nickname: nickname.clone(),
+                                    voter: editor_name.to_string(),
+                                    password: password.to_string(),
+                                    voter_class: None,
+                                    withdraw,
+                                    rank,
+                                    target: None,
+                                });
+                                if let Some(rank) = vote.your_rank {
+                                    ui.label(format!("choix n°{}", rank));
+                                    if rank > 1 && ui.button("⬆").on_hover_text("préférer ce surnom au précédent").clicked() {
+                                        action = vote_for(false, Some(rank - 1));
+                                    }
+                                }
+                                if ui.button("Retirer").clicked() {
+                                    action = vote_for(true, None);
+                                }
+                            });
+                        } else if self.allow_to_modify
                             && self.persons.contains_key(editor_name)
                             && ui.button("Voter").clicked() { //lazy evaluation hide the button if your not in the list
-                            action = Action::Vote(VoteNickname {
-                                class: class.to_string(),
-                                name: self.selected.clone(),
-                                nickname: nickname.clone(),
-                                voter: editor_name.to_string(),
-                                password: password.to_string(),
-                            });
+                            if self.batch_mode {
+                                self.pending_votes.insert(self.selected.clone(), Some(nickname.clone()));
+                            } else {
+                                action = Action::Vote(VoteNickname {
+                                    class: class.to_string(),
+                                    name: self.selected.clone(),
+                                    nickname: nickname.clone(),
+                                    voter: editor_name.to_string(),
+                                    password: password.to_string(),
+                                    voter_class: None,
+                                    withdraw: false,
+                                    rank: None,
+                                    target: None,
+                                });
+                            }
                         }
 
-                        if self.allow_to_modify && editor_name == self.selected && ui.button("Supprimer").clicked() {
+                        if !vote.protection.can_delete() {
+                            ui.label(RichText::new("protégé").color(egui::Color32::GRAY))
+                                .on_hover_text("ce surnom ne peut plus être supprimé");
+                        } else if self.allow_to_modify && editor_name == self.selected && ui.button("Supprimer").clicked() {
                             action = Action::Delete(DeleteNickname {
                                 class: class.to_string(),
                                 editor: editor_name.to_string(),
                                 nickname: nickname.clone(),
                                 password: password.to_string(),
+                                target: None,
                             });
                         }
+
+                        if self.allow_to_modify || !vote.comments.is_empty() {
+                            egui::CollapsingHeader::new(format!("💬 {}", vote.comments.len()))
+                                .id_salt(("comments", &self.selected, nickname))
+                                .show(ui, |ui| {
+                                    for comment in &vote.comments {
+                                        let author = if is_anonymous(&comment.author) {
+                                            "anonyme"
+                                        } else {
+                                            self.display_names.get(&comment.author).unwrap_or(&comment.author)
+                                        };
+                                        ui.horizontal_wrapped(|ui| {
+                                            ui.label(RichText::new(author).strong());
+                                            ui.label(RichText::new(format_unix_time(comment.time)).small().color(egui::Color32::GRAY));
+                                            if comment.yours && ui.small_button("✖").on_hover_text("supprimer ce commentaire").clicked() {
+                                                action = Action::DeleteComment(DeleteComment {
+                                                    class: class.to_string(),
+                                                    editor: editor_name.to_string(),
+                                                    password: password.to_string(),
+                                                    name: self.selected.clone(),
+                                                    nickname: nickname.clone(),
+                                                    id: comment.id.clone(),
+                                                });
+                                            }
+                                        });
+                                        ui.label(&comment.text);
+                                    }
+                                    if self.allow_to_modify {
+                                        let draft = self.new_comments.entry(nickname.clone()).or_default();
+                                        ui.add(egui::TextEdit::multiline(draft).hint_text("pourquoi ce surnom lui va").desired_rows(2).char_limit(280));
+                                        if ui.button("Commenter").clicked() && !draft.trim().is_empty() {
+                                            action = Action::Comment(AddComment {
+                                                class: class.to_string(),
+                                                editor: editor_name.to_string(),
+                                                password: password.to_string(),
+                                                name: self.selected.clone(),
+                                                nickname: nickname.clone(),
+                                                text: std::mem::take(draft),
+                                            });
+                                        }
+                                    }
+                                });
+                        }
                         ui.end_row();
                     }
                 });
 
+                if let Some(receipt) = self.receipts.get(&self.selected).filter(|_| self.allow_to_modify) {
+                    let votes = if receipt.nicknames.is_empty() { "aucun".to_string() } else { receipt.nicknames.join(", ") };
+                    ui.label(RichText::new(format!("vos votes pour {} enregistrés le {} UTC : {}", self.display_names.get(&self.selected).unwrap_or(&self.selected), format_unix_time(receipt.time), votes))
+                        .small().color(egui::Color32::GRAY));
+                }
+
                 if self.allow_to_modify {
-                    ui.add(egui::TextEdit::singleline(&mut self.new_nickname).hint_text(format!("nouveau surnom pour {}", self.selected)).char_limit(30));
-                    if ui.button("Proposer").clicked() {
+                    if !self.prompts.is_empty() {
+                        let time = ui.input(|i| i.time);
+                        let prompt = &self.prompts[(time / PROMPT_SECS) as usize % self.prompts.len()];
+                        ui.label(RichText::new(prompt).italics().color(egui::Color32::GRAY));
+                        ui.ctx().request_repaint_after(Duration::from_secs_f64(PROMPT_SECS - time % PROMPT_SECS));
+                    }
+                    let full = self.proposition_cap.is_reached(nicknames.len());
+                    let blocked = full && self.proposition_cap.overflow == Overflow::Reject;
+                    if blocked {
+                        ui.label(RichText::new(format!("{} surnoms au maximum par personne, votez plutôt pour l'un d'eux", self.proposition_cap.max))
+                            .color(egui::Color32::from_rgb(255, 100, 100)));
+                    } else if full {
+                        ui.label(RichText::new("limite de surnoms atteinte, un nouveau remplacera le moins voté").color(egui::Color32::GRAY));
+                    }
+                    let input = ui.add(egui::TextEdit::singleline(&mut self.new_nickname).hint_text(format!("nouveau surnom pour {}", self.selected)).char_limit(30));
+                    if std::mem::take(&mut self.focus_new_nickname) {
+                        input.request_focus();
+                    }
+                    if input.changed() {
+                        action = Action::Suggest(AskForSuggestions {
+                            class: class.to_string(),
+                            name: self.selected.clone(),
+                            text: self.new_nickname.clone(),
+                            editor: editor_name.to_string(),
+                            password: password.to_string(),
+                        });
+                    }
+
+                    //only the answer to what is typed right now, older ones may come back late
+                    let suggestions = self.suggestions.as_ref()
+                        .filter(|s| s.name == self.selected && s.text == self.new_nickname && !s.nicknames.is_empty());
+                    if let Some(suggestions) = suggestions {
+                        let can_vote = self.persons.contains_key(editor_name);
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label("déjà proposé :");
+                            for suggestion in &suggestions.nicknames {
+                                let votable = can_vote && nicknames.get(suggestion).is_some_and(|v| v.protection.can_vote());
+                                if !votable {
+                                    ui.label(suggestion);
+                                } else if ui.button(suggestion).on_hover_text("voter pour ce surnom plutôt que d'en proposer un nouveau").clicked() {
+                                    if self.batch_mode {
+                                        self.pending_votes.insert(self.selected.clone(), Some(suggestion.clone()));
+                                    } else {
+                                        action = Action::Vote(VoteNickname {
+                                            class: class.to_string(),
+                                            name: self.selected.clone(),
+                                            nickname: suggestion.clone(),
+                                            voter: editor_name.to_string(),
+                                            password: password.to_string(),
+                                            voter_class: None,
+                                            withdraw: false,
+                                            rank: None,
+                                            target: None,
+                                        });
+                                    }
+                                    self.new_nickname.clear();
+                                }
+                            }
+                        });
+                    }
+                    if ui.add_enabled(!blocked, egui::Button::new("Proposer")).clicked() {
                         action = Action::Propose(AddNickname {
                             class: class.to_string(),
                             editor: editor_name.to_string(),
                             password: password.to_string(),
                             name: self.selected.clone(),
                             nickname: self.new_nickname.clone(),
+                            target: None,
                         });
                         self.new_nickname.clear();
                     }
+                    if let Some(error) = &self.error {
+                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                    }
                 }
             });
         }
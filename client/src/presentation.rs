@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use egui::{Color32, RichText};
+use common::packets::s2c::VoteCount;
+
+const SLIDE_DURATION: f64 = 8.0; //seconds spent on each participant
+const REVEAL_DELAY: f64 = 1.5; //the name shows up alone first, then the nickname is revealed
+const REVEAL_DURATION: f64 = 1.0;
+
+pub struct Presentation {
+    pub active: bool,
+    index: usize,
+    slide_start: f64,
+    paused: bool,
+}
+
+impl Presentation {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            index: 0,
+            slide_start: 0.0,
+            paused: false,
+        }
+    }
+
+    pub fn start(&mut self, ctx: &egui::Context) {
+        self.active = true;
+        self.index = 0;
+        self.paused = false;
+        self.slide_start = ctx.input(|i| i.time);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+    }
+
+    pub fn stop(&mut self, ctx: &egui::Context) {
+        self.active = false;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+    }
+
+    fn go_to(&mut self, index: usize, now: f64) {
+        self.index = index;
+        self.slide_start = now;
+    }
+
+    fn winner(nicknames: &BTreeMap<String, VoteCount>) -> Option<(&String, &VoteCount)> {
+        nicknames.iter()
+            .filter(|(_, v)| v.count > 0)
+            .max_by(|(_, a), (_, b)| a.score().total_cmp(&b.score()))
+    }
+
+    pub fn update(&mut self, ctx: &egui::Context, persons: &[(&String, &BTreeMap<String, VoteCount>)]) {
+        let now = ctx.input(|i| i.time);
+        let count = persons.len();
+
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.stop(ctx);
+            return;
+        }
+        if count > 0 {
+            if ctx.input(|i| i.key_pressed(egui::Key::ArrowRight)) {
+                self.go_to((self.index + 1) % count, now);
+            }
+            if ctx.input(|i| i.key_pressed(egui::Key::ArrowLeft)) {
+                self.go_to((self.index + count - 1) % count, now);
+            }
+            if ctx.input(|i| i.key_pressed(egui::Key::Space)) {
+                self.paused = !self.paused;
+                self.slide_start = now;
+            }
+            if !self.paused && now - self.slide_start > SLIDE_DURATION {
+                self.go_to((self.index + 1) % count, now);
+            }
+        }
+
+        let elapsed = now - self.slide_start;
+        let reveal = ((elapsed - REVEAL_DELAY) / REVEAL_DURATION).clamp(0.0, 1.0) as f32;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Quitter").clicked() {
+                    self.stop(ctx);
+                }
+                let label = if self.paused { "Reprendre" } else { "Pause" };
+                if ui.button(label).clicked() {
+                    self.paused = !self.paused;
+                    self.slide_start = now;
+                }
+                ui.label("← → pour naviguer, Échap pour quitter");
+            });
+
+            let Some(&(name, nicknames)) = persons.get(self.index.min(count.saturating_sub(1))) else {
+                ui.centered_and_justified(|ui| ui.heading("Aucun participant"));
+                return;
+            };
+
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() * 0.25);
+                ui.label(RichText::new(name).size(48.0).strong());
+                ui.add_space(32.0);
+
+                match Self::winner(nicknames) {
+                    Some((nickname, vote)) => {
+                        let color = Color32::from_rgb(255, 100, 100).gamma_multiply(reveal);
+                        ui.label(RichText::new(nickname).size(24.0 + 56.0 * reveal).color(color));
+                        ui.label(RichText::new(format!("{} votes", vote.count)).size(24.0).color(Color32::GRAY.gamma_multiply(reveal)));
+                    }
+                    None => {
+                        ui.label(RichText::new("aucun surnom").size(32.0).color(Color32::GRAY.gamma_multiply(reveal)));
+                    }
+                }
+
+                ui.add_space(32.0);
+                ui.label(format!("{} / {}", self.index + 1, count));
+            });
+        });
+
+        if reveal < 1.0 || !self.paused {
+            ctx.request_repaint();
+        }
+    }
+}
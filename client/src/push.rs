@@ -0,0 +1,108 @@
+use common::packets::c2s::Subscribe;
+use common::packets::s2c::Push;
+
+const RETRY_SECS: f64 = 10.0; //between two connection attempts while the server is unreachable
+
+//the /ws connection, the profiles changed by the others arrive through it instead of waiting for a refresh
+pub struct PushChannel {
+    connection: Option<(ewebsock::WsSender, ewebsock::WsReceiver)>,
+    opened: bool,
+    subscription: Option<Subscribe>, //what the server was last told, sent again on every new connection
+    last_attempt: Option<f64>,
+}
+
+impl PushChannel {
+    pub fn new() -> Self {
+        Self {
+            connection: None,
+            opened: false,
+            subscription: None,
+            last_attempt: None,
+        }
+    }
+
+    //server is the base url used for the requests, empty on the web where the page tells where it came from
+    fn url(server: &str) -> String {
+        let base = if server.is_empty() { page_origin() } else { server.trim_end_matches('/').to_string() };
+        let base = base.replacen("https://", "wss://", 1).replacen("http://", "ws://", 1);
+        format!("{}/ws", base)
+    }
+
+    fn connect(&mut self, ctx: &egui::Context, server: &str) {
+        let ctx = ctx.clone();
+        match ewebsock::connect_with_wakeup(Self::url(server), ewebsock::Options::default(), move || ctx.request_repaint()) {
+            Ok(connection) => self.connection = Some(connection),
+            Err(e) => log::warn!("Failed to open the push channel: {}", e),
+        }
+        self.opened = false;
+    }
+
+    //the server changed, or the page came back after being suspended and the connection may be dead
+    pub fn reconnect(&mut self) {
+        self.connection = None;
+        self.last_attempt = None;
+    }
+
+    pub fn subscribe(&mut self, subscribe: Subscribe) {
+        if self.subscription.as_ref() == Some(&subscribe) {
+            return;
+        }
+        self.subscription = Some(subscribe);
+        self.send_subscription();
+    }
+
+    fn send_subscription(&mut self) {
+        let (Some((sender, _)), true, Some(subscription)) = (&mut self.connection, self.opened, &self.subscription) else {
+            return;
+        };
+        let text = serde_json::to_string(subscription).expect("Failed to serialize subscription");
+        sender.send(ewebsock::WsMessage::Text(text));
+    }
+
+    //what arrived since the last frame, connecting again now and then when the connection dropped
+    pub fn poll(&mut self, ctx: &egui::Context, server: &str) -> Vec<Push> {
+        let now = ctx.input(|i| i.time);
+        if self.connection.is_none() && self.last_attempt.is_none_or(|t| now - t >= RETRY_SECS) {
+            self.last_attempt = Some(now);
+            self.connect(ctx, server);
+        }
+
+        let mut pushes = Vec::new();
+        let (mut opened, mut closed) = (false, false);
+        if let Some((_, receiver)) = &self.connection {
+            while let Some(event) = receiver.try_recv() {
+                match event {
+                    ewebsock::WsEvent::Opened => opened = true,
+                    ewebsock::WsEvent::Message(ewebsock::WsMessage::Text(text)) => match serde_json::from_str::<Push>(&text) {
+                        Ok(push) => pushes.push(push),
+                        Err(e) => log::error!("Failed to parse a push: {}", e),
+                    },
+                    ewebsock::WsEvent::Message(_) => {}
+                    ewebsock::WsEvent::Error(e) => {
+                        log::warn!("Push channel error: {}", e);
+                        closed = true;
+                    }
+                    ewebsock::WsEvent::Closed => closed = true,
+                }
+            }
+        }
+        if closed {
+            self.connection = None;
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(RETRY_SECS));
+        } else if opened {
+            self.opened = true;
+            self.send_subscription();
+        }
+        pushes
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn page_origin() -> String {
+    web_sys::window().and_then(|w| w.location().origin().ok()).unwrap_or_default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn page_origin() -> String {
+    String::new()
+}
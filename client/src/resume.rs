@@ -0,0 +1,38 @@
+//notices the page coming back after mobile browsers suspended it, the data shown is stale by then
+const MIN_AWAY_SECS: f64 = 20.0; //a quick look at another tab doesn't refetch everything
+
+pub struct Resume {
+    visible: bool,
+    hidden_at: f64,
+}
+
+impl Resume {
+    pub fn new() -> Self {
+        Self {
+            visible: true,
+            hidden_at: 0.0,
+        }
+    }
+
+    //true once, on the first frame after being hidden or unfocused for MIN_AWAY_SECS
+    pub fn update(&mut self, ctx: &egui::Context) -> bool {
+        let (focused, now) = ctx.input(|i| (i.focused, i.time));
+        let visible = focused && is_page_visible();
+        let resumed = visible && !self.visible && now - self.hidden_at >= MIN_AWAY_SECS;
+        if self.visible && !visible {
+            self.hidden_at = now;
+        }
+        self.visible = visible;
+        resumed
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn is_page_visible() -> bool {
+    web_sys::window().and_then(|w| w.document()).is_none_or(|d| !d.hidden())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_page_visible() -> bool {
+    true
+}
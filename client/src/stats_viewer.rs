@@ -0,0 +1,68 @@
+use egui::{Color32, RichText};
+use common::packets::s2c::WordStats;
+
+const MIN_SIZE: f32 = 12.0;
+const MAX_SIZE: f32 = 48.0;
+const COLORS: [Color32; 4] = [
+    Color32::from_rgb(255, 100, 100),
+    Color32::from_rgb(100, 100, 255),
+    Color32::from_rgb(255, 200, 60),
+    Color32::from_rgb(90, 200, 120),
+];
+
+//word cloud of the propositions of the selected class, sized by how often each word comes up
+pub struct StatsViewer {
+    pub open: bool,
+    stats: Option<WordStats>,
+}
+
+impl StatsViewer {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            stats: None,
+        }
+    }
+
+    pub fn set_stats(&mut self, stats: WordStats) {
+        self.stats = Some(stats);
+    }
+
+    pub fn display(&mut self, ctx: &egui::Context, class: Option<&str>) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = true;
+        egui::Window::new("Nuage de mots")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                let Some(stats) = self.stats.as_ref().filter(|s| Some(s.class.as_str()) == class) else {
+                    ui.spinner();
+                    return;
+                };
+                if stats.words.is_empty() {
+                    ui.label("aucun surnom à afficher pour l'instant");
+                    return;
+                }
+
+                //alphabetical so the big words end up scattered instead of all at the top
+                let max = stats.words.first().map_or(1, |(_, count)| *count).max(1) as f32;
+                let mut words: Vec<&(String, usize)> = stats.words.iter().collect();
+                words.sort_by(|(a, _), (b, _)| a.cmp(b));
+                ui.horizontal_wrapped(|ui| {
+                    for (i, (word, count)) in words.into_iter().enumerate() {
+                        let size = MIN_SIZE + (MAX_SIZE - MIN_SIZE) * (*count as f32 / max);
+                        ui.label(RichText::new(word).size(size).color(COLORS[i % COLORS.len()]))
+                            .on_hover_text(format!("{} fois", count));
+                    }
+                });
+            });
+
+        if !open {
+            self.open = false;
+        }
+    }
+}
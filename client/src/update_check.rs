@@ -0,0 +1,28 @@
+use common::version::BuildInfo;
+
+//true when the server serves a different client than the one running, usually a wasm kept in the browser cache,
+//the native client can't reload itself so it only gets the footer
+pub fn is_outdated(served: &BuildInfo) -> bool {
+    cfg!(target_arch = "wasm32") && !served.git_hash.is_empty() && served.git_hash != BuildInfo::current().git_hash
+}
+
+pub fn display_banner(ctx: &egui::Context) {
+    egui::TopBottomPanel::top("update_banner").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Nouvelle version disponible").strong());
+            if ui.button("Recharger").clicked() {
+                reload();
+            }
+        });
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn reload() {
+    if let Some(window) = web_sys::window() {
+        let _ = window.location().reload();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn reload() {}
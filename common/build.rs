@@ -0,0 +1,12 @@
+use vergen_gitcl::{BuildBuilder, Emitter, GitclBuilder};
+
+//embeds the git hash and build date, see common::version
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let build = BuildBuilder::default().build_timestamp(true).build()?;
+    let git = GitclBuilder::default().sha(true).dirty(false).build()?;
+    Emitter::default()
+        .add_instructions(&build)?
+        .add_instructions(&git)?
+        .emit()?;
+    Ok(())
+}
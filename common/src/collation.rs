@@ -0,0 +1,36 @@
+use std::cmp::Ordering;
+use icu_collator::{Collator, CollatorBorrowed, CollatorPreferences};
+use icu_collator::options::CollatorOptions;
+use icu_locale_core::Locale;
+
+pub const DEFAULT_LOCALE: &str = "fr";
+
+//sorts names the way people expect them ("Élise" next to "Emma", not after "Zoé") instead of by bytes
+pub struct Collation {
+    collator: CollatorBorrowed<'static>,
+}
+
+impl Collation {
+    //unknown or unparsable locales fall back to the root collation
+    pub fn new(locale: &str) -> Self {
+        let prefs = Locale::try_from_str(locale).map(|l| CollatorPreferences::from(&l)).unwrap_or_default();
+        let collator = Collator::try_new(prefs, CollatorOptions::default())
+            .or_else(|_| Collator::try_new(CollatorPreferences::default(), CollatorOptions::default()))
+            .expect("Failed to load the root collation");
+        Self { collator }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.collator.compare(a, b)
+    }
+
+    pub fn sort<T: AsRef<str>>(&self, items: &mut [T]) {
+        items.sort_by(|a, b| self.compare(a.as_ref(), b.as_ref()));
+    }
+}
+
+impl Default for Collation {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCALE)
+    }
+}
@@ -0,0 +1,55 @@
+//links of the form `#/class/<class>/profil/<name>`, names are percent-encoded since they contain spaces and accents
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeepLink {
+    pub class: String,
+    pub profil: Option<String>,
+}
+
+impl DeepLink {
+    pub fn parse(fragment: &str) -> Option<Self> {
+        let mut parts = fragment.trim_start_matches('#').trim_start_matches('/').split('/');
+        if parts.next()? != "class" {
+            return None;
+        }
+        let class = decode(parts.next()?)?;
+        let profil = match (parts.next(), parts.next()) {
+            (Some("profil"), Some(name)) => Some(decode(name)?),
+            _ => None,
+        };
+        Some(Self { class, profil })
+    }
+
+    pub fn to_fragment(&self) -> String {
+        match &self.profil {
+            Some(profil) => format!("#/class/{}/profil/{}", encode(&self.class), encode(profil)),
+            None => format!("#/class/{}", encode(&self.class)),
+        }
+    }
+}
+
+fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
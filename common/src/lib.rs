@@ -1,13 +1,275 @@
 
 pub mod packets;
+pub mod deep_link;
+pub mod time;
+pub mod collation;
+pub mod version;
+pub mod language;
 
-use std::collections::BTreeMap;
-use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Protection {
+    #[default]
+    Open,
+    VotesLocked, //the votes it got are kept: voting still works but it can't be deleted
+    Locked, //neither votes nor deletion, set by the abuse detection until someone looks at it
+}
+
+impl Protection {
+    pub fn is_open(&self) -> bool {
+        *self == Self::Open
+    }
+
+    pub fn can_vote(&self) -> bool {
+        *self != Self::Locked
+    }
+
+    pub fn can_delete(&self) -> bool {
+        *self == Self::Open
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_lowercase().as_str() {
+            "open" => Some(Self::Open),
+            "votes-locked" => Some(Self::VotesLocked),
+            "locked" => Some(Self::Locked),
+            _ => None,
+        }
+    }
+}
+
+//what happens to a new proposition for a participant who already has the maximum
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Overflow {
+    #[default]
+    Reject,
+    ReplaceLowest, //the least voted proposition that can still be deleted makes room, it goes to the trash
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct PropositionCap {
+    pub max: usize, //propositions per participant, 0 for no limit
+    pub overflow: Overflow,
+}
+
+impl Default for PropositionCap {
+    fn default() -> Self {
+        Self { max: 50, overflow: Overflow::default() }
+    }
+}
+
+impl PropositionCap {
+    pub fn is_reached(&self, propositions: usize) -> bool {
+        self.max > 0 && propositions >= self.max
+    }
+}
+
+//how a voter's votes on the propositions for one participant add up
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VoteMode {
+    #[default]
+    Single, //voting moves the vote from the previous proposition
+    Multi, //every proposition liked gets a vote
+    Ranked, //like multi, in order of preference
+}
+
+impl VoteMode {
+    pub fn is_single(&self) -> bool {
+        *self == Self::Single
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_lowercase().as_str() {
+            "single" => Some(Self::Single),
+            "multi" => Some(Self::Multi),
+            "ranked" => Some(Self::Ranked),
+            _ => None,
+        }
+    }
+}
+
+//files saved before protection levels existed have "frozen": true/false
+fn deserialize_protection<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Protection, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Frozen(bool),
+        Level(Protection),
+    }
+    Ok(match Stored::deserialize(deserializer)? {
+        Stored::Frozen(true) => Protection::Locked,
+        Stored::Frozen(false) => Protection::Open,
+        Stored::Level(level) => level,
+    })
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum NicknameEventKind {
+    Created { by: String },
+    Frozen { by: String }, //kept to read histories recorded before protection levels
+    Unfrozen { by: String },
+    Protection { by: String, level: Protection },
+    Transferred { by: String, to: String }, //authorship handed to another profil
+    VoteCount { count: usize }, //votes stay anonymous, only the count is kept
+    Merged { by: String, from: String }, //another spelling was folded into this one, with its votes
+    Deleted { by: String }, //only seen once restored, with the time of the deletion
+    Restored { by: String },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NicknameEvent {
+    pub time: u64, //unix seconds
+    pub kind: NicknameEventKind,
+}
+
+//why a voter thinks a proposition fits, kept with it
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub id: String, //what DeleteComment names it by
+    pub author: String, //recorded like the authors of the propositions, hashed once the class is anonymized
+    pub time: u64, //unix seconds
+    pub text: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct Nickname {
     pub nickname: String,
     pub votes: Vec<String>,
+    #[serde(default, alias = "frozen", deserialize_with = "deserialize_protection", skip_serializing_if = "Protection::is_open")]
+    pub protection: Protection,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<NicknameEvent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>, //stable identifier for archives and other instances, only with stable_ids
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub internal_joke: bool, //only makes sense inside the class, left out of the public views
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<Comment>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub voted_at: BTreeMap<String, u64>, //voter -> unix seconds of the vote, for the decay, the votes cast before it was kept have none
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub announced_votes: BTreeSet<usize>, //vote thresholds already posted to the webhooks, not posted again when the count drops and climbs back
+}
+
+//what a profil is in the class, only the students are proposed for
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProfilKind {
+    #[default]
+    Student,
+    Teacher, //proposes and votes for the students
+    Staff, //only reads
+}
+
+impl ProfilKind {
+    pub const ALL: [Self; 3] = [Self::Student, Self::Teacher, Self::Staff];
+
+    pub fn is_target(&self) -> bool {
+        *self == Self::Student
+    }
+
+    pub fn can_vote(&self) -> bool {
+        *self != Self::Staff
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Student => "student",
+            Self::Teacher => "teacher",
+            Self::Staff => "staff",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name().eq_ignore_ascii_case(text))
+    }
+}
+
+//what a proposition is for: one participant, or the whole class (its motto, its nickname...)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Target {
+    Profil(String), //profil name
+    Class(String), //class name, only the members of the class may propose and vote
+}
+
+impl Target {
+    pub fn profil(&self) -> Option<&str> {
+        match self {
+            Self::Profil(name) => Some(name),
+            Self::Class(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Profil(name) => write!(f, "{}", name),
+            Self::Class(class) => write!(f, "board of {}", class),
+        }
+    }
+}
+
+//the propositions for the class itself, voted for like the ones of a participant
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Board {
+    pub nicknames: Vec<Nickname>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rankings: BTreeMap<String, Vec<String>>, //voter -> propositions voted for, preferred first, ranked mode only
+}
+
+impl Board {
+    pub fn is_empty(&self) -> bool {
+        self.nicknames.is_empty() && self.rankings.is_empty()
+    }
+}
+
+//authors of anonymized classes are recorded as this prefix followed by a hash of their login
+pub const ANONYMOUS_AUTHOR_PREFIX: &str = "anonyme:";
+
+//response header carrying the id the server gave the request, the same as in its logs
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+pub fn is_anonymous(author: &str) -> bool {
+    author.starts_with(ANONYMOUS_AUTHOR_PREFIX)
+}
+
+//the profil that created the proposition, or the last one it was transferred to
+pub fn author(history: &[NicknameEvent]) -> Option<&str> {
+    history.iter().rev().find_map(|e| match &e.kind {
+        NicknameEventKind::Created { by } => Some(by.as_str()),
+        NicknameEventKind::Transferred { to, .. } => Some(to.as_str()),
+        _ => None,
+    })
+}
+
+impl Nickname {
+    pub fn author(&self) -> Option<&str> {
+        author(&self.history)
+    }
+
+    pub fn add_vote(&mut self, voter: &str, time: u64) {
+        self.votes.push(voter.to_string());
+        self.voted_at.insert(voter.to_string(), time);
+    }
+
+    pub fn remove_vote(&mut self, voter: &str) {
+        self.votes.retain(|v| v != voter);
+        self.voted_at.remove(voter);
+    }
+
+    //forgets the times of the votes taken away some other way
+    pub fn prune_vote_times(&mut self) {
+        let Self { votes, voted_at, .. } = self;
+        voted_at.retain(|voter, _| votes.contains(voter));
+    }
 }
 
 impl Default for Nickname {
@@ -15,23 +277,92 @@ impl Default for Nickname {
         Self {
             nickname: "template nickname".to_string(),
             votes: Vec::new(),
+            protection: Protection::Open,
+            history: Vec::new(),
+            uuid: None,
+            internal_joke: false,
+            comments: Vec::new(),
+            voted_at: BTreeMap::new(),
+            announced_votes: BTreeSet::new(),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
 pub struct Group {
     pub profiles: BTreeMap<String, (String, Vec<Nickname>)>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub uuids: BTreeMap<String, String>, //profil name -> stable identifier, only with stable_ids
+    #[serde(default, skip_serializing)]
+    pub author_salt: Option<String>, //what older versions hashed the authors with, such classes are anonymized again at startup
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub anonymous_authors: bool, //set once the class is anonymized, authors are then only kept as a hash of their login
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub public_min_votes: usize, //propositions with fewer votes are left out of the public views
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub display_names: BTreeMap<String, (String, u64)>, //profil name -> name shown instead and when it was chosen, the login keeps the profil name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompts: Vec<String>, //questions shown above the proposal field to give ideas
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hashed_passwords: bool, //set by --migrate-passwords, the profiles then only hold salted hashes
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub password_fingerprints: BTreeMap<String, String>, //profil name -> hash of the password keyed by the audit_key of the config, without salt, lets AuditPasswords compare hashed ones
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub password_changed: BTreeSet<String>, //profils that picked their own password instead of the one handed out
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub must_change_password: BTreeSet<String>, //set by ForcePasswordChange, the client asks for a new one at login
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vote_mode: Option<VoteMode>, //none follows the server's vote_mode
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rankings: BTreeMap<String, BTreeMap<String, Vec<String>>>, //profil name -> voter -> propositions voted for, preferred first, ranked mode only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voting_opens: Option<u64>, //unix seconds, votes and deletions are refused before, none votes from the start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voting_closes: Option<u64>, //unix seconds, the class is read-only from then on, none never closes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vote_half_life_days: Option<u32>, //a vote counts half as much in the displayed rankings after each such period, none counts them all fully
+    #[serde(default, skip_serializing_if = "Board::is_empty")]
+    pub board: Board,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub kinds: BTreeMap<String, ProfilKind>, //profil name -> kind, the students are left out
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub avatars: BTreeMap<String, String>, //profil name -> hash of the png in ./avatars, served by /avatar/<hash>
 }
 
-/*impl Default for Group {
-    fn default() -> Self {
-        let mut names = BTreeMap::new();
-        names.insert("template 1".to_string(), vec![Nickname::default()]);
-        names.insert("template 2".to_string(), vec![Nickname::default()]);
-        Self { names }
+impl Group {
+    pub fn kind(&self, name: &str) -> ProfilKind {
+        self.kinds.get(name).copied().unwrap_or_default()
     }
-}*/
 
+    //none for a profil that doesn't exist, a class target is always this group's board
+    pub fn nicknames(&self, target: &Target) -> Option<&Vec<Nickname>> {
+        match target {
+            Target::Profil(name) => self.profiles.get(name).map(|(_, nicknames)| nicknames),
+            Target::Class(_) => Some(&self.board.nicknames),
+        }
+    }
 
+    pub fn nicknames_mut(&mut self, target: &Target) -> Option<&mut Vec<Nickname>> {
+        match target {
+            Target::Profil(name) => self.profiles.get_mut(name).map(|(_, nicknames)| nicknames),
+            Target::Class(_) => Some(&mut self.board.nicknames),
+        }
+    }
 
+    //the choices of each voter for target, ranked mode only
+    pub fn rankings(&self, target: &Target) -> Option<&BTreeMap<String, Vec<String>>> {
+        match target {
+            Target::Profil(name) => self.rankings.get(name),
+            Target::Class(_) => Some(&self.board.rankings),
+        }
+    }
+
+    //every list of propositions, the participants' then the board
+    pub fn all_nicknames_mut(&mut self) -> impl Iterator<Item = &mut Vec<Nickname>> {
+        self.profiles.values_mut().map(|(_, nicknames)| nicknames).chain(std::iter::once(&mut self.board.nicknames))
+    }
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
@@ -1,6 +1,7 @@
 
 pub mod c2s {
     use serde::{Deserialize, Serialize};
+    use crate::Target;
 
     #[derive(Deserialize, Serialize, Debug, Clone)]
     pub struct AddNickname {
@@ -9,14 +10,53 @@ pub mod c2s {
         pub password: String,
         pub name: String,
         pub nickname: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub target: Option<Target>, //none proposes for name
     }
 
+    impl AddNickname {
+        pub fn target(&self) -> Target {
+            self.target.clone().unwrap_or_else(|| Target::Profil(self.name.clone()))
+        }
+    }
+
+    //a participant deletes the propositions made for them, those of the class board only their author deletes
     #[derive(Deserialize, Serialize, Debug, Clone)]
     pub struct DeleteNickname {
         pub class: String,
         pub editor: String,
         pub password: String,
         pub nickname: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub target: Option<Target>, //none deletes among the propositions for the editor
+    }
+
+    impl DeleteNickname {
+        pub fn target(&self) -> Target {
+            self.target.clone().unwrap_or_else(|| Target::Profil(self.editor.clone()))
+        }
+    }
+
+    //answered with the profil of name, like the modifications of its propositions
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AddComment {
+        pub class: String,
+        pub editor: String,
+        pub password: String,
+        pub name: String,
+        pub nickname: String,
+        pub text: String,
+    }
+
+    //only its author may delete a comment
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct DeleteComment {
+        pub class: String,
+        pub editor: String,
+        pub password: String,
+        pub name: String,
+        pub nickname: String,
+        pub id: String,
     }
 
     #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -26,12 +66,169 @@ pub mod c2s {
         pub nickname: String,
         pub voter: String,
         pub password: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub voter_class: Option<String>, //class the voter logs in with when invited from another one
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub withdraw: bool, //takes the vote back instead, for the multi and ranked modes
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub rank: Option<usize>, //ranked mode, where to put it among the voter's choices from 1, none puts it last
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub target: Option<Target>, //none votes for name, the guests of other classes can't vote on a class board
+    }
+
+    impl VoteNickname {
+        pub fn target(&self) -> Target {
+            self.target.clone().unwrap_or_else(|| Target::Profil(self.name.clone()))
+        }
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct VoteOperation {
+        pub name: String,
+        pub nickname: Option<String>, //none removes the vote of the voter for this name
+    }
+
+    //several votes applied at once, either all of them or none
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct BatchVotes {
+        pub class: String,
+        pub voter: String,
+        pub password: String,
+        pub operations: Vec<VoteOperation>,
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct TransferNickname {
+        pub class: String,
+        pub editor: String, //current author
+        pub password: String,
+        pub name: String,
+        pub nickname: String,
+        pub to: String, //new author, a profil of the same class
     }
 
     #[derive(Deserialize, Serialize, Debug, Clone)]
     pub enum RequestKind {
         All,
         Custom(Vec<String>),
+        Top(usize), //every participant with only their n most voted propositions, the details are asked with Custom
+        Public, //every participant without the internal jokes and the propositions under the class threshold, for projected views
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AskForVoteSummary {
+        pub class: String,
+        pub editor: String,
+        pub password: String,
+    }
+
+    //the name shown to the others, an empty one goes back to the profil name
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct ChangeDisplayName {
+        pub class: String,
+        pub editor: String,
+        pub password: String,
+        pub display_name: String,
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct ChangePassword {
+        pub class: String,
+        pub editor: String,
+        pub password: String,
+        pub new_password: String,
+    }
+
+    //a panic of the web client, sent to /client_error before the page stops
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct ClientError {
+        pub message: String,
+        #[serde(default)]
+        pub location: Option<String>, //file:line:column
+        pub build: String,
+        pub page: String,
+    }
+
+    //sent over /ws, the last one tells which class the pushes are for and what the login may see of it
+    #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+    pub struct Subscribe {
+        pub class: String,
+        #[serde(default)]
+        pub editor: String,
+        #[serde(default)]
+        pub password: String,
+    }
+
+    //checks a login without doing anything with it
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AskForWhoAmI {
+        pub class: String,
+        pub editor: String,
+        pub password: String,
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AskForClassSummary {
+        pub class: String,
+        #[serde(default)]
+        pub editor: String, //optional login, without it the server's guest access applies
+        #[serde(default)]
+        pub password: String,
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AskForWordStats {
+        pub class: String,
+        #[serde(default)]
+        pub editor: String,
+        #[serde(default)]
+        pub password: String,
+    }
+
+    //the most voted propositions of every class the caller may read, or of only, the login is the one of class
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AskForLeaderboard {
+        pub class: String,
+        #[serde(default)]
+        pub editor: String,
+        #[serde(default)]
+        pub password: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub only: Option<String>,
+        pub top: usize,
+    }
+
+    //text being typed as a new proposition for name
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AskForSuggestions {
+        pub class: String,
+        pub name: String,
+        pub text: String,
+        #[serde(default)]
+        pub editor: String,
+        #[serde(default)]
+        pub password: String,
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AskForNicknameHistory {
+        pub class: String,
+        pub name: String,
+        pub nickname: String,
+        #[serde(default)]
+        pub editor: String,
+        #[serde(default)]
+        pub password: String,
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct AskForHistory {
+        pub class: String,
+        pub name: String,
+        #[serde(default)]
+        pub editor: String,
+        #[serde(default)]
+        pub password: String,
     }
 
     #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -43,34 +240,228 @@ pub mod c2s {
     }
 }
 pub mod s2c {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
     use serde::{Deserialize, Serialize};
+    use crate::{NicknameEvent, ProfilKind, PropositionCap, Protection, VoteMode};
+    use crate::version::BuildInfo;
+
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct Capabilities {
+        pub server: BuildInfo,
+        #[serde(default)]
+        pub client: BuildInfo, //client served from client/dist, as trunk build recorded it, empty when unknown
+    }
 
     #[derive(Deserialize, Serialize, Debug, Clone)]
     pub struct ClassList {
         pub names: Vec<String>,
+        #[serde(default)]
+        pub locale: String, //how the client should sort names, empty for the default
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub vote_modes: BTreeMap<String, VoteMode>, //classes not voting in single mode
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub voting_opens: BTreeMap<String, u64>, //classes whose vote isn't open yet -> when it opens
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub voting_closes: BTreeMap<String, u64>, //classes with a deadline -> when the vote closes, past ones included
+        #[serde(default)]
+        pub proposition_cap: PropositionCap, //the same for every class
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub read_only: bool, //the data failed its check, every change is refused until it is repaired
     }
 
     #[derive(Deserialize, Serialize, Debug, Clone)]
     pub struct VoteCount {
         pub count: usize,
         pub contain_you: bool,
+        #[serde(default)]
+        pub protection: Protection,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub your_rank: Option<usize>, //ranked mode, where this one is among your choices from 1
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub comments: Vec<CommentView>, //only to the participants logged in, the public views go without
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub yours: bool, //proposed by whoever asked, only told on the class board where the author deletes
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub weight: Option<f64>, //the votes once the decay of the class is applied, none when it has none
+    }
+
+    impl VoteCount {
+        //what the rankings order by
+        pub fn score(&self) -> f64 {
+            self.weight.unwrap_or(self.count as f64)
+        }
     }
 
     #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct CommentView {
+        pub id: String,
+        pub author: String, //profil name, or the anonymous key of an anonymized class
+        pub time: u64, //unix seconds
+        pub text: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub yours: bool, //may be deleted by whoever asked
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
     pub struct PersonProfileResponse {
         pub partial_response: bool,
         pub allowed_to_modify: bool,
         pub profiles: BTreeMap<String, BTreeMap<String, VoteCount>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub error: Option<String>, //why the last modification was refused, shown to the user
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub counts_hidden: bool, //guest access without vote counts, every count is sent as 0
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub display_names: BTreeMap<String, String>, //profil name -> name to show, only for those who chose one
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub avatars: BTreeMap<String, String>, //profil name -> avatar_hash, the image is at /avatar/<avatar_hash>
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub prompts: Vec<String>, //questions of the class, the client rotates through them
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub request_id: Option<String>, //set along with error, what to give when reporting it
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub receipts: Vec<VoteReceipt>, //answers to a vote, what the voter now supports for each participant voted on
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub board: BTreeMap<String, VoteCount>, //the propositions for the class, always whole even in a partial response
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub kinds: BTreeMap<String, ProfilKind>, //profils that aren't students, left out of the lists to propose for
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+    pub struct VoteReceipt {
+        pub name: String,
+        pub nicknames: Vec<String>, //in the order of the choices in ranked mode, empty once every vote is withdrawn
+        pub time: u64, //unix seconds, when the server recorded it
+    }
+
+    //names of the participants the editor has already voted for, empty if the login is refused
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct VoteSummary {
+        pub voted: BTreeSet<String>,
+    }
+
+    //the profil a login belongs to, the server answers 401 instead when it is refused
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct WhoAmI {
+        pub class: String,
+        pub name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub uuid: Option<String>, //only with stable_ids
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub must_change_password: bool, //the admin asked for a new password before anything else
     }
 
-    impl Default for PersonProfileResponse {
-        fn default() -> Self {
-            Self {
-                partial_response: false,
-                allowed_to_modify: false,
-                profiles: BTreeMap::new(),
-            }
+    //body of the 423 answered to any login of a profil locked after too many failed attempts
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct Locked {
+        pub locked_until: u64, //unix seconds
+    }
+
+    //what /ws sends without being asked, the profiles are partial responses like the answers to the modifications
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    #[serde(tag = "push", rename_all = "snake_case")]
+    pub enum Push {
+        Classes(ClassList),
+        Profiles(PersonProfileResponse),
+    }
+
+    //answer to ChangePassword, changed when there is no error
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct PasswordChange {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub error: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub request_id: Option<String>,
+    }
+
+    //shown while no participant is selected
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct ClassSummary {
+        pub class: String,
+        pub participants: usize,
+        pub voters: usize, //participants who voted at least once
+        pub propositions: usize,
+        pub leaders: BTreeMap<String, (String, usize)>, //name -> most voted nickname and its votes, absent without votes
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub counts_hidden: bool, //voters and leaders are left empty
+    }
+
+    //words used in the propositions of a class, most frequent first
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct WordStats {
+        pub class: String,
+        pub words: Vec<(String, usize)>,
+    }
+
+    //one proposition of the leaderboard, name is the one shown to the others
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct LeaderboardEntry {
+        pub class: String,
+        pub name: String,
+        pub nickname: String,
+        pub votes: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub weight: Option<f64>, //the votes once the decay of the class is applied, none when it has none
+    }
+
+    impl LeaderboardEntry {
+        //what the leaderboard orders by, like the rankings of the class
+        pub fn score(&self) -> f64 {
+            self.weight.unwrap_or(self.votes as f64)
         }
     }
+
+    //an author and what their propositions gathered, the anonymized classes have none
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct ProposerEntry {
+        pub class: String,
+        pub name: String,
+        pub propositions: usize,
+        pub votes: usize,
+    }
+
+    //most votes first, the classes the caller can't see the counts of are left out
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct Leaderboard {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub only: Option<String>,
+        pub nicknames: Vec<LeaderboardEntry>,
+        pub proposers: Vec<ProposerEntry>,
+    }
+
+    //existing propositions close to what is being typed, closest first
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct Suggestions {
+        pub name: String,
+        pub text: String,
+        pub nicknames: Vec<String>,
+    }
+
+    //nicknames of the same person in the other classes it was linked to
+    #[derive(Deserialize, Serialize, Debug, Clone)]
+    pub struct HistoryEntry {
+        pub class: String,
+        pub name: String,
+        pub nicknames: BTreeMap<String, usize>, //nickname -> vote count
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct ProfilHistory {
+        pub class: String,
+        pub name: String,
+        pub entries: Vec<HistoryEntry>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub counts_hidden: bool,
+    }
+
+    #[derive(Deserialize, Serialize, Debug, Clone, Default)]
+    pub struct NicknameHistory {
+        pub name: String,
+        pub nickname: String,
+        pub events: Vec<NicknameEvent>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub error: Option<String>, //a refused transfer, the history is then unchanged
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub request_id: Option<String>,
+    }
 }
\ No newline at end of file
@@ -0,0 +1,30 @@
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+
+//what a binary was built from, filled in by build.rs
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_hash: String,
+    pub build_date: String, //rfc 3339
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: env!("VERGEN_GIT_SHA").to_string(),
+            build_date: env!("VERGEN_BUILD_TIMESTAMP").to_string(),
+        }
+    }
+
+    pub fn short_hash(&self) -> &str {
+        self.git_hash.get(..7).unwrap_or(&self.git_hash)
+    }
+}
+
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}, {})", self.version, self.short_hash(), self.build_date.get(..10).unwrap_or(&self.build_date))
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::mpsc::{channel, Sender};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use common::Group;
+use common::packets::c2s::{AskForPersonProfile, AskForVoteSummary, RequestKind, VoteNickname};
+use common::packets::s2c::{ClassList, PersonProfileResponse, VoteSummary};
+
+const USAGE: &str = "usage: sweat-loadgen <server url> <class file> [--users N] [--rounds N] [--reads-only]
+simulates participants of the class browsing and voting, votes are real: run it against a copy of the data
+--reads-only skips the votes, to compare how the read handlers scale without writers";
+
+struct Options {
+    url: String,
+    class: String,
+    group: Group,
+    users: usize,
+    rounds: usize,
+    reads_only: bool,
+}
+
+fn parse_options() -> anyhow::Result<Options> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut positional = Vec::new();
+    let mut users = 20;
+    let mut rounds = 10;
+    let mut reads_only = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--users" => users = iter.next().ok_or(anyhow::anyhow!("--users needs a value"))?.parse()?,
+            "--rounds" => rounds = iter.next().ok_or(anyhow::anyhow!("--rounds needs a value"))?.parse()?,
+            "--reads-only" => reads_only = true,
+            _ => positional.push(arg.clone()),
+        }
+    }
+    let [url, path] = positional.as_slice() else {
+        anyhow::bail!("expected a server url and a class file");
+    };
+
+    let path = Path::new(path);
+    let class = path.file_stem().ok_or(anyhow::anyhow!("invalid class file name"))?.to_string_lossy().to_string();
+    let group = serde_json::from_reader(File::open(path)?)?;
+    Ok(Options {
+        url: url.trim_end_matches('/').to_string(),
+        class,
+        group,
+        users,
+        rounds,
+        reads_only,
+    })
+}
+
+//xorshift, only used to pick who gets a vote
+struct Random(u32);
+
+impl Random {
+    fn below(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as usize % max.max(1)
+    }
+}
+
+struct Sample {
+    endpoint: &'static str,
+    duration: Duration,
+    ok: bool,
+}
+
+struct User<'a> {
+    options: &'a Options,
+    name: String,
+    password: String,
+    samples: Sender<Sample>,
+}
+
+impl User<'_> {
+    fn send<T: DeserializeOwned>(&self, endpoint: &'static str, body: Option<&impl Serialize>) -> Option<T> {
+        let url = format!("{}/{}", self.options.url, endpoint);
+        let request = match body {
+            Some(body) => ehttp::Request::json(url, body).ok()?,
+            None => ehttp::Request::get(url),
+        };
+
+        let start = Instant::now();
+        let response = ehttp::fetch_blocking(&request);
+        let duration = start.elapsed();
+
+        let parsed = response.ok()
+            .filter(|r| r.ok)
+            .and_then(|r| r.json::<T>().ok());
+        let _ = self.samples.send(Sample { endpoint, duration, ok: parsed.is_some() });
+        parsed
+    }
+
+    fn run(&self, seed: u32) {
+        let mut random = Random(seed.max(1));
+        let class = self.options.class.clone();
+
+        for _ in 0..self.options.rounds {
+            self.send::<ClassList>("class_list", None::<&()>);
+
+            let profiles = self.send::<PersonProfileResponse>("person_profile", Some(&AskForPersonProfile {
+                class: class.clone(),
+                editor: self.name.clone(),
+                password: self.password.clone(),
+                kind: RequestKind::All,
+            }));
+            self.send::<VoteSummary>("my_vote_summary", Some(&AskForVoteSummary {
+                class: class.clone(),
+                editor: self.name.clone(),
+                password: self.password.clone(),
+            }));
+
+            let Some(profiles) = profiles.filter(|_| !self.options.reads_only) else {
+                continue;
+            };
+            let candidates: Vec<(&String, &String)> = profiles.profiles.iter()
+                .flat_map(|(name, nicknames)| nicknames.iter().filter(|(_, v)| v.protection.can_vote()).map(move |(n, _)| (name, n)))
+                .collect();
+            if candidates.is_empty() {
+                continue;
+            }
+            let (name, nickname) = candidates[random.below(candidates.len())];
+            self.send::<PersonProfileResponse>("vote_nickname", Some(&VoteNickname {
+                class: class.clone(),
+                name: name.clone(),
+                nickname: nickname.clone(),
+                voter: self.name.clone(),
+                password: self.password.clone(),
+                voter_class: None,
+                withdraw: false,
+                rank: None,
+                target: None,
+            }));
+        }
+    }
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let index = ((sorted.len() as f64 - 1.0) * p).round() as usize;
+    sorted.get(index).copied().unwrap_or_default()
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(e) => {
+            println!("{}\n{}", e, USAGE);
+            std::process::exit(1);
+        }
+    };
+    let participants: Vec<(&String, &String)> = options.group.profiles.iter().map(|(name, (password, _))| (name, password)).collect();
+    if participants.is_empty() {
+        println!("{} has no participants", options.class);
+        std::process::exit(1);
+    }
+    println!("{} users x {} rounds against {} (class {}){}", options.users, options.rounds, options.url, options.class,
+        if options.reads_only { ", reads only" } else { "" });
+
+    let (sender, receiver) = channel();
+    let start = Instant::now();
+    std::thread::scope(|scope| {
+        for i in 0..options.users {
+            let (name, password) = participants[i % participants.len()];
+            let user = User {
+                options: &options,
+                name: name.clone(),
+                password: password.clone(),
+                samples: sender.clone(),
+            };
+            scope.spawn(move || user.run(0x9E37_79B9 ^ i as u32));
+        }
+    });
+    drop(sender);
+    let elapsed = start.elapsed();
+
+    let mut by_endpoint: BTreeMap<&str, (Vec<Duration>, usize)> = BTreeMap::new();
+    for sample in receiver.iter() {
+        let (durations, failures) = by_endpoint.entry(sample.endpoint).or_default();
+        durations.push(sample.duration);
+        if !sample.ok {
+            *failures += 1;
+        }
+    }
+
+    let total: usize = by_endpoint.values().map(|(d, _)| d.len()).sum();
+    println!("{} requests in {:.2?} ({:.1} req/s)", total, elapsed, total as f64 / elapsed.as_secs_f64());
+    println!("{:<16} {:>7} {:>7} {:>10} {:>10} {:>10} {:>10}", "endpoint", "count", "failed", "p50", "p90", "p99", "max");
+    for (endpoint, (mut durations, failures)) in by_endpoint {
+        durations.sort();
+        println!("{:<16} {:>7} {:>7} {:>10.2?} {:>10.2?} {:>10.2?} {:>10.2?}",
+            endpoint,
+            durations.len(),
+            failures,
+            percentile(&durations, 0.5),
+            percentile(&durations, 0.9),
+            percentile(&durations, 0.99),
+            durations.last().copied().unwrap_or_default(),
+        );
+    }
+}
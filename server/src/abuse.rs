@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use crate::config::AbuseConfig;
+use crate::memory::HeapSize;
+use crate::unix_now;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagKind {
+    VoteBurst, //one voter casting many votes in a few seconds
+    SharedAddress, //many accounts voting from the same address
+    NicknameBurst, //one proposition gaining many votes in a minute
+    FilteredWord, //proposition matching a mild term of the content filter
+}
+
+#[derive(Debug, Clone)]
+pub struct Flag {
+    pub time: u64, //unix seconds
+    pub kind: FlagKind,
+    pub class: String,
+    pub description: String,
+    pub propositions: Vec<(String, String)>, //(name, nickname) involved
+}
+
+struct VoteEvent {
+    time: Instant,
+    voter: String,
+    name: String,
+    nickname: String,
+}
+
+//keeps short sliding windows of recent votes, nothing here is saved to disk
+pub struct AbuseDetector {
+    config: AbuseConfig,
+    by_voter: HashMap<(String, String), VecDeque<VoteEvent>>, //(class, voter)
+    by_address: HashMap<(String, IpAddr), VecDeque<VoteEvent>>, //(class, address)
+    by_nickname: HashMap<(String, String, String), VecDeque<VoteEvent>>, //(class, name, nickname)
+    flags: Vec<Flag>,
+}
+
+impl AbuseDetector {
+    pub fn new(config: AbuseConfig) -> Self {
+        Self {
+            config,
+            by_voter: HashMap::new(),
+            by_address: HashMap::new(),
+            by_nickname: HashMap::new(),
+            flags: Vec::new(),
+        }
+    }
+
+    pub fn auto_freeze(&self) -> bool {
+        self.config.auto_freeze
+    }
+
+    fn push(window: &mut VecDeque<VoteEvent>, event: VoteEvent, duration: Duration) {
+        while window.front().is_some_and(|e| event.time.duration_since(e.time) > duration) {
+            window.pop_front();
+        }
+        window.push_back(event);
+    }
+
+    fn involved(window: &VecDeque<VoteEvent>) -> Vec<(String, String)> {
+        let mut propositions: Vec<(String, String)> = window.iter().map(|e| (e.name.clone(), e.nickname.clone())).collect();
+        propositions.sort();
+        propositions.dedup();
+        propositions
+    }
+
+    fn raise(&mut self, kind: FlagKind, class: &str, description: String, propositions: Vec<(String, String)>) -> Flag {
+        println!("abuse flag {:?} in {}: {}", kind, class, description);
+        let flag = Flag {
+            time: unix_now(),
+            kind,
+            class: class.to_string(),
+            description,
+            propositions,
+        };
+        self.flags.push(flag.clone());
+        flag
+    }
+
+    //returns the flags raised by this vote, a window that raised a flag is cleared so it doesn't fire on every following vote
+    pub fn record_vote(&mut self, class: &str, voter: &str, name: &str, nickname: &str, address: Option<IpAddr>) -> Vec<Flag> {
+        let now = Instant::now();
+        let event = || VoteEvent { time: now, voter: voter.to_string(), name: name.to_string(), nickname: nickname.to_string() };
+        let mut raised = Vec::new();
+
+        let key = (class.to_string(), voter.to_string());
+        let window = self.by_voter.entry(key.clone()).or_default();
+        Self::push(window, event(), Duration::from_secs(self.config.voter_window_secs));
+        if window.len() >= self.config.votes_per_voter {
+            let description = format!("{} cast {} votes in {}s", voter, window.len(), self.config.voter_window_secs);
+            let propositions = Self::involved(window);
+            self.by_voter.remove(&key);
+            raised.push(self.raise(FlagKind::VoteBurst, class, description, propositions));
+        }
+
+        if let Some(address) = address {
+            let key = (class.to_string(), address);
+            let window = self.by_address.entry(key.clone()).or_default();
+            Self::push(window, event(), Duration::from_secs(self.config.ip_window_secs));
+            let accounts: HashSet<&str> = window.iter().map(|e| e.voter.as_str()).collect();
+            if accounts.len() >= self.config.accounts_per_ip {
+                let mut accounts: Vec<&str> = accounts.into_iter().collect();
+                accounts.sort();
+                let description = format!("{} accounts voted from {} in {}s: {}", accounts.len(), address, self.config.ip_window_secs, accounts.join(", "));
+                let propositions = Self::involved(window);
+                self.by_address.remove(&key);
+                raised.push(self.raise(FlagKind::SharedAddress, class, description, propositions));
+            }
+        }
+
+        let key = (class.to_string(), name.to_string(), nickname.to_string());
+        let window = self.by_nickname.entry(key.clone()).or_default();
+        Self::push(window, event(), Duration::from_secs(self.config.nickname_window_secs));
+        if window.len() >= self.config.votes_per_nickname {
+            let description = format!("\"{}\" for {} gained {} votes in {}s", nickname, name, window.len(), self.config.nickname_window_secs);
+            self.by_nickname.remove(&key);
+            raised.push(self.raise(FlagKind::NicknameBurst, class, description, vec![(name.to_string(), nickname.to_string())]));
+        }
+
+        raised
+    }
+
+    pub fn flag_content(&mut self, class: &str, editor: &str, name: &str, nickname: &str, term: &str) -> Flag {
+        let description = format!("{} proposed \"{}\" for {} (matches \"{}\")", editor, nickname, name, term);
+        self.raise(FlagKind::FilteredWord, class, description, vec![(name.to_string(), nickname.to_string())])
+    }
+
+    pub fn flags(&self) -> &[Flag] {
+        &self.flags
+    }
+
+    pub fn clear_flags(&mut self) {
+        self.flags.clear();
+    }
+}
+
+impl HeapSize for VoteEvent {
+    fn heap_size(&self) -> usize {
+        self.voter.heap_size() + self.name.heap_size() + self.nickname.heap_size()
+    }
+}
+
+impl HeapSize for Flag {
+    fn heap_size(&self) -> usize {
+        self.class.heap_size() + self.description.heap_size() + self.propositions.heap_size()
+    }
+}
+
+impl HeapSize for AbuseDetector {
+    fn heap_size(&self) -> usize {
+        self.by_voter.heap_size() + self.by_address.heap_size() + self.by_nickname.heap_size() + self.flags.heap_size()
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::{BTreeMap, BTreeSet};
+use sha2::{Digest, Sha256};
+use common::{Group, NicknameEventKind, ANONYMOUS_AUTHOR_PREFIX};
+use crate::classes::new_uuid;
+use crate::passwords;
+
+//what gets recorded as the author: the name, or once the class was anonymized a slow hash of the login of the author,
+//the server keeps nothing it could be computed back from, only the author typing their password again is recognized
+pub fn author_key(group: &Group, name: &str, password: &str) -> String {
+    if group.anonymous_authors {
+        format!("{}{}", ANONYMOUS_AUTHOR_PREFIX, passwords::derive(password, &format!("author\n{}", name)))
+    } else {
+        name.to_string()
+    }
+}
+
+//the hash of older versions, salt and name only, whoever read the data could compute it
+fn legacy_key(salt: &str, name: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(name.as_bytes())
+        .finalize();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", ANONYMOUS_AUTHOR_PREFIX, hex)
+}
+
+//every recorded author goes through rewrite, returns how many entries were changed
+fn rewrite_authors(group: &mut Group, mut rewrite: impl FnMut(&str) -> Option<String>) -> usize {
+    let mut rewritten = 0;
+    for nicknames in group.all_nicknames_mut() {
+        for event in nicknames.iter_mut().flat_map(|n| n.history.iter_mut()) {
+            let authors = match &mut event.kind {
+                NicknameEventKind::Created { by } => vec![by],
+                NicknameEventKind::Transferred { by, to } => vec![by, to],
+                _ => Vec::new(),
+            };
+            for author in authors {
+                if let Some(key) = rewrite(author) {
+                    *author = key;
+                    rewritten += 1;
+                }
+            }
+        }
+        for comment in nicknames.iter_mut().flat_map(|n| n.comments.iter_mut()) {
+            if let Some(key) = rewrite(&comment.author) {
+                comment.author = key;
+                rewritten += 1;
+            }
+        }
+    }
+    rewritten
+}
+
+//replaces every author already recorded by their key, returns how many entries were rewritten and how many
+//authors can't be recognized anymore: with hashed passwords the server can't compute the key of the past authors,
+//they get one nobody can log in as
+pub fn anonymize(group: &mut Group) -> (usize, usize) {
+    let legacy_salt = group.author_salt.take();
+    let anonymous = group.anonymous_authors;
+    group.anonymous_authors = true;
+
+    let hashed = group.hashed_passwords;
+    let mut keys = BTreeMap::new(); //recorded author -> key
+    for (name, (password, _)) in &group.profiles {
+        let recorded = match &legacy_salt {
+            Some(salt) => legacy_key(salt, name),
+            None if anonymous => continue,
+            None => name.clone(),
+        };
+        let key = if hashed {
+            format!("{}{}", ANONYMOUS_AUTHOR_PREFIX, new_uuid())
+        } else {
+            author_key(group, name, password)
+        };
+        keys.insert(recorded, key);
+    }
+    let mut lost = BTreeSet::new();
+    let rewritten = rewrite_authors(group, |author| {
+        let key = keys.get(author)?;
+        if hashed {
+            lost.insert(author.to_string());
+        }
+        Some(key.clone())
+    });
+    (rewritten, lost.len())
+}
+
+//the key follows the password, called with both while changing it
+pub fn rekey(group: &mut Group, name: &str, old_password: &str, new_password: &str) {
+    if !group.anonymous_authors {
+        return;
+    }
+    let old = author_key(group, name, old_password);
+    let new = author_key(group, name, new_password);
+    rewrite_authors(group, |author| (author == old).then(|| new.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{Comment, Group, Nickname, NicknameEvent, NicknameEventKind, ANONYMOUS_AUTHOR_PREFIX};
+    use super::*;
+
+    //Alice proposed a nickname for Bob and commented it
+    fn class(hashed_passwords: bool) -> Group {
+        let mut group = Group { hashed_passwords, ..Group::default() };
+        let proposed = Nickname {
+            nickname: "Bobby".to_string(),
+            history: vec![NicknameEvent { time: 1, kind: NicknameEventKind::Created { by: "Alice".to_string() } }],
+            comments: vec![Comment { id: "c1".to_string(), author: "Alice".to_string(), time: 2, text: "oui".to_string() }],
+            ..Nickname::default()
+        };
+        group.profiles.insert("Alice".to_string(), ("alicepass1".to_string(), Vec::new()));
+        group.profiles.insert("Bob".to_string(), ("bobpass12".to_string(), vec![proposed]));
+        group
+    }
+
+    fn recorded(group: &Group) -> (String, String) {
+        let proposed = &group.profiles["Bob"].1[0];
+        (proposed.author().expect("Failed to find the author").to_string(), proposed.comments[0].author.clone())
+    }
+
+    #[test]
+    fn names_until_anonymized() {
+        assert_eq!(author_key(&class(false), "Alice", "alicepass1"), "Alice");
+    }
+
+    #[test]
+    fn plaintext_class_keeps_its_authors() {
+        let mut group = class(false);
+        assert_eq!(anonymize(&mut group), (2, 0));
+        let key = author_key(&group, "Alice", "alicepass1");
+        assert!(key.starts_with(ANONYMOUS_AUTHOR_PREFIX));
+        assert!(!key.contains("Alice"));
+        assert_eq!(recorded(&group), (key.clone(), key));
+        assert_ne!(author_key(&group, "Alice", "alicepass2"), author_key(&group, "Alice", "alicepass1"));
+        assert_eq!(anonymize(&mut group), (0, 0), "anonymizing again changes nothing");
+    }
+
+    #[test]
+    fn hashed_class_loses_its_authors() {
+        let mut group = class(true);
+        assert_eq!(anonymize(&mut group), (2, 1));
+        let (proposed, commented) = recorded(&group);
+        assert!(proposed.starts_with(ANONYMOUS_AUTHOR_PREFIX));
+        assert_eq!(proposed, commented, "one random key per author");
+        assert_ne!(proposed, author_key(&group, "Alice", "alicepass1"));
+    }
+
+    #[test]
+    fn legacy_salt_is_replaced() {
+        let mut group = class(false);
+        group.anonymous_authors = true;
+        group.author_salt = Some("salt".to_string());
+        for nicknames in group.all_nicknames_mut() {
+            for nickname in nicknames.iter_mut() {
+                nickname.history[0].kind = NicknameEventKind::Created { by: legacy_key("salt", "Alice") };
+                nickname.comments[0].author = legacy_key("salt", "Alice");
+            }
+        }
+        assert_eq!(anonymize(&mut group), (2, 0));
+        assert_eq!(group.author_salt, None);
+        let key = author_key(&group, "Alice", "alicepass1");
+        assert_eq!(recorded(&group), (key.clone(), key));
+    }
+
+    #[test]
+    fn key_follows_the_password() {
+        let mut group = class(false);
+        anonymize(&mut group);
+        rekey(&mut group, "Alice", "alicepass1", "alicepass2");
+        let key = author_key(&group, "Alice", "alicepass2");
+        assert_eq!(recorded(&group), (key.clone(), key));
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use actix_web::HttpRequest;
+use tokio::sync::broadcast;
+use common::collation::Collation;
+use common::language::Language;
+use crate::abuse::AbuseDetector;
+use crate::anonymity;
+use crate::archive;
+use crate::classes::Class;
+use crate::config::{IntegrityConfig, ServerConfig};
+use crate::filter::ContentFilter;
+use crate::notifications::Notifier;
+use crate::grants::Grants;
+use crate::ip_log::IpLog;
+use crate::jobs::Jobs;
+use crate::links::Links;
+use crate::lockout::Lockouts;
+use crate::passwords;
+use crate::push::{self, ProfilChange};
+use crate::rate_limit::RateLimiter;
+use crate::reload::Settings;
+use crate::stats::StatsCache;
+use crate::storage::{self, Storage};
+use crate::timing::{TimedMutex, TimedRwLock};
+use crate::trash::Trash;
+
+//shared state of the server, the behaviour lives in classes.rs, profils.rs and propositions.rs
+pub struct AppState {
+    pub classes: HashMap<String, TimedRwLock<Class>>, //class name -> Class
+    pub storage: Arc<dyn Storage>, //the one of every class, for the classes created while running
+    pub abuse: TimedMutex<AbuseDetector>,
+    pub notifier: Notifier, //the Discord webhooks of the config
+    pub ip_log: TimedMutex<IpLog>,
+    pub trust_forwarded_for: bool,
+    pub client_errors: bool, //error_report.client_errors
+    pub rate_limiter: TimedMutex<RateLimiter>,
+    pub lockouts: TimedMutex<Lockouts>, //failed logins, see AppState::check_login
+    pub links: TimedMutex<Links>,
+    pub trash: TimedMutex<Trash>, //deleted propositions, see UndoDelete
+    pub archive: TimedMutex<Trash>, //propositions left without votes, see archive.rs
+    pub grants: TimedMutex<Grants>,
+    pub filter: TimedMutex<ContentFilter>,
+    pub jobs: TimedMutex<Jobs>,
+    pub locale: String,
+    pub language: Language, //of the errors when the request asks for none the server knows, from locale
+    pub stable_ids: bool,
+    pub admin_token: Option<String>,
+    pub audit_key: Option<String>, //of the password fingerprints, from the config only
+    pub changes: broadcast::Sender<ProfilChange>, //what /ws pushes to the subscribed clients
+    pub collation: Collation,
+    pub stats: TimedMutex<StatsCache>, //refreshed by stats::spawn_refresh
+    pub settings: TimedRwLock<Settings>, //the reloadable part of the config
+    pub config: TimedMutex<ServerConfig>, //as running, what ReloadConfig compares the file with
+    pub integrity: IntegrityConfig,
+    pub read_only: Arc<AtomicBool>, //set when the data failed the startup check or a save lost the data lock, see integrity::refuse_writes
+}
+
+impl AppState {
+    pub fn new(config: &ServerConfig) -> anyhow::Result<Self> {
+        println!("Creating new AppState");
+
+        let storage = storage::open(config.save_format)?;
+        if passwords::forget_stored_key(storage.as_ref())? {
+            println!("the password fingerprints kept next to their key were dropped, set audit_key in the config to record them again");
+        }
+        let read_only = Arc::new(AtomicBool::new(false));
+        let mut groups = HashMap::new();
+        for (name, participants) in storage.load_classes() {
+            match participants {
+                Ok(participants) => {
+                    let mut class = Class { name: name.clone(), participants, storage: storage.clone(), read_only: read_only.clone() };
+                    if config.stable_ids && class.assign_uuids() {
+                        class.save();
+                    }
+                    if class.participants.author_salt.is_some() {
+                        //the salt of older versions sat next to the names it hid
+                        let (rewritten, lost) = anonymity::anonymize(&mut class.participants);
+                        println!("{}: {} authors hashed again from their login, {} can't be recognized anymore", name, rewritten, lost);
+                        class.save();
+                    }
+                    groups.insert(name, TimedRwLock::new(class));
+                }
+                Err(e) => println!("Failed to load class {}: {:?}", name, e),
+            }
+        }
+
+        Ok(AppState {
+            classes: groups,
+            abuse: TimedMutex::new(AbuseDetector::new(config.abuse.clone())),
+            notifier: Notifier::start(&config.notifications),
+            ip_log: TimedMutex::new(IpLog::new(config.ip_log.clone())),
+            trust_forwarded_for: config.ip_log.trust_forwarded_for,
+            client_errors: config.error_report.client_errors,
+            rate_limiter: TimedMutex::new(RateLimiter::new(config.rate_limit.clone())),
+            lockouts: TimedMutex::new(Lockouts::new(config.lockout.clone())),
+            links: TimedMutex::new(Links::load(storage.clone())),
+            trash: TimedMutex::new(Trash::deleted(storage.clone())),
+            archive: TimedMutex::new(archive::load(storage.clone())),
+            grants: TimedMutex::new(Grants::load(storage.clone())),
+            filter: TimedMutex::new(ContentFilter::load(storage.clone())),
+            jobs: TimedMutex::new(Jobs::default()),
+            locale: config.locale.clone(),
+            language: Language::from_tag(&config.locale).unwrap_or(Language::French),
+            stable_ids: config.stable_ids,
+            admin_token: config.admin_token.clone().filter(|t| !t.is_empty()),
+            audit_key: config.audit_key.clone(),
+            changes: push::channel(),
+            collation: Collation::new(&config.locale),
+            stats: TimedMutex::new(StatsCache::default()),
+            settings: TimedRwLock::new(Settings::new(config)),
+            config: TimedMutex::new(config.clone()),
+            integrity: config.integrity.clone(),
+            read_only,
+            storage,
+        })
+    }
+
+    //address of the client, taken from X-Forwarded-For / Forwarded only when the config says a proxy sets it
+    pub fn client_address(&self, request: &HttpRequest) -> Option<IpAddr> {
+        if self.trust_forwarded_for {
+            let connection = request.connection_info();
+            let address = connection.realip_remote_addr()?;
+            address.parse().ok()
+                .or_else(|| address.parse::<std::net::SocketAddr>().ok().map(|a| a.ip()))
+        } else {
+            request.peer_addr().map(|a| a.ip())
+        }
+    }
+
+    pub fn record_address(&self, class: &str, name: &str, address: Option<IpAddr>) {
+        self.ip_log.lock().expect("Failed to lock ip log").record(class, name, address);
+    }
+}
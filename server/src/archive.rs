@@ -0,0 +1,103 @@
+use std::sync::Arc;
+use std::time::Duration;
+use common::{Group, Nickname, NicknameEventKind, Target};
+use common::time::format_unix_time;
+use crate::app_state::AppState;
+use crate::config::ArchiveConfig;
+use crate::storage::Storage;
+use crate::trash::Trash;
+use crate::{unix_now, State};
+
+const DOCUMENT: &str = "archived_nicknames";
+const MAX_KEPT: usize = 10_000; //the oldest archived ones can't be restored past that
+const CHECK: Duration = Duration::from_secs(3600);
+const ARCHIVED_BY: &str = "archive"; //shown as who deleted it once restored
+
+//kept apart from the deletions so a cleanup doesn't push them out of UndoDelete
+pub fn load(storage: Arc<dyn Storage>) -> Trash {
+    Trash::load(storage, DOCUMENT, MAX_KEPT)
+}
+
+//created before the limit and voted for by nobody but its author, the protected ones stay,
+//without a vote at all in an anonymized class where the server can't tell the author among the voters
+fn is_stale(group: &Group, nickname: &Nickname, created_before: u64) -> bool {
+    let created = nickname.history.iter().find_map(|e| match e.kind {
+        NicknameEventKind::Created { .. } => Some(e.time),
+        _ => None,
+    });
+    let author = nickname.author();
+    nickname.protection.can_delete()
+        && created.is_some_and(|time| time < created_before)
+        && if group.anonymous_authors { nickname.votes.is_empty() } else { nickname.votes.iter().all(|voter| Some(voter.as_str()) == author) }
+}
+
+impl AppState {
+    pub fn archive_stale(&self, after_days: u64) -> Vec<String> {
+        let created_before = unix_now().saturating_sub(after_days * 24 * 3600);
+        let mut lines = Vec::new();
+        for (class_name, class) in &self.classes {
+            let mut archived = Vec::new();
+            {
+                let mut lock = class.write().expect("Failed to lock data");
+                let group = &mut lock.participants;
+                let stale: Vec<(String, Vec<usize>)> = group.profiles.iter()
+                    .map(|(name, (_, nicknames))| (name.clone(), nicknames.iter().enumerate()
+                        .filter(|(_, n)| is_stale(group, n, created_before))
+                        .map(|(position, _)| position)
+                        .collect::<Vec<usize>>()))
+                    .filter(|(_, positions)| !positions.is_empty())
+                    .collect();
+                for (name, positions) in stale {
+                    let (_, nicknames) = group.profiles.get_mut(&name).expect("Failed to find name");
+                    for position in positions.into_iter().rev() {
+                        archived.push((name.clone(), position, nicknames.remove(position)));
+                    }
+                }
+                if archived.is_empty() {
+                    continue;
+                }
+                lock.save();
+            }
+
+            let mut archive = self.archive.lock().expect("Failed to lock archive");
+            for (name, position, nickname) in archived {
+                let shown = nickname.nickname.clone();
+                let id = archive.bury(class_name, &Target::Profil(name.clone()), position, nickname, ARCHIVED_BY.to_string());
+                lines.push(format!("\"{}\" for {} in {} archived as {}", shown, name, class_name, id));
+                self.notify(class_name, &name);
+            }
+        }
+        lines
+    }
+
+    pub fn list_archived(&self) -> Vec<String> {
+        let archive = self.archive.lock().expect("Failed to lock archive");
+        let mut lines: Vec<String> = archive.recent(20)
+            .map(|t| format!("{}: \"{}\" for {} in {}, archived {} UTC", t.id, t.nickname.nickname, t.target(), t.class, format_unix_time(t.time)))
+            .collect();
+        if lines.is_empty() {
+            lines.push("no archived proposition".to_string());
+        }
+        lines
+    }
+
+    pub fn unarchive(&self, id: u64, by: &str) -> Result<String, String> {
+        self.restore_from(&self.archive, id, by).map_err(|e| e.unwrap_or_else(|| format!("no archived proposition {}", id)))
+    }
+}
+
+//every CHECK, the first one right at start
+pub fn spawn(state: State, config: ArchiveConfig) {
+    if !config.enabled {
+        return;
+    }
+    std::thread::spawn(move || loop {
+        //archiving is a change too, it waits for the data to be repaired
+        if !state.is_read_only() {
+            for line in state.archive_stale(config.after_days) {
+                println!("{}", line);
+            }
+        }
+        std::thread::sleep(CHECK);
+    });
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use common::{author, Group, NicknameEvent, NicknameEventKind, Protection};
+
+#[derive(Deserialize, Debug)]
+pub struct AsOfQuery {
+    pub class: String,
+    pub time: String, //unix seconds or UTC "YYYY-MM-DD HH:MM"
+    #[serde(default)]
+    pub token: String, //admin_token, the authors are in the answer
+}
+
+//a proposition as it was at some point, rebuilt from its history
+#[derive(Serialize, Debug, Clone)]
+pub struct PastNickname {
+    pub nickname: String,
+    pub votes: usize,
+    pub protection: Protection,
+    pub author: Option<String>,
+}
+
+fn protection_at(history: &[NicknameEvent]) -> Protection {
+    history.iter().rev().find_map(|e| match &e.kind {
+        NicknameEventKind::Frozen { .. } => Some(Protection::Locked),
+        NicknameEventKind::Unfrozen { .. } => Some(Protection::Open),
+        NicknameEventKind::Protection { level, .. } => Some(*level),
+        _ => None,
+    }).unwrap_or_default()
+}
+
+//the propositions of every profil at time, from the histories kept in the group,
+//a deleted proposition leaves nothing behind so it can't show up here, nor one saved before histories existed
+pub fn as_of(group: &Group, time: u64) -> BTreeMap<String, Vec<PastNickname>> {
+    let mut profiles = BTreeMap::new();
+    for (name, (_, nicknames)) in &group.profiles {
+        let past = nicknames.iter()
+            .filter_map(|nickname| {
+                let end = nickname.history.partition_point(|e| e.time <= time);
+                let history = &nickname.history[..end];
+                if !history.iter().any(|e| matches!(e.kind, NicknameEventKind::Created { .. })) {
+                    return None;
+                }
+
+                //every vote change is recorded since the creation event exists
+                let votes = history.iter().rev()
+                    .find_map(|e| match e.kind { NicknameEventKind::VoteCount { count } => Some(count), _ => None })
+                    .unwrap_or(0);
+                Some(PastNickname {
+                    nickname: nickname.nickname.clone(),
+                    votes,
+                    protection: protection_at(history),
+                    author: author(history).map(str::to_string),
+                })
+            })
+            .collect();
+        profiles.insert(name.clone(), past);
+    }
+    profiles
+}
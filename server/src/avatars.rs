@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use sha2::{Digest, Sha256};
+use common::packets::s2c::PersonProfileResponse;
+use crate::app_state::AppState;
+use crate::messages::Message;
+use crate::request_id;
+
+pub const DIR: &str = "./avatars"; //next to the classes whatever the save format, the storages only hold the hashes
+pub const MAX_BYTES: usize = 200 * 1024; //under the 256 KiB actix reads into a body
+const MAX_SIDE: u32 = 512;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+//what a multipart/form-data body holds, field name -> content, there is no crate for it here
+//and the upload form is small: class, editor, password and the avatar file
+pub fn multipart_fields(content_type: &str, body: &[u8]) -> Option<BTreeMap<String, Vec<u8>>> {
+    let boundary = content_type.split(';')
+        .find_map(|p| p.trim().strip_prefix("boundary="))?
+        .trim_matches('"');
+    let delimiter = format!("--{}", boundary).into_bytes();
+
+    let mut fields = BTreeMap::new();
+    let mut parts = split(body, &delimiter).into_iter().skip(1); //before the first delimiter is the preamble
+    for part in parts.by_ref() {
+        if part.starts_with(b"--") {
+            break; //the closing delimiter
+        }
+        let part = part.strip_prefix(b"\r\n")?;
+        let end_of_headers = find(part, b"\r\n\r\n")?;
+        let headers = String::from_utf8_lossy(&part[..end_of_headers]);
+        let content = &part[end_of_headers + 4..];
+        let content = content.strip_suffix(b"\r\n").unwrap_or(content);
+        let name = headers.lines()
+            .filter(|line| line.to_ascii_lowercase().starts_with("content-disposition:"))
+            .flat_map(|line| line.split(';'))
+            .find_map(|p| p.trim().strip_prefix("name="))?
+            .trim_matches('"');
+        fields.insert(name.to_string(), content.to_vec());
+    }
+    Some(fields)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn split<'a>(mut body: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = Vec::new();
+    while let Some(at) = find(body, delimiter) {
+        parts.push(&body[..at]);
+        body = &body[at + delimiter.len()..];
+    }
+    parts.push(body);
+    parts
+}
+
+//width and height from the IHDR chunk, always the first one of a png
+fn png_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if !bytes.starts_with(&PNG_SIGNATURE) || bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+fn refusal(bytes: &[u8]) -> Option<Message> {
+    if bytes.len() > MAX_BYTES {
+        return Some(Message::AvatarTooLarge(MAX_BYTES / 1024));
+    }
+    match png_size(bytes) {
+        None => Some(Message::AvatarNotPng),
+        Some((width, height)) if width > MAX_SIDE || height > MAX_SIDE => Some(Message::AvatarTooWide(MAX_SIDE)),
+        Some(_) => None,
+    }
+}
+
+//the hash names the file and changes with it, so the clients may cache /avatar/<hash> forever;
+//class and name are in it so two profils with the same image don't share a file one of them could remove
+fn avatar_hash(class: &str, name: &str, bytes: &[u8]) -> String {
+    let digest = Sha256::new()
+        .chain_update(class.as_bytes())
+        .chain_update([0])
+        .chain_update(name.as_bytes())
+        .chain_update([0])
+        .chain_update(bytes)
+        .finalize();
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+//none for anything but a hash, the route can't be made to read another file
+pub fn path(hash: &str) -> Option<PathBuf> {
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit() && !b.is_ascii_uppercase()))
+        .then(|| PathBuf::from(DIR).join(format!("{}.png", hash)))
+}
+
+fn field(fields: &BTreeMap<String, Vec<u8>>, name: &str) -> String {
+    fields.get(name).map(|v| String::from_utf8_lossy(v).to_string()).unwrap_or_default()
+}
+
+impl AppState {
+    //replaces the avatar of the editor, an empty or missing avatar field removes it
+    pub fn upload_avatar(&self, fields: &BTreeMap<String, Vec<u8>>, address: Option<IpAddr>) -> PersonProfileResponse {
+        let (class, editor, password) = (field(fields, "class"), field(fields, "editor"), field(fields, "password"));
+        let bytes = fields.get("avatar").map_or(&[][..], |v| v.as_slice());
+        println!("[{}] upload_avatar: {} in class {}, {} bytes", request_id::current(), editor, class, bytes.len());
+
+        let Some(group) = self.classes.get(&class) else {
+            return PersonProfileResponse::default();
+        };
+        let mut lock = group.write().expect("Failed to lock data");
+        if !self.check_login(&class, &lock.participants, &editor, &password) {
+            return PersonProfileResponse::default();
+        }
+        let names = vec![editor.clone()];
+        if let Some(refused) = Self::refuse_until_changed(&lock.participants, &editor, &password, &names) {
+            return refused;
+        }
+        self.record_address(&class, &editor, address);
+
+        let refused = (!bytes.is_empty()).then(|| refusal(bytes)).flatten();
+        if refused.is_none() {
+            let saved = if bytes.is_empty() {
+                Ok(None)
+            } else {
+                let hash = avatar_hash(&class, &editor, bytes);
+                let path = path(&hash).expect("Failed to build avatar path");
+                std::fs::create_dir_all(DIR).and_then(|()| std::fs::write(&path, bytes)).map(|()| Some(hash))
+            };
+            match saved {
+                Ok(hash) => {
+                    let previous = match hash {
+                        Some(hash) => lock.participants.avatars.insert(editor.clone(), hash),
+                        None => lock.participants.avatars.remove(&editor),
+                    };
+                    lock.save();
+                    if let Some(previous) = previous.filter(|p| lock.participants.avatars.get(&editor) != Some(p)).and_then(|p| path(&p)) {
+                        if let Err(e) = std::fs::remove_file(&previous) {
+                            println!("Failed to remove {}: {:?}", previous.display(), e);
+                        }
+                    }
+                    self.notify(&class, &editor);
+                }
+                Err(e) => println!("[{}] Failed to save the avatar of {}: {:?}", request_id::current(), editor, e),
+            }
+        }
+
+        let mut response = Self::group_to_response_custom(&lock.participants, &editor, &password, &names);
+        response.error = refused.map(|m| m.localized());
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::multipart_fields;
+
+    const CONTENT_TYPE: &str = "multipart/form-data; boundary=\"xyz\"";
+
+    #[test]
+    fn reads_every_field() {
+        let body = b"preamble\r\n--xyz\r\nContent-Disposition: form-data; name=\"editor\"\r\n\r\nAlice\r\n\
+            --xyz\r\nContent-Disposition: form-data; name=\"avatar\"; filename=\"a.png\"\r\nContent-Type: image/png\r\n\r\n\x89PNG\r\n--\r\n\
+            --xyz--\r\n";
+        let fields = multipart_fields(CONTENT_TYPE, body).expect("Failed to parse the form");
+        assert_eq!(fields["editor"], b"Alice");
+        assert_eq!(fields["avatar"], b"\x89PNG\r\n--", "the content keeps what looks like a line or a delimiter");
+        assert_eq!(fields.len(), 2);
+    }
+
+    #[test]
+    fn refuses_what_isnt_a_form() {
+        assert_eq!(multipart_fields("application/json", b"{}"), None);
+        assert_eq!(multipart_fields(CONTENT_TYPE, b"--xyz\r\nno headers end\r\n--xyz--"), None);
+        assert_eq!(multipart_fields(CONTENT_TYPE, b"--xyz\r\nContent-Type: text/plain\r\n\r\nnameless\r\n--xyz--"), None);
+        assert_eq!(multipart_fields(CONTENT_TYPE, b"no delimiter at all").map(|f| f.len()), Some(0));
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use common::Group;
+use common::packets::c2s::AskForHistory;
+use common::packets::s2c::{ClassList, HistoryEntry, ProfilHistory};
+use crate::app_state::AppState;
+use crate::guests::{Endpoint, GuestAccess};
+use crate::links::ProfilRef;
+use crate::storage::{LockLost, Storage};
+use crate::unix_now;
+
+pub struct Class {
+    pub name: String,
+    pub participants: Group,
+    pub storage: Arc<dyn Storage>,
+    pub read_only: Arc<AtomicBool>, //the one of AppState, set when another server took the data directory
+}
+
+impl Class {
+    //a failed save is only logged, a panic here would poison the lock of the class for every later request;
+    //once another server holds the data the whole server turns read-only and the writes get 503,
+    //nothing is written while it is, whichever command or route asked
+    pub fn save(&self) {
+        if self.read_only.load(Ordering::Relaxed) {
+            println!("{} not saved, the server is read-only", self.name);
+            return;
+        }
+        self.save_anyway();
+    }
+
+    //for RepairData and Repair, the way out of read-only
+    pub fn save_anyway(&self) {
+        if let Err(e) = self.storage.save_class(&self.name, &self.participants) {
+            println!("Failed to save {}: {:?}", self.name, e);
+            if e.chain().any(|cause| cause.is::<LockLost>()) && !self.read_only.swap(true, Ordering::Relaxed) {
+                println!("the server is read-only, another server is using its data");
+            }
+        }
+    }
+
+    //gives an uuid to every profil and proposition still missing one, true if any was added
+    pub fn assign_uuids(&mut self) -> bool {
+        let mut assigned = false;
+        for name in self.participants.profiles.keys() {
+            if !self.participants.uuids.contains_key(name) {
+                self.participants.uuids.insert(name.clone(), new_uuid());
+                assigned = true;
+            }
+        }
+        for nickname in self.participants.all_nicknames_mut().flatten().filter(|n| n.uuid.is_none()) {
+            nickname.uuid = Some(new_uuid());
+            assigned = true;
+        }
+        assigned
+    }
+}
+
+pub fn new_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+impl AppState {
+    pub fn list_classes(&self) -> ClassList {
+        let mut names = self.classes.keys().cloned().collect::<Vec<String>>();
+        self.collation.sort(&mut names);
+        let vote_modes = self.classes.iter()
+            .map(|(name, group)| (name.clone(), self.vote_mode(&group.read().expect("Failed to lock data").participants)))
+            .filter(|(_, mode)| !mode.is_single())
+            .collect();
+        let now = unix_now();
+        let voting_opens = self.classes.iter()
+            .filter_map(|(name, group)| Some((name.clone(), group.read().expect("Failed to lock data").participants.voting_opens.filter(|opens| *opens > now)?)))
+            .collect();
+        let voting_closes = self.classes.iter()
+            .filter_map(|(name, group)| Some((name.clone(), group.read().expect("Failed to lock data").participants.voting_closes?)))
+            .collect();
+        ClassList { names, locale: self.locale.clone(), vote_modes, voting_opens, voting_closes, proposition_cap: self.settings.read().expect("Failed to lock settings").proposition_cap, read_only: self.is_read_only() }
+    }
+
+    pub fn history(&self, asked: &AskForHistory) -> ProfilHistory {
+        //the login is checked in the class asked for, the linked profils are the same person
+        let access = match self.classes.get(&asked.class) {
+            Some(group) => self.access(Endpoint::ProfilHistory, &asked.class, &group.read().expect("Failed to lock data").participants, &asked.editor, &asked.password),
+            None => self.guest_access(Endpoint::ProfilHistory),
+        };
+        if access == GuestAccess::Closed {
+            return ProfilHistory::default();
+        }
+        let counts_hidden = access == GuestAccess::Propositions;
+        let linked = self.links.lock().expect("Failed to lock links")
+            .linked(&ProfilRef { class: asked.class.clone(), name: asked.name.clone() });
+
+        let mut entries = Vec::new();
+        for ProfilRef { class, name } in linked {
+            if class == asked.class && name == asked.name {
+                continue;
+            }
+            let Some(group) = self.classes.get(&class) else {
+                continue;
+            };
+            let lock = group.read().expect("Failed to lock data");
+            if let Some((_, nicknames)) = lock.participants.profiles.get(&name) {
+                entries.push(HistoryEntry {
+                    class: class.clone(),
+                    name: name.clone(),
+                    nicknames: nicknames.iter().map(|n| (n.nickname.clone(), if counts_hidden { 0 } else { n.votes.len() })).collect(),
+                });
+            }
+        }
+
+        entries.sort_by(|a, b| self.collation.compare(&a.class, &b.class));
+        ProfilHistory {
+            class: asked.class.clone(),
+            name: asked.name.clone(),
+            entries,
+            counts_hidden,
+        }
+    }
+}
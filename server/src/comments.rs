@@ -0,0 +1,104 @@
+use std::net::IpAddr;
+use common::Comment;
+use common::packets::c2s::{AddComment, DeleteComment};
+use common::packets::s2c::PersonProfileResponse;
+use crate::anonymity::author_key;
+use crate::app_state::AppState;
+use crate::classes::new_uuid;
+use crate::filter::Severity;
+use crate::messages::Message;
+use crate::request_id;
+use crate::unix_now;
+
+const MAX_LENGTH: usize = 280; //characters of a comment
+const MAX_PER_NICKNAME: usize = 50;
+
+impl AppState {
+    //the content filter applies like to the propositions, a mild term is flagged without freezing anything
+    pub fn add_comment(&self, add: &AddComment, address: Option<IpAddr>) -> PersonProfileResponse {
+        let AddComment { class, editor, password, name, nickname, text } = add;
+        println!("[{}] add_comment: on {} of {} by {} in class {}", request_id::current(), nickname, name, editor, class);
+
+        let class_name = class;
+        let Some(class) = self.classes.get(class) else {
+            return PersonProfileResponse::default();
+        };
+        let mut lock = class.write().expect("Failed to lock data");
+        if !self.check_login(class_name, &lock.participants, editor, password) {
+            return PersonProfileResponse::default();
+        }
+        let names = vec![name.clone()];
+        if let Some(refused) = Self::refuse_until_changed(&lock.participants, editor, password, &names) {
+            return refused;
+        }
+        self.record_address(class_name, editor, address);
+
+        let text = text.trim();
+        let author = author_key(&lock.participants, editor, password);
+        let filtered = self.filter.lock().expect("Failed to lock filter").check(text);
+        let Some(target) = lock.participants.profiles.get_mut(name)
+            .and_then(|(_, nicknames)| nicknames.iter_mut().find(|n| n.nickname == *nickname)) else {
+            return Self::group_to_response_custom(&lock.participants, editor, password, &names);
+        };
+        let refusal = match &filtered {
+            _ if text.chars().count() > MAX_LENGTH => Some(Message::CommentTooLong(MAX_LENGTH)),
+            Some((Severity::Severe, _)) => Some(Message::ForbiddenTerm),
+            _ if target.comments.len() >= MAX_PER_NICKNAME => Some(Message::TooManyComments),
+            _ => None,
+        };
+        if let Some(refusal) = refusal {
+            let mut response = Self::group_to_response_custom(&lock.participants, editor, password, &names);
+            response.error = Some(refusal.localized());
+            return response;
+        }
+        if text.is_empty() {
+            return Self::group_to_response_custom(&lock.participants, editor, password, &names);
+        }
+        target.comments.push(Comment { id: new_uuid(), author: author.clone(), time: unix_now(), text: text.to_string() });
+
+        if let Some((Severity::Mild, term)) = filtered {
+            self.abuse.lock().expect("Failed to lock abuse detector").flag_content(class_name, &author, name, text, &term);
+        }
+        lock.save();
+        self.notify(class_name, name);
+        Self::group_to_response_custom(&lock.participants, editor, password, &names)
+    }
+
+    pub fn delete_comment(&self, delete: &DeleteComment, address: Option<IpAddr>) -> PersonProfileResponse {
+        let DeleteComment { class, editor, password, name, nickname, id } = delete;
+        println!("[{}] delete_comment: {} on {} of {} by {} in class {}", request_id::current(), id, nickname, name, editor, class);
+
+        let class_name = class;
+        let Some(class) = self.classes.get(class) else {
+            return PersonProfileResponse::default();
+        };
+        let mut lock = class.write().expect("Failed to lock data");
+        if !self.check_login(class_name, &lock.participants, editor, password) {
+            return PersonProfileResponse::default();
+        }
+        let names = vec![name.clone()];
+        if let Some(refused) = Self::refuse_until_changed(&lock.participants, editor, password, &names) {
+            return refused;
+        }
+        self.record_address(class_name, editor, address);
+
+        let author = author_key(&lock.participants, editor, password);
+        let comments = lock.participants.profiles.get_mut(name)
+            .and_then(|(_, nicknames)| nicknames.iter_mut().find(|n| n.nickname == *nickname))
+            .map(|n| &mut n.comments);
+        let Some(position) = comments.as_ref().and_then(|c| c.iter().position(|c| c.id == *id)) else {
+            return Self::group_to_response_custom(&lock.participants, editor, password, &names);
+        };
+        let comments = comments.expect("Failed to find comments");
+        if comments[position].author != author {
+            let mut response = Self::group_to_response_custom(&lock.participants, editor, password, &names);
+            response.error = Some(Message::CommentNotYours.localized());
+            return response;
+        }
+        comments.remove(position);
+
+        lock.save();
+        self.notify(class_name, name);
+        Self::group_to_response_custom(&lock.participants, editor, password, &names)
+    }
+}
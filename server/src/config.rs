@@ -0,0 +1,636 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use common::collation::DEFAULT_LOCALE;
+use common::{PropositionCap, VoteMode};
+use crate::guests::{Endpoint, GuestAccess};
+use crate::storage::SaveFormat;
+
+pub const CONFIG_PATH: &str = "./config.json";
+pub const TOML_CONFIG_PATH: &str = "./config.toml";
+const INCLUDE: &str = "include"; //files merged over the config, for the secrets kept out of it
+const ENV_PREFIX: &str = "SWEAT__";
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct AbuseConfig {
+    pub votes_per_voter: usize, //votes from one voter within voter_window_secs before raising a flag
+    pub voter_window_secs: u64,
+    pub accounts_per_ip: usize, //distinct voters from one address within ip_window_secs
+    pub ip_window_secs: u64,
+    pub votes_per_nickname: usize, //votes gained by one proposition within nickname_window_secs
+    pub nickname_window_secs: u64,
+    pub auto_freeze: bool, //freeze the propositions involved in a flag until someone looks at it
+}
+
+impl Default for AbuseConfig {
+    fn default() -> Self {
+        Self {
+            votes_per_voter: 15,
+            voter_window_secs: 10,
+            accounts_per_ip: 5,
+            ip_window_secs: 300,
+            votes_per_nickname: 10,
+            nickname_window_secs: 60,
+            auto_freeze: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct IpLogConfig {
+    pub enabled: bool, //off by default, addresses are personal data
+    pub trust_forwarded_for: bool, //only when running behind a reverse proxy, otherwise clients can forge the header
+    pub retention_secs: u64,
+}
+
+impl Default for IpLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trust_forwarded_for: false,
+            retention_secs: 7 * 24 * 3600,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub struct Bucket {
+    pub burst: u32, //requests allowed at once
+    pub per_minute: u32, //tokens given back each minute
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub per_address: Bucket, //a whole school may share an address, keep it well above per_profil
+    pub per_profil: Bucket,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            per_address: Bucket { burst: 200, per_minute: 600 },
+            per_profil: Bucket { burst: 30, per_minute: 60 },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ErrorReportConfig {
+    pub dsn: Option<String>, //"https://<key>@<host>/<project>" of a Sentry-compatible server, none keeps the errors in the log only
+    pub environment: String,
+    pub client_errors: bool, //accept the panics the web clients send to /client_error
+}
+
+impl Default for ErrorReportConfig {
+    fn default() -> Self {
+        Self {
+            dsn: None,
+            environment: "production".to_string(),
+            client_errors: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct LogFileConfig {
+    pub enabled: bool, //in addition to the standard output
+    pub directory: String,
+    pub retention_days: u64, //days of files kept, 0 keeps them all
+    pub filter: String, //same syntax as RUST_LOG, SetLogLevel doesn't change it
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: "./logs".to_string(),
+            retention_days: 30,
+            filter: "info".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    pub enabled: bool, //archives hourly the propositions nobody voted for
+    pub after_days: u64, //without a vote other than the author's since its creation
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_days: 30,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct LockoutConfig {
+    pub enabled: bool,
+    pub max_failures: usize, //different wrong passwords within window_secs before the profil is locked
+    pub window_secs: u64,
+    pub lock_secs: u64, //Unlock lifts it earlier
+}
+
+impl Default for LockoutConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_failures: 5,
+            window_secs: 15 * 60,
+            lock_secs: 15 * 60,
+        }
+    }
+}
+
+//what the server checks in the classes at startup, see integrity.rs
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct IntegrityConfig {
+    pub enabled: bool, //starts read-only when a class has duplicates or too many dangling references
+    pub max_dangling: usize, //references to missing profils tolerated in a class, past it the server is read-only
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_dangling: 10,
+        }
+    }
+}
+
+//a Discord webhook and what is posted to it
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String, //"https://discord.com/api/webhooks/<id>/<token>", better kept in an include
+    pub classes: Vec<String>, //empty for every class
+    pub new_propositions: bool,
+    pub vote_threshold: Option<usize>, //posts when a proposition reaches this many votes
+    pub deletions: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub workers: usize, //0 means one per cpu core
+    pub keep_alive_secs: Option<u64>, //none leaves it to the os, 0 disables keep-alive
+    pub client_request_timeout_ms: u64, //time given to a client to send the request head, 0 disables
+    pub max_connections: usize, //per worker
+    pub backlog: u32, //pending connections waiting to be accepted
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            workers: 0,
+            keep_alive_secs: None,
+            client_request_timeout_ms: 5000,
+            max_connections: 25_000,
+            backlog: 1024,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind: String, //address and port the http server listens on
+    pub save_format: SaveFormat,
+    pub locale: String, //collation used to sort names, sent to the clients with the class list
+    pub stable_ids: bool, //give profils and propositions an uuid, kept by every storage and snapshot
+    pub slow_request_ms: u64, //requests taking longer are logged with their lock waiting time, 0 disables
+    pub guest_access: GuestAccess, //for the endpoints missing from guest_endpoints
+    pub guest_endpoints: BTreeMap<Endpoint, GuestAccess>,
+    pub display_name_cooldown_secs: u64, //time a participant waits between two changes of their shown name
+    pub vote_mode: VoteMode, //for the classes without their own, see VoteMode
+    pub proposition_cap: PropositionCap, //propositions per participant and what a new one does past it
+    pub admin_token: Option<String>, //asked by the /admin routes that change data, none disables them
+    pub audit_key: Option<String>, //of the fingerprints AuditPasswords compares hashed passwords with, keep it in an include or SWEAT__AUDIT_KEY, never with the data
+    pub http: HttpConfig,
+    pub abuse: AbuseConfig,
+    pub ip_log: IpLogConfig,
+    pub rate_limit: RateLimitConfig,
+    pub lockout: LockoutConfig,
+    pub integrity: IntegrityConfig,
+    pub error_report: ErrorReportConfig,
+    pub log_file: LogFileConfig,
+    pub archive: ArchiveConfig,
+    pub notifications: Vec<WebhookConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0:8080".to_string(),
+            save_format: SaveFormat::default(),
+            locale: DEFAULT_LOCALE.to_string(),
+            stable_ids: false,
+            slow_request_ms: 500,
+            guest_access: GuestAccess::default(),
+            guest_endpoints: BTreeMap::new(),
+            display_name_cooldown_secs: 7 * 24 * 3600,
+            vote_mode: VoteMode::default(),
+            proposition_cap: PropositionCap::default(),
+            admin_token: None,
+            audit_key: None,
+            http: HttpConfig::default(),
+            abuse: AbuseConfig::default(),
+            ip_log: IpLogConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            lockout: LockoutConfig::default(),
+            integrity: IntegrityConfig::default(),
+            error_report: ErrorReportConfig::default(),
+            log_file: LogFileConfig::default(),
+            archive: ArchiveConfig::default(),
+            notifications: Vec::new(),
+        }
+    }
+}
+
+//what was found while reading the config, warnings keep the server starting, errors stop it
+#[derive(Default)]
+pub struct ConfigReport {
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl ServerConfig {
+    //missing file means default values, a broken one is reported and the server exits, SWEAT__* variables override both
+    pub fn load(path: &Path) -> Self {
+        Self::load_with(path, true)
+    }
+
+    //the file alone, for --init which writes it back
+    pub fn load_file(path: &Path) -> Self {
+        Self::load_with(path, false)
+    }
+
+    fn load_with(path: &Path, overrides: bool) -> Self {
+        let (config, report) = Self::check(path, overrides);
+        for warning in &report.warnings {
+            println!("{}: {}", path.display(), warning);
+        }
+        for error in &report.errors {
+            println!("{}: {}", path.display(), error);
+        }
+        if !report.errors.is_empty() {
+            //the defaults could mean another bind, another storage or no admin token, better not start at all
+            println!("Failed to load {}, fix it or check it with --check-config", path.display());
+            std::process::exit(1);
+        }
+        config
+    }
+
+    //--check-config prints the report and exits
+    pub fn check(path: &Path, overrides: bool) -> (Self, ConfigReport) {
+        let mut report = ConfigReport::default();
+        let mut given = if path.exists() {
+            match read_value(path) {
+                Ok(Value::Object(fields)) => Value::Object(fields),
+                Ok(_) => {
+                    report.errors.push("expected an object at the top level".to_string());
+                    return (Self::default(), report);
+                }
+                Err(e) => {
+                    report.errors.push(format!("not valid {}: {}", format_name(path), e));
+                    return (Self::default(), report);
+                }
+            }
+        } else {
+            Value::Object(Map::new())
+        };
+        apply_includes(path, &mut given, &mut report);
+        if overrides {
+            apply_env_overrides(&mut given, std::env::vars(), &mut report);
+        }
+
+        let defaults = serde_json::to_value(Self::default()).expect("Failed to serialize the default config");
+        unknown_fields(&defaults, &given, "", &mut report.warnings);
+        match serde_json::from_value::<Self>(given.clone()) {
+            Ok(config) => {
+                report.errors.extend(config.validate());
+                let config = if report.errors.is_empty() { config } else { Self::default() };
+                (config, report)
+            }
+            Err(e) => {
+                //the same error again, field by field, so the message says where it is
+                let before = report.errors.len();
+                field_errors(&defaults, &defaults, &given, "", &mut report.errors);
+                if report.errors.len() == before {
+                    report.errors.push(e.to_string());
+                }
+                (Self::default(), report)
+            }
+        }
+    }
+
+    //values serde accepts but the server can't run with
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.bind.rsplit_once(':').is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err()) {
+            errors.push(format!("bind: expected \"host:port\", got \"{}\"", self.bind));
+        }
+        if self.http.max_connections == 0 {
+            errors.push("http.max_connections: must be at least 1".to_string());
+        }
+        if self.rate_limit.enabled {
+            for (name, bucket) in [("per_address", self.rate_limit.per_address), ("per_profil", self.rate_limit.per_profil)] {
+                if bucket.burst == 0 || bucket.per_minute == 0 {
+                    errors.push(format!("rate_limit.{}: burst and per_minute must be at least 1 while the rate limit is enabled", name));
+                }
+            }
+        }
+        if self.lockout.enabled && (self.lockout.max_failures == 0 || self.lockout.lock_secs == 0) {
+            errors.push("lockout: max_failures and lock_secs must be at least 1 while the lockout is enabled".to_string());
+        }
+        if let Some(dsn) = &self.error_report.dsn {
+            if !(dsn.starts_with("https://") || dsn.starts_with("http://")) || !dsn.contains('@') {
+                errors.push(format!("error_report.dsn: expected \"https://<key>@<host>/<project>\", got \"{}\"", dsn));
+            }
+        }
+        if self.log_file.enabled && self.log_file.directory.trim().is_empty() {
+            errors.push("log_file.directory: empty while the log file is enabled".to_string());
+        }
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.log_file.filter) {
+            errors.push(format!("log_file.filter: {}", e));
+        }
+        for (i, webhook) in self.notifications.iter().enumerate() {
+            if !(webhook.url.starts_with("https://") || webhook.url.starts_with("http://")) {
+                errors.push(format!("notifications[{}].url: expected \"https://discord.com/api/webhooks/<id>/<token>\", got \"{}\"", i, webhook.url));
+            }
+            if webhook.vote_threshold == Some(0) {
+                errors.push(format!("notifications[{}].vote_threshold: must be at least 1, remove it for no threshold", i));
+            }
+        }
+        if self.admin_token.as_ref().is_some_and(|t| t.trim().is_empty()) {
+            errors.push("admin_token: empty, remove it to disable the /admin routes".to_string());
+        }
+        if self.audit_key.as_ref().is_some_and(|k| k.trim().is_empty()) {
+            errors.push("audit_key: empty, remove it to record no password fingerprint".to_string());
+        }
+        errors
+    }
+}
+
+//config.toml when there is one, config.json otherwise
+pub fn config_path() -> &'static Path {
+    if Path::new(TOML_CONFIG_PATH).exists() {
+        if Path::new(CONFIG_PATH).exists() {
+            println!("{} and {} both exist, {} is ignored", TOML_CONFIG_PATH, CONFIG_PATH, CONFIG_PATH);
+        }
+        Path::new(TOML_CONFIG_PATH)
+    } else {
+        Path::new(CONFIG_PATH)
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "toml")
+}
+
+fn format_name(path: &Path) -> &'static str {
+    if is_toml(path) { "toml" } else { "json" }
+}
+
+//json or toml by the extension, both end up as the json value the rest of the checks work on
+fn read_value(path: &Path) -> anyhow::Result<Value> {
+    let text = std::fs::read_to_string(path)?;
+    if is_toml(path) {
+        let document: toml_edit::Document = text.parse()?;
+        Ok(toml_table(document.as_table()))
+    } else {
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+fn toml_table<'a>(entries: impl IntoIterator<Item = (&'a str, &'a toml_edit::Item)>) -> Value {
+    let fields = entries.into_iter()
+        .filter_map(|(key, item)| toml_item(item).map(|value| (key.to_string(), value)))
+        .collect();
+    Value::Object(fields)
+}
+
+fn toml_item(item: &toml_edit::Item) -> Option<Value> {
+    match item {
+        toml_edit::Item::None => None,
+        toml_edit::Item::Value(value) => Some(toml_value(value)),
+        toml_edit::Item::Table(table) => Some(toml_table(table.iter())),
+        toml_edit::Item::ArrayOfTables(tables) => Some(Value::Array(tables.iter().map(|t| toml_table(t.iter())).collect())),
+    }
+}
+
+fn toml_value(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => Value::from(*i.value()),
+        toml_edit::Value::Float(f) => Value::from(*f.value()),
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(d) => Value::String(d.value().to_string()),
+        toml_edit::Value::Array(array) => Value::Array(array.iter().map(toml_value).collect()),
+        toml_edit::Value::InlineTable(table) => Value::Object(table.iter().map(|(k, v)| (k.to_string(), toml_value(v))).collect()),
+    }
+}
+
+//"include": "secrets.toml" or a list of them, relative to the config, merged over it in order,
+//they hold the tokens and dsn so they must not be readable by other users of the machine
+fn apply_includes(path: &Path, given: &mut Value, report: &mut ConfigReport) {
+    let Some(include) = given.as_object_mut().and_then(|fields| fields.remove(INCLUDE)) else {
+        return;
+    };
+    let files: Vec<String> = match include {
+        Value::String(file) => vec![file],
+        Value::Array(files) if files.iter().all(Value::is_string) => files.into_iter().filter_map(|f| f.as_str().map(str::to_string)).collect(),
+        _ => {
+            report.errors.push(format!("{}: expected a file name or a list of them", INCLUDE));
+            return;
+        }
+    };
+    let directory = path.parent().unwrap_or(Path::new("."));
+    for file in files {
+        let included = directory.join(&file);
+        if let Some(problem) = loose_permissions(&included) {
+            report.errors.push(format!("{}: {}", included.display(), problem));
+            continue;
+        }
+        match read_value(&included) {
+            Ok(Value::Object(mut fields)) => {
+                if fields.remove(INCLUDE).is_some() {
+                    report.warnings.push(format!("{}: {} is only read from the main config, ignored", included.display(), INCLUDE));
+                }
+                merge(given, Value::Object(fields));
+            }
+            Ok(_) => report.errors.push(format!("{}: expected an object at the top level", included.display())),
+            Err(e) => report.errors.push(format!("{}: {}", included.display(), e)),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn loose_permissions(path: &Path) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(path).ok()?.permissions().mode();
+    (mode & 0o077 != 0).then(|| format!("readable by other users (mode {:o}), chmod 600 it", mode & 0o777))
+}
+
+#[cfg(not(unix))]
+fn loose_permissions(_path: &Path) -> Option<String> {
+    None
+}
+
+//objects are merged field by field, anything else is replaced
+fn merge(target: &mut Value, other: Value) {
+    match (target, other) {
+        (Value::Object(target), Value::Object(other)) => {
+            for (key, value) in other {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, other) => *target = other,
+    }
+}
+
+//--init only asks for the bind address, it is set in the file as written so the includes stay out of it
+pub fn set_bind(path: &Path, bind: &str) -> anyhow::Result<()> {
+    if is_toml(path) {
+        let mut document: toml_edit::Document = std::fs::read_to_string(path)?.parse()?;
+        document["bind"] = toml_edit::value(bind);
+        std::fs::write(path, document.to_string())?;
+    } else {
+        let mut fields = match path.exists() {
+            true => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+            false => Map::new(),
+        };
+        fields.insert("bind".to_string(), Value::String(bind.to_string()));
+        serde_json::to_writer_pretty(std::fs::File::create(path)?, &fields)?;
+    }
+    Ok(())
+}
+
+//SWEAT__HTTP__WORKERS=4 sets http.workers, the value is read as json and falls back to a string
+fn apply_env_overrides(given: &mut Value, vars: impl Iterator<Item = (String, String)>, report: &mut ConfigReport) {
+    for (key, raw) in vars {
+        let Some(path) = key.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let path: Vec<String> = path.split("__").map(str::to_lowercase).collect();
+        if path.iter().any(String::is_empty) {
+            report.warnings.push(format!("{}: ignored, empty field name", key));
+            continue;
+        }
+        let value = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+        let mut target = &mut *given;
+        for field in &path[..path.len() - 1] {
+            if !target.is_object() {
+                break;
+            }
+            target = target.as_object_mut().expect("checked above").entry(field.clone()).or_insert_with(|| Value::Object(Map::new()));
+        }
+        match target.as_object_mut() {
+            Some(fields) => {
+                fields.insert(path[path.len() - 1].clone(), value);
+                report.warnings.push(format!("{} overridden by {}", path.join("."), key));
+            }
+            None => report.errors.push(format!("{}: {} is not an object", key, path[..path.len() - 1].join("."))),
+        }
+    }
+}
+
+//fields of the defaults are the known ones, an empty object is a map (guest_endpoints) and takes any key
+fn unknown_fields(defaults: &Value, given: &Value, path: &str, warnings: &mut Vec<String>) {
+    let (Some(defaults), Some(given)) = (defaults.as_object(), given.as_object()) else {
+        return;
+    };
+    if defaults.is_empty() {
+        return;
+    }
+    for (key, value) in given {
+        let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+        match defaults.get(key) {
+            Some(default) => unknown_fields(default, value, &field, warnings),
+            None => warnings.push(format!("{}: unknown field, ignored (expected one of {})", field, defaults.keys().cloned().collect::<Vec<_>>().join(", "))),
+        }
+    }
+}
+
+//puts each given value alone into the defaults and keeps the ones serde refuses
+fn field_errors(root: &Value, defaults: &Value, given: &Value, path: &str, errors: &mut Vec<String>) {
+    let (Some(defaults), Some(given)) = (defaults.as_object(), given.as_object()) else {
+        return;
+    };
+    for (key, value) in given {
+        let Some(default) = defaults.get(key) else {
+            continue;
+        };
+        let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+        if default.as_object().is_some_and(|d| !d.is_empty()) && value.is_object() {
+            field_errors(root, default, value, &field, errors);
+            continue;
+        }
+        let mut candidate = root.clone();
+        let mut target = &mut candidate;
+        for part in field.split('.') {
+            target = &mut target[part];
+        }
+        *target = value.clone();
+        if let Err(e) = serde_json::from_value::<ServerConfig>(candidate) {
+            errors.push(format!("{}: {}", field, e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_valid() {
+        assert_eq!(ServerConfig::default().validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn every_mistake_is_reported() {
+        let mut config = ServerConfig {
+            bind: "8080".to_string(),
+            admin_token: Some(" ".to_string()),
+            audit_key: Some(String::new()),
+            ..ServerConfig::default()
+        };
+        config.http.max_connections = 0;
+        config.rate_limit.per_profil.per_minute = 0;
+        config.lockout.max_failures = 0;
+        config.notifications.push(WebhookConfig { url: "discord.com".to_string(), vote_threshold: Some(0), ..WebhookConfig::default() });
+        let errors = config.validate();
+        let fields: Vec<&str> = errors.iter().map(|e| e.split(':').next().unwrap_or_default()).collect();
+        assert_eq!(fields, vec!["bind", "http.max_connections", "rate_limit.per_profil", "lockout", "notifications[0].url",
+            "notifications[0].vote_threshold", "admin_token", "audit_key"]);
+    }
+
+    #[test]
+    fn disabled_limits_may_be_zero() {
+        let empty = Bucket { burst: 0, per_minute: 0 };
+        let config = ServerConfig {
+            rate_limit: RateLimitConfig { enabled: false, per_address: empty, per_profil: empty },
+            lockout: LockoutConfig { enabled: false, max_failures: 0, window_secs: 0, lock_secs: 0 },
+            ..ServerConfig::default()
+        };
+        assert_eq!(config.validate(), Vec::<String>::new());
+    }
+}
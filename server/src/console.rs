@@ -0,0 +1,723 @@
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use serde::{Deserialize, Serialize};
+use common::{ProfilKind, Protection, Target, VoteMode};
+use common::time::{format_unix_time, parse_unix_time};
+use common::version::BuildInfo;
+use crate::filter::Severity;
+use crate::links::ProfilRef;
+use crate::memory::{compact, HeapSize};
+use crate::repair::Fix;
+use crate::app_state::AppState;
+use crate::{anonymity, as_of, diff, jobs, log_level, unix_now, State};
+
+//a line for /admin/cmd_input, the admin_token of the config guards it
+#[derive(Deserialize)]
+pub struct CommandInput {
+    pub token: String,
+    pub line: String,
+}
+
+//what /admin/cmd_input answers, job is the id to follow with /job/{id} when the line started one
+#[derive(Serialize)]
+pub struct CommandOutput {
+    pub output: Vec<String>,
+    pub job: Option<u64>,
+}
+
+//the commands changing the classes or the documents, refused while the server is read-only:
+//CheckData, RepairData, Repair and ReadOnly are the way out, the others only read
+const WRITES: [&str; 34] = ["freeze", "unfreeze", "protect", "transfer", "mergenicknames", "merge-nicknames", "anonymize",
+    "internaljoke", "internal-joke", "publicthreshold", "public-threshold", "votemode", "vote-mode", "votingopens", "voting-opens",
+    "setdeadline", "set-deadline", "votedecay", "vote-decay", "archivestale", "archive-stale", "link", "unlink",
+    "grantguestaccess", "grant-guest-access", "revokeguestaccess", "revoke-guest-access", "forcepasswordchange", "force-password-change",
+    "setkind", "set-kind", "importcsv", "import-csv", "compact"];
+
+//the same with the commands that only change something with some arguments: UndoDelete and Unarchive
+//list without an id, Prompts and ManageFilter have a list of their own
+fn changes_data(command: &str, args: &[&str]) -> bool {
+    match command {
+        "undodelete" | "undo-delete" | "unarchive" => !args.is_empty(),
+        "prompts" => args.get(1).is_some_and(|a| *a != "list"),
+        "managefilter" | "manage-filter" => args.first() != Some(&"list"),
+        command => WRITES.contains(&command),
+    }
+}
+
+//commands typed on the server's standard input, for the person running the instance
+pub fn spawn(state: State) {
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            for output in execute(&state, &line) {
+                println!("{}", output);
+            }
+        }
+    });
+}
+
+//splits on whitespace, "double quotes" keep names with spaces together
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+pub fn execute(state: &State, line: &str) -> Vec<String> {
+    run(state, line, &AtomicBool::new(false)).output
+}
+
+//cancel is the flag of the job running the line, the long commands stop at their next step once it is set
+pub fn run(state: &State, line: &str, cancel: &AtomicBool) -> CommandOutput {
+    let tokens = tokenize(line);
+    let Some((command, args)) = tokens.split_first() else {
+        return CommandOutput { output: Vec::new(), job: None };
+    };
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    tracing::info!(target: "admin", "{}", line); //the history of what was done, kept by the log file
+    if state.is_read_only() && changes_data(&command.to_lowercase(), &args) {
+        let refused = format!("{} refused: the server is read-only, repair the data with RepairData or Repair, then ReadOnly off", command);
+        return CommandOutput { output: vec![refused], job: None };
+    }
+
+    let mut job = None;
+    let output = match (command.to_lowercase().as_str(), args.as_slice()) {
+        ("help", _) => vec![
+            "Help".to_string(),
+            "Version".to_string(),
+            "DiffSnapshots <a.json> <b.json>".to_string(),
+            "Flags".to_string(),
+            "ClearFlags".to_string(),
+            "Freeze <class> \"<name>\" \"<nickname>\"".to_string(),
+            "Unfreeze <class> \"<name>\" \"<nickname>\"".to_string(),
+            "Protect <class> \"<name>\" \"<nickname>\" <open|votes-locked|locked>".to_string(),
+            "Transfer <class> \"<name>\" \"<nickname>\" \"<new author>\"".to_string(),
+            "MergeNicknames <class> \"<name>\" \"<from>\" \"<into>\"".to_string(),
+            "Anonymize <class>".to_string(),
+            "InternalJoke <class> \"<name>\" \"<nickname>\" <on|off>".to_string(),
+            "PublicThreshold <class> <min votes>".to_string(),
+            "VoteMode <class> <single|multi|ranked|default>".to_string(),
+            "VotingOpens <class> <unix time|\"YYYY-MM-DD HH:MM\"|now>".to_string(),
+            "SetDeadline <class> <unix time|\"YYYY-MM-DD HH:MM\"|none>".to_string(),
+            "VoteDecay <class> <half-life in days|off>".to_string(),
+            "Prompts <class> <list|add \"<question>\"|remove <number>>".to_string(),
+            "Addresses <class> \"<name>\"".to_string(),
+            "SharedAddresses <class>".to_string(),
+            "UndoDelete [<id>]".to_string(),
+            "ArchiveStale [<days>]".to_string(),
+            "Unarchive [<id>]".to_string(),
+            "Link <class> \"<name>\" <other class> \"<other name>\"".to_string(),
+            "Unlink <class> \"<name>\"".to_string(),
+            "Links".to_string(),
+            "GrantGuestAccess <class> \"<name>\" <invited in> <until>".to_string(),
+            "RevokeGuestAccess <class> \"<name>\" <invited in>".to_string(),
+            "GuestGrants".to_string(),
+            "AuditPasswords".to_string(),
+            "Unlock [<class> \"<name>\"]".to_string(),
+            "ForcePasswordChange <class> \"<name>\" | --shared".to_string(),
+            "ViewPermissions [\"<name>\"|--class <class>]".to_string(),
+            "SetKind <class> \"<name>\" <student|teacher|staff>".to_string(),
+            "ExportPermissions <file.csv>".to_string(),
+            "ImportCsv <file.csv>".to_string(),
+            "ManageFilter list".to_string(),
+            "ManageFilter --severity <mild|severe> <add|remove> <term>".to_string(),
+            "SetLogLevel [<filter>]".to_string(),
+            "ReloadConfig".to_string(),
+            "MemoryReport".to_string(),
+            "Compact".to_string(),
+            "CheckStorage".to_string(),
+            "CheckData".to_string(),
+            "RepairData <class>".to_string(),
+            "ReadOnly [on|off]".to_string(),
+            "Repair <dedupe-names|drop-orphan-nicknames|reindex-classes> [<class>] [--dry-run]".to_string(),
+            "AsOf <unix time|\"YYYY-MM-DD HH:MM\"> <class> [\"<name>\"]".to_string(),
+            "ExportYearbook <directory> [--stats]".to_string(),
+            "Job <command...>".to_string(),
+            "Jobs".to_string(),
+            "CancelJob <id>".to_string(),
+        ],
+        ("version", _) => {
+            let build = BuildInfo::current();
+            vec![format!("version {}, commit {}, built {}", build.version, build.git_hash, build.build_date)]
+        }
+        ("diffsnapshots" | "diff-snapshots", [a, b]) => diff_snapshots(Path::new(a), Path::new(b)),
+        ("diffsnapshots" | "diff-snapshots", _) => vec!["usage: DiffSnapshots <a.json> <b.json>".to_string()],
+        ("flags", _) => flags(state),
+        ("clearflags" | "clear-flags", _) => {
+            state.abuse.lock().expect("Failed to lock abuse detector").clear_flags();
+            vec!["flags cleared".to_string()]
+        }
+        ("freeze", [class, name, nickname]) => set_protection(state, class, name, nickname, Protection::Locked),
+        ("unfreeze", [class, name, nickname]) => set_protection(state, class, name, nickname, Protection::Open),
+        ("freeze" | "unfreeze", _) => vec![format!("usage: {} <class> \"<name>\" \"<nickname>\"", command)],
+        ("protect", [class, name, nickname, level]) => match Protection::parse(level) {
+            Some(level) => set_protection(state, class, name, nickname, level),
+            None => vec![format!("unknown level: {}, expected open, votes-locked or locked", level)],
+        },
+        ("protect", _) => vec!["usage: Protect <class> \"<name>\" \"<nickname>\" <open|votes-locked|locked>".to_string()],
+        ("transfer", [class, name, nickname, to]) => transfer(state, class, name, nickname, to),
+        ("transfer", _) => vec!["usage: Transfer <class> \"<name>\" \"<nickname>\" \"<new author>\"".to_string()],
+        ("mergenicknames" | "merge-nicknames", [class, name, from, into]) => merge_nicknames(state, class, name, from, into),
+        ("mergenicknames" | "merge-nicknames", _) => vec!["usage: MergeNicknames <class> \"<name>\" \"<from>\" \"<into>\"".to_string()],
+        ("anonymize", [class]) => anonymize(state, class),
+        ("anonymize", _) => vec!["usage: Anonymize <class>".to_string()],
+        ("internaljoke" | "internal-joke", [class, name, nickname, "on" | "off"]) => set_internal_joke(state, class, name, nickname, args[3] == "on"),
+        ("internaljoke" | "internal-joke", _) => vec!["usage: InternalJoke <class> \"<name>\" \"<nickname>\" <on|off>".to_string()],
+        ("publicthreshold" | "public-threshold", [class, min_votes]) => match min_votes.parse() {
+            Ok(min_votes) => set_public_threshold(state, class, min_votes),
+            Err(_) => vec![format!("invalid vote count: {}", min_votes)],
+        },
+        ("publicthreshold" | "public-threshold", _) => vec!["usage: PublicThreshold <class> <min votes>".to_string()],
+        ("votemode" | "vote-mode", [class, "default"]) => set_vote_mode(state, class, None),
+        ("votemode" | "vote-mode", [class, mode]) => match VoteMode::parse(mode) {
+            Some(mode) => set_vote_mode(state, class, Some(mode)),
+            None => vec![format!("invalid vote mode: {}", mode)],
+        },
+        ("votingopens" | "voting-opens", [class, "now"]) => state.set_voting_opens(class, None),
+        ("votingopens" | "voting-opens", [class, time]) => match parse_unix_time(time) {
+            Some(time) => state.set_voting_opens(class, Some(time)),
+            None => vec![format!("invalid time: {}, expected unix seconds or \"YYYY-MM-DD HH:MM\" (UTC)", time)],
+        },
+        ("votingopens" | "voting-opens", _) => vec!["usage: VotingOpens <class> <unix time|\"YYYY-MM-DD HH:MM\"|now>".to_string()],
+        ("setdeadline" | "set-deadline", [class, "none"]) => state.set_deadline(class, None),
+        ("setdeadline" | "set-deadline", [class, time]) => match parse_unix_time(time) {
+            Some(time) => state.set_deadline(class, Some(time)),
+            None => vec![format!("invalid time: {}, expected unix seconds or \"YYYY-MM-DD HH:MM\" (UTC)", time)],
+        },
+        ("setdeadline" | "set-deadline", _) => vec!["usage: SetDeadline <class> <unix time|\"YYYY-MM-DD HH:MM\"|none>, the class is read-only once it is past".to_string()],
+        ("votedecay" | "vote-decay", [class, "off"]) => state.set_vote_decay(class, None),
+        ("votedecay" | "vote-decay", [class, days]) => match days.parse() {
+            Ok(days) if days > 0 => state.set_vote_decay(class, Some(days)),
+            _ => vec![format!("invalid half-life: {}, expected a number of days", days)],
+        },
+        ("votedecay" | "vote-decay", _) => vec!["usage: VoteDecay <class> <half-life in days|off>, older votes count less in the displayed rankings".to_string()],
+        ("undodelete" | "undo-delete", []) => state.list_deleted(),
+        ("undodelete" | "undo-delete", [id]) => match id.parse() {
+            Ok(id) => vec![state.restore_nickname(id, "console").unwrap_or_else(|e| e)],
+            Err(_) => vec![format!("invalid id: {}", id)],
+        },
+        ("undodelete" | "undo-delete", _) => vec!["usage: UndoDelete [<id>], without id lists the last deletions".to_string()],
+        ("archivestale" | "archive-stale", []) => {
+            let after_days = state.config.lock().expect("Failed to lock config").archive.after_days;
+            archive_stale(state, after_days)
+        }
+        ("archivestale" | "archive-stale", [days]) => match days.parse() {
+            Ok(days) => archive_stale(state, days),
+            Err(_) => vec![format!("invalid day count: {}", days)],
+        },
+        ("archivestale" | "archive-stale", _) => vec!["usage: ArchiveStale [<days>], the archive.after_days of the config by default".to_string()],
+        ("unarchive", []) => state.list_archived(),
+        ("unarchive", [id]) => match id.parse() {
+            Ok(id) => vec![state.unarchive(id, "console").unwrap_or_else(|e| e)],
+            Err(_) => vec![format!("invalid id: {}", id)],
+        },
+        ("unarchive", _) => vec!["usage: Unarchive [<id>], without id lists the last archived propositions".to_string()],
+        ("votemode" | "vote-mode", _) => vec!["usage: VoteMode <class> <single|multi|ranked|default>".to_string()],
+        ("prompts", [class, "list"]) => list_prompts(state, class),
+        ("prompts", [class, "add", question]) => add_prompt(state, class, question),
+        ("prompts", [class, "remove", number]) => match number.parse() {
+            Ok(number) => remove_prompt(state, class, number),
+            Err(_) => vec![format!("invalid number: {}", number)],
+        },
+        ("prompts", _) => vec!["usage: Prompts <class> <list|add \"<question>\"|remove <number>>".to_string()],
+        ("addresses", [class, name]) => addresses(state, class, name),
+        ("addresses", _) => vec!["usage: Addresses <class> \"<name>\"".to_string()],
+        ("sharedaddresses" | "shared-addresses", [class]) => shared_addresses(state, class),
+        ("sharedaddresses" | "shared-addresses", _) => vec!["usage: SharedAddresses <class>".to_string()],
+        ("link", [class, name, other_class, other_name]) => link(state, class, name, other_class, other_name),
+        ("link", _) => vec!["usage: Link <class> \"<name>\" <other class> \"<other name>\"".to_string()],
+        ("unlink", [class, name]) => {
+            let profil = ProfilRef { class: class.to_string(), name: name.to_string() };
+            if state.links.lock().expect("Failed to lock links").unlink(&profil) {
+                vec![format!("{} in {} unlinked", name, class)]
+            } else {
+                vec![format!("{} in {} wasn't linked", name, class)]
+            }
+        }
+        ("unlink", _) => vec!["usage: Unlink <class> \"<name>\"".to_string()],
+        ("links", _) => links(state),
+        ("grantguestaccess" | "grant-guest-access", [class, name, host, until]) => grant_guest_access(state, class, name, host, until),
+        ("grantguestaccess" | "grant-guest-access", _) => vec!["usage: GrantGuestAccess <class> \"<name>\" <invited in> <unix time|\"YYYY-MM-DD HH:MM\">".to_string()],
+        ("revokeguestaccess" | "revoke-guest-access", [class, name, host]) => {
+            let guest = ProfilRef { class: class.to_string(), name: name.to_string() };
+            if state.grants.lock().expect("Failed to lock grants").revoke(&guest, host) {
+                vec![format!("{} ({}) can't vote in {} anymore", name, class, host)]
+            } else {
+                vec![format!("{} ({}) wasn't invited in {}", name, class, host)]
+            }
+        }
+        ("revokeguestaccess" | "revoke-guest-access", _) => vec!["usage: RevokeGuestAccess <class> \"<name>\" <invited in>".to_string()],
+        ("guestgrants" | "guest-grants", _) => guest_grants(state),
+        ("auditpasswords" | "audit-passwords", _) => state.audit_passwords(),
+        ("unlock", []) => locked_profils(state),
+        ("unlock", [class, name]) => {
+            let profil = ProfilRef { class: class.to_string(), name: name.to_string() };
+            if state.lockouts.lock().expect("Failed to lock lockouts").unlock(&profil) {
+                vec![format!("{} in {} unlocked", name, class)]
+            } else {
+                vec![format!("{} in {} wasn't locked, their failed logins are forgotten", name, class)]
+            }
+        }
+        ("unlock", _) => vec!["usage: Unlock [<class> \"<name>\"], without a profil lists the locked ones".to_string()],
+        ("forcepasswordchange" | "force-password-change", ["--shared"]) => state.force_shared_password_change(),
+        ("forcepasswordchange" | "force-password-change", [class, name]) => state.force_password_change(class, name),
+        ("forcepasswordchange" | "force-password-change", _) => vec!["usage: ForcePasswordChange <class> \"<name>\" | --shared".to_string()],
+        ("viewpermissions" | "view-permissions", []) => state.view_permissions(None, None),
+        ("viewpermissions" | "view-permissions", ["--class", class]) => state.view_permissions(Some(class), None),
+        ("viewpermissions" | "view-permissions", [name]) => state.view_permissions(None, Some(name)),
+        ("viewpermissions" | "view-permissions", _) => vec!["usage: ViewPermissions [\"<name>\"|--class <class>]".to_string()],
+        ("setkind" | "set-kind", [class, name, kind]) => match ProfilKind::parse(kind) {
+            Some(kind) => state.set_kind(class, name, kind),
+            None => vec![format!("unknown kind: {}, expected student, teacher or staff", kind)],
+        },
+        ("setkind" | "set-kind", _) => vec!["usage: SetKind <class> \"<name>\" <student|teacher|staff>".to_string()],
+        ("exportpermissions" | "export-permissions", [file]) => state.export_permissions(Path::new(file)),
+        ("exportpermissions" | "export-permissions", _) => vec!["usage: ExportPermissions <file.csv>".to_string()],
+        ("importcsv" | "import-csv", [file]) => state.import_csv(Path::new(file), cancel),
+        ("importcsv" | "import-csv", _) => vec!["usage: ImportCsv <file.csv>, one \"name,class,password,kind\" per line, an empty password is generated, kind is student (default), teacher or staff".to_string()],
+        ("managefilter" | "manage-filter", ["list"]) => filter_list(state),
+        ("managefilter" | "manage-filter", ["--severity", severity, operation, term @ ..]) if !term.is_empty() => {
+            manage_filter(state, severity, operation, &term.join(" "))
+        }
+        ("managefilter" | "manage-filter", _) => vec!["usage: ManageFilter list | ManageFilter --severity <mild|severe> <add|remove> <term>".to_string()],
+        ("setloglevel" | "set-log-level", []) => vec![format!("log filter is \"{}\"", log_level::current())],
+        ("setloglevel" | "set-log-level", [filter]) => vec![log_level::set(filter).unwrap_or_else(|e| e)],
+        ("setloglevel" | "set-log-level", _) => vec!["usage: SetLogLevel [<filter>], same syntax as RUST_LOG, \"info,actix_web=debug\"".to_string()],
+        ("reloadconfig" | "reload-config", _) => state.reload_config(),
+        ("memoryreport" | "memory-report", _) => memory_report(state),
+        ("compact", _) => compact_classes(state),
+        ("checkstorage" | "check-storage", _) => state.check_storage(),
+        ("checkdata" | "check-data", _) => state.check_data().0,
+        ("repairdata" | "repair-data", [class]) => state.repair_data(class),
+        ("repairdata" | "repair-data", _) => vec!["usage: RepairData <class>, merges the duplicates and drops the dangling references then saves".to_string()],
+        ("readonly" | "read-only", []) => vec![format!("read-only: {}", if state.is_read_only() { "on" } else { "off" })],
+        ("readonly" | "read-only", ["on"]) => state.set_read_only(true),
+        ("readonly" | "read-only", ["off"]) => state.set_read_only(false),
+        ("repair", [fix, options @ ..]) if options.iter().filter(|o| **o != "--dry-run").count() <= 1 => match Fix::parse(fix) {
+            Some(fix) => {
+                let class = options.iter().find(|o| **o != "--dry-run").copied();
+                state.repair(fix, class, options.contains(&"--dry-run"))
+            }
+            None => vec![format!("unknown repair: {}, expected dedupe-names, drop-orphan-nicknames or reindex-classes", fix)],
+        },
+        ("repair", _) => vec!["usage: Repair <dedupe-names|drop-orphan-nicknames|reindex-classes> [<class>] [--dry-run], every class without one".to_string()],
+        ("readonly" | "read-only", _) => vec!["usage: ReadOnly [on|off], without argument tells whether the server refuses changes".to_string()],
+        ("asof" | "as-of", [time, class]) => show_as_of(state, time, class, None),
+        ("asof" | "as-of", [time, class, name]) => show_as_of(state, time, class, Some(name)),
+        ("asof" | "as-of", _) => vec!["usage: AsOf <unix time|\"YYYY-MM-DD HH:MM\"> <class> [\"<name>\"]".to_string()],
+        ("exportyearbook" | "export-yearbook", [dir, options @ ..]) if options.iter().all(|o| *o == "--stats") => {
+            let (dir, with_stats) = (dir.to_string(), !options.is_empty());
+            let id = jobs::spawn(state, format!("ExportYearbook {}", dir), move |state, cancel| state.export_yearbook(Path::new(&dir), with_stats, cancel));
+            job = Some(id);
+            vec![format!("job {} started, Jobs to follow it", id)]
+        }
+        ("exportyearbook" | "export-yearbook", _) => vec!["usage: ExportYearbook <directory> [--stats]".to_string()],
+        ("job", [_, ..]) => {
+            let line = args.iter().map(|a| if a.contains(char::is_whitespace) { format!("\"{}\"", a) } else { a.to_string() }).collect::<Vec<_>>().join(" ");
+            let id = jobs::spawn(state, line.clone(), move |state, cancel| run(state, &line, cancel).output);
+            job = Some(id);
+            vec![format!("job {} started, Jobs to follow it", id)]
+        }
+        ("job", _) => vec!["usage: Job <command...>".to_string()],
+        ("jobs", _) => list_jobs(state),
+        ("canceljob" | "cancel-job", [id]) => match id.parse() {
+            Ok(id) if state.jobs.lock().expect("Failed to lock jobs").cancel(id) => vec![format!("job {} cancelled, it stops at its next step", id)],
+            Ok(id) => vec![format!("job {} isn't running", id)],
+            Err(_) => vec![format!("invalid job id: {}", id)],
+        },
+        ("canceljob" | "cancel-job", _) => vec!["usage: CancelJob <id>".to_string()],
+        _ => vec![format!("unknown command: {}, type Help for the list", command)],
+    };
+    CommandOutput { output, job }
+}
+
+fn flags(state: &AppState) -> Vec<String> {
+    let abuse = state.abuse.lock().expect("Failed to lock abuse detector");
+    if abuse.flags().is_empty() {
+        return vec!["no flag".to_string()];
+    }
+    abuse.flags().iter()
+        .map(|f| {
+            let propositions: Vec<String> = f.propositions.iter().map(|(name, nickname)| format!("{}: \"{}\"", name, nickname)).collect();
+            format!("[{}] {:?} in {}: {} ({})", format_unix_time(f.time), f.kind, f.class, f.description, propositions.join(", "))
+        })
+        .collect()
+}
+
+fn locked_profils(state: &AppState) -> Vec<String> {
+    let locked = state.lockouts.lock().expect("Failed to lock lockouts").list(unix_now());
+    if locked.is_empty() {
+        return vec!["no locked profil".to_string()];
+    }
+    locked.iter().map(|(p, until)| format!("{} in {} locked until {} UTC", p.name, p.class, format_unix_time(*until))).collect()
+}
+
+fn show_as_of(state: &AppState, time: &str, class: &str, name: Option<&str>) -> Vec<String> {
+    let Some(time) = parse_unix_time(time) else {
+        return vec![format!("invalid time: {}, expected unix seconds or \"YYYY-MM-DD HH:MM\" (UTC)", time)];
+    };
+    let Some(group) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let past = as_of::as_of(&group.read().expect("Failed to lock data").participants, time);
+
+    let mut lines = vec![format!("{} at {} UTC, deleted propositions are not kept and can't show up", class, format_unix_time(time))];
+    for (profil, nicknames) in past.iter().filter(|(p, _)| name.is_none_or(|n| n == p.as_str())) {
+        lines.push(format!("{}:", profil));
+        for nickname in nicknames {
+            lines.push(format!("  \"{}\" {} votes, {:?}, by {}", nickname.nickname, nickname.votes, nickname.protection, nickname.author.as_deref().unwrap_or("?")));
+        }
+    }
+    lines
+}
+
+fn list_jobs(state: &AppState) -> Vec<String> {
+    let jobs = state.jobs.lock().expect("Failed to lock jobs");
+    let lines: Vec<String> = jobs.list()
+        .map(|j| format!("[{}] {:?} started {}: {}", j.id, j.state, format_unix_time(j.started), j.description))
+        .collect();
+    if lines.is_empty() {
+        vec!["no job".to_string()]
+    } else {
+        lines
+    }
+}
+
+fn set_protection(state: &AppState, class: &str, name: &str, nickname: &str, level: Protection) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let mut lock = class.write().expect("Failed to lock data");
+    if AppState::set_protection(&mut lock.participants, &Target::Profil(name.to_string()), nickname, level, "console") {
+        lock.save();
+        vec![format!("\"{}\" for {} is now {:?}", nickname, name, level)]
+    } else {
+        vec![format!("\"{}\" not found for {}", nickname, name)]
+    }
+}
+
+fn transfer(state: &AppState, class: &str, name: &str, nickname: &str, to: &str) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let mut lock = class.write().expect("Failed to lock data");
+    if lock.participants.anonymous_authors {
+        return vec![format!("{} is anonymized, its propositions can't be handed over", lock.name)];
+    }
+    if AppState::transfer(&mut lock.participants, name, nickname, to, "console") {
+        lock.save();
+        vec![format!("\"{}\" for {} now belongs to {}", nickname, name, to)]
+    } else {
+        vec![format!("\"{}\" for {} or {} not found", nickname, name, to)]
+    }
+}
+
+fn merge_nicknames(state: &AppState, class: &str, name: &str, from: &str, into: &str) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let mut lock = class.write().expect("Failed to lock data");
+    match AppState::merge(&mut lock.participants, name, from, into, "console") {
+        Some(moved) => {
+            lock.save();
+            vec![format!("\"{}\" merged into \"{}\" for {}, {} votes moved", from, into, name, moved)]
+        }
+        None => vec![format!("\"{}\" and \"{}\" must be two propositions of {}", from, into, name)],
+    }
+}
+
+fn anonymize(state: &AppState, class: &str) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let mut lock = class.write().expect("Failed to lock data");
+    let (rewritten, lost) = anonymity::anonymize(&mut lock.participants);
+    lock.save();
+    let mut lines = vec![format!("{} authors replaced by their hash, new propositions of {} will be anonymous", rewritten, lock.name)];
+    if lost > 0 {
+        lines.push(format!("{} past authors can't be recognized anymore, their passwords are hashed", lost));
+    }
+    if !lock.participants.hashed_passwords {
+        lines.push("the passwords are stored as is, the admins can still compute the authors until --migrate-passwords ran".to_string());
+    }
+    lines
+}
+
+fn set_internal_joke(state: &AppState, class: &str, name: &str, nickname: &str, internal_joke: bool) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let mut lock = class.write().expect("Failed to lock data");
+    let found = lock.participants.profiles.get_mut(name)
+        .and_then(|(_, nicknames)| nicknames.iter_mut().find(|n| n.nickname == nickname));
+    let Some(found) = found else {
+        return vec![format!("\"{}\" not found for {}", nickname, name)];
+    };
+    found.internal_joke = internal_joke;
+    lock.save();
+    if internal_joke {
+        vec![format!("\"{}\" for {} is now hidden from the public views", nickname, name)]
+    } else {
+        vec![format!("\"{}\" for {} is shown in the public views again", nickname, name)]
+    }
+}
+
+fn set_public_threshold(state: &AppState, class: &str, min_votes: usize) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let mut lock = class.write().expect("Failed to lock data");
+    lock.participants.public_min_votes = min_votes;
+    lock.save();
+    vec![format!("the public views of {} now show propositions with at least {} votes", lock.name, min_votes)]
+}
+
+//the votes already cast stay as they are, a voter with several loses the others at their next vote in single mode
+fn archive_stale(state: &AppState, after_days: u64) -> Vec<String> {
+    let lines = state.archive_stale(after_days);
+    if lines.is_empty() {
+        return vec![format!("no proposition without votes for more than {} days", after_days)];
+    }
+    lines
+}
+
+fn set_vote_mode(state: &AppState, class: &str, mode: Option<VoteMode>) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let mut lock = class.write().expect("Failed to lock data");
+    lock.participants.vote_mode = mode;
+    lock.save();
+    let mode = state.vote_mode(&lock.participants);
+    vec![format!("{} now votes in {} mode{}", lock.name, format!("{:?}", mode).to_lowercase(), if lock.participants.vote_mode.is_none() { ", the server's" } else { "" })]
+}
+
+fn list_prompts(state: &AppState, class: &str) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let lock = class.read().expect("Failed to lock data");
+    if lock.participants.prompts.is_empty() {
+        return vec![format!("no prompt in {}", lock.name)];
+    }
+    lock.participants.prompts.iter().enumerate()
+        .map(|(i, prompt)| format!("{}: {}", i + 1, prompt))
+        .collect()
+}
+
+fn add_prompt(state: &AppState, class: &str, question: &str) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let question = question.trim();
+    if question.is_empty() {
+        return vec!["empty question".to_string()];
+    }
+    let mut lock = class.write().expect("Failed to lock data");
+    lock.participants.prompts.push(question.to_string());
+    lock.save();
+    vec![format!("prompt {} added to {}", lock.participants.prompts.len(), lock.name)]
+}
+
+//number as shown by Prompts <class> list, starting at 1
+fn remove_prompt(state: &AppState, class: &str, number: usize) -> Vec<String> {
+    let Some(class) = state.classes.get(class) else {
+        return vec![format!("unknown class: {}", class)];
+    };
+    let mut lock = class.write().expect("Failed to lock data");
+    if number == 0 || number > lock.participants.prompts.len() {
+        return vec![format!("no prompt {} in {}", number, lock.name)];
+    }
+    let removed = lock.participants.prompts.remove(number - 1);
+    lock.save();
+    vec![format!("removed from {}: {}", lock.name, removed)]
+}
+
+fn diff_snapshots(a: &Path, b: &Path) -> Vec<String> {
+    let old = match diff::load_snapshot(a) {
+        Ok(group) => group,
+        Err(e) => return vec![format!("Failed to load {}: {:?}", a.display(), e)],
+    };
+    let new = match diff::load_snapshot(b) {
+        Ok(group) => group,
+        Err(e) => return vec![format!("Failed to load {}: {:?}", b.display(), e)],
+    };
+
+    let lines = diff::diff_groups(&old, &new);
+    if lines.is_empty() {
+        vec!["no difference".to_string()]
+    } else {
+        lines
+    }
+}
+
+fn addresses(state: &AppState, class: &str, name: &str) -> Vec<String> {
+    let addresses = state.ip_log.lock().expect("Failed to lock ip log").addresses_of(class, name);
+    if addresses.is_empty() {
+        return vec![format!("no address recorded for {} (is ip_log.enabled set?)", name)];
+    }
+    addresses.iter()
+        .map(|(address, u)| format!("{}: {} requests, first seen {}, last seen {}", address, u.requests, format_unix_time(u.first_seen), format_unix_time(u.last_seen)))
+        .collect()
+}
+
+fn shared_addresses(state: &AppState, class: &str) -> Vec<String> {
+    let shared = state.ip_log.lock().expect("Failed to lock ip log").shared_addresses(class);
+    if shared.is_empty() {
+        return vec!["no address shared between accounts".to_string()];
+    }
+    shared.iter()
+        .map(|(address, names)| format!("{}: {}", address, names.join(", ")))
+        .collect()
+}
+
+fn link(state: &AppState, class: &str, name: &str, other_class: &str, other_name: &str) -> Vec<String> {
+    for (class, name) in [(class, name), (other_class, other_name)] {
+        let exists = state.classes.get(class)
+            .is_some_and(|c| c.read().expect("Failed to lock data").participants.profiles.contains_key(name));
+        if !exists {
+            return vec![format!("{} not found in {}", name, class)];
+        }
+    }
+
+    let a = ProfilRef { class: class.to_string(), name: name.to_string() };
+    let b = ProfilRef { class: other_class.to_string(), name: other_name.to_string() };
+    let mut links = state.links.lock().expect("Failed to lock links");
+    links.link(a.clone(), b);
+    let linked: Vec<String> = links.linked(&a).iter().map(|p| format!("{} ({})", p.name, p.class)).collect();
+    vec![format!("linked: {}", linked.join(", "))]
+}
+
+fn links(state: &AppState) -> Vec<String> {
+    let links = state.links.lock().expect("Failed to lock links");
+    if links.groups().is_empty() {
+        return vec!["no link".to_string()];
+    }
+    links.groups().iter()
+        .map(|group| group.iter().map(|p| format!("{} ({})", p.name, p.class)).collect::<Vec<_>>().join(" = "))
+        .collect()
+}
+
+fn grant_guest_access(state: &AppState, class: &str, name: &str, host: &str, until: &str) -> Vec<String> {
+    let Some(expires) = parse_unix_time(until) else {
+        return vec![format!("invalid time: {}, expected unix seconds or \"YYYY-MM-DD HH:MM\" (UTC)", until)];
+    };
+    let known = state.classes.get(class)
+        .is_some_and(|group| group.read().expect("Failed to lock data").participants.profiles.contains_key(name));
+    if !known {
+        return vec![format!("unknown profil: {} in {}", name, class)];
+    }
+    if class == host || !state.classes.contains_key(host) {
+        return vec![format!("invalid class to invite in: {}", host)];
+    }
+    let guest = ProfilRef { class: class.to_string(), name: name.to_string() };
+    state.grants.lock().expect("Failed to lock grants").grant(guest, host.to_string(), expires);
+    vec![format!("{} ({}) can vote in {} until {} UTC", name, class, host, format_unix_time(expires))]
+}
+
+fn guest_grants(state: &AppState) -> Vec<String> {
+    let grants = state.grants.lock().expect("Failed to lock grants");
+    if grants.list().is_empty() {
+        return vec!["no guest grant".to_string()];
+    }
+    let now = unix_now();
+    grants.list().iter()
+        .map(|g| format!("{} ({}) in {} until {} UTC{}", g.guest.name, g.guest.class, g.class, format_unix_time(g.expires), if g.expires <= now { ", expired" } else { "" }))
+        .collect()
+}
+
+fn filter_list(state: &AppState) -> Vec<String> {
+    let filter = state.filter.lock().expect("Failed to lock filter");
+    [Severity::Mild, Severity::Severe].iter()
+        .map(|s| format!("{:?}: {}", s, filter.terms(*s).iter().cloned().collect::<Vec<_>>().join(", ")))
+        .collect()
+}
+
+fn manage_filter(state: &AppState, severity: &str, operation: &str, term: &str) -> Vec<String> {
+    let Some(severity) = Severity::parse(severity) else {
+        return vec![format!("unknown severity: {}, expected mild or severe", severity)];
+    };
+    let mut filter = state.filter.lock().expect("Failed to lock filter");
+    match operation {
+        "add" if filter.add(severity, term) => vec![format!("\"{}\" added to {:?}", term, severity)],
+        "add" => vec![format!("\"{}\" is already in {:?}", term, severity)],
+        "remove" if filter.remove(severity, term) => vec![format!("\"{}\" removed from {:?}", term, severity)],
+        "remove" => vec![format!("\"{}\" isn't in {:?}", term, severity)],
+        _ => vec![format!("unknown operation: {}, expected add or remove", operation)],
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn memory_report(state: &AppState) -> Vec<String> {
+    let mut names: Vec<&String> = state.classes.keys().collect();
+    state.collation.sort(&mut names);
+
+    let mut lines = Vec::new();
+    let mut total = 0;
+    for name in names {
+        let lock = state.classes[name].read().expect("Failed to lock data");
+        let size = lock.participants.heap_size();
+        let propositions: usize = lock.participants.profiles.values().map(|(_, n)| n.len()).sum();
+        lines.push(format!("class {}: {} profils, {} propositions, {}", name, lock.participants.profiles.len(), propositions, format_bytes(size)));
+        total += size;
+    }
+
+    let stores = [
+        ("abuse detector", state.abuse.lock().expect("Failed to lock abuse detector").heap_size()),
+        ("ip log", state.ip_log.lock().expect("Failed to lock ip log").heap_size()),
+        ("links", state.links.lock().expect("Failed to lock links").heap_size()),
+        ("filter", state.filter.lock().expect("Failed to lock filter").heap_size()),
+    ];
+    for (store, size) in stores {
+        lines.push(format!("{}: {}", store, format_bytes(size)));
+        total += size;
+    }
+    lines.push(format!("total: {} (estimate)", format_bytes(total)));
+    lines
+}
+
+fn compact_classes(state: &AppState) -> Vec<String> {
+    let mut before = 0;
+    let mut after = 0;
+    let mut removed = 0;
+    for class in state.classes.values() {
+        let mut lock = class.write().expect("Failed to lock data");
+        before += lock.participants.heap_size();
+        let dropped = compact(&mut lock.participants);
+        after += lock.participants.heap_size();
+        if dropped > 0 {
+            lock.save();
+            removed += dropped;
+        }
+    }
+    vec![format!("{} empty propositions removed, {} -> {}", removed, format_bytes(before), format_bytes(after))]
+}
@@ -0,0 +1,83 @@
+use common::{Group, Nickname, NicknameEventKind};
+use crate::app_state::AppState;
+
+const DAY_SECS: f64 = 24.0 * 3600.0;
+
+//when the vote of voter was cast, the ones kept from before the times were recorded date from the proposition
+fn cast_at(nickname: &Nickname, voter: &str) -> Option<u64> {
+    nickname.voted_at.get(voter).copied().or_else(|| {
+        nickname.history.iter().find_map(|e| matches!(e.kind, NicknameEventKind::Created { .. }).then_some(e.time))
+    })
+}
+
+//the votes of nickname as the displayed rankings count them, a vote weighs half as much after each half-life
+//of the class and one without a known time counts fully; none when the class has no decay, the raw count stays
+pub fn weighted_votes(group: &Group, nickname: &Nickname, now: u64) -> Option<f64> {
+    let half_life = f64::from(group.vote_half_life_days?) * DAY_SECS;
+    Some(nickname.votes.iter()
+        .map(|voter| cast_at(nickname, voter).map_or(1.0, |time| 0.5f64.powf(now.saturating_sub(time) as f64 / half_life)))
+        .sum())
+}
+
+//what the displayed rankings order by
+pub fn score(group: &Group, nickname: &Nickname, now: u64) -> f64 {
+    weighted_votes(group, nickname, now).unwrap_or(nickname.votes.len() as f64)
+}
+
+impl AppState {
+    //VoteDecay, none counts every vote fully again, the votes themselves are never changed
+    pub fn set_vote_decay(&self, class: &str, half_life_days: Option<u32>) -> Vec<String> {
+        let Some(group) = self.classes.get(class) else {
+            return vec![format!("unknown class: {}", class)];
+        };
+        let mut lock = group.write().expect("Failed to lock data");
+        lock.participants.vote_half_life_days = half_life_days;
+        lock.save();
+        match half_life_days {
+            Some(days) => vec![format!("the votes of {} count half as much in the rankings after each {} days", class, days)],
+            None => vec![format!("every vote of {} counts fully", class)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{Group, Nickname, NicknameEvent, NicknameEventKind};
+    use super::*;
+
+    const NOW: u64 = 100 * DAY_SECS as u64;
+
+    //one vote from now, one from ten days ago, and one kept from before the times were recorded
+    fn nickname() -> Nickname {
+        let mut nickname = Nickname {
+            history: vec![NicknameEvent { time: NOW - 20 * DAY_SECS as u64, kind: NicknameEventKind::Created { by: "Alice".to_string() } }],
+            ..Nickname::default()
+        };
+        nickname.add_vote("Alice", NOW);
+        nickname.add_vote("Bob", NOW - 10 * DAY_SECS as u64);
+        nickname.votes.push("Carol".to_string());
+        nickname
+    }
+
+    #[test]
+    fn no_decay_counts_the_votes() {
+        let group = Group::default();
+        assert_eq!(weighted_votes(&group, &nickname(), NOW), None);
+        assert_eq!(score(&group, &nickname(), NOW), 3.0);
+    }
+
+    #[test]
+    fn votes_halve_each_half_life() {
+        let group = Group { vote_half_life_days: Some(10), ..Group::default() };
+        //1 + 0.5 + 0.25, the vote without a time dates from the proposition
+        assert_eq!(weighted_votes(&group, &nickname(), NOW), Some(1.75));
+        assert_eq!(score(&group, &nickname(), NOW), 1.75);
+    }
+
+    #[test]
+    fn votes_without_any_time_count_fully() {
+        let group = Group { vote_half_life_days: Some(1), ..Group::default() };
+        let nickname = Nickname { votes: vec!["Alice".to_string(), "Bob".to_string()], ..Nickname::default() };
+        assert_eq!(weighted_votes(&group, &nickname, NOW), Some(2.0));
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::Path;
+use common::Group;
+
+pub fn load_snapshot(path: &Path) -> anyhow::Result<Group> {
+    let json = File::open(path)?;
+    Ok(serde_json::from_reader(json)?)
+}
+
+//human readable list of what changed between two saves of the same class
+pub fn diff_groups(old: &Group, new: &Group) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for name in old.profiles.keys().filter(|n| !new.profiles.contains_key(*n)) {
+        lines.push(format!("- profil {}", name));
+    }
+    for name in new.profiles.keys().filter(|n| !old.profiles.contains_key(*n)) {
+        lines.push(format!("+ profil {}", name));
+    }
+
+    for (name, (_, new_nicknames)) in &new.profiles {
+        let Some((_, old_nicknames)) = old.profiles.get(name) else {
+            continue;
+        };
+
+        for old_nickname in old_nicknames {
+            if !new_nicknames.iter().any(|n| n.nickname == old_nickname.nickname) {
+                lines.push(format!("- {}: \"{}\" ({} votes)", name, old_nickname.nickname, old_nickname.votes.len()));
+            }
+        }
+
+        for new_nickname in new_nicknames {
+            let Some(old_nickname) = old_nicknames.iter().find(|n| n.nickname == new_nickname.nickname) else {
+                lines.push(format!("+ {}: \"{}\" ({} votes)", name, new_nickname.nickname, new_nickname.votes.len()));
+                continue;
+            };
+
+            let old_votes: BTreeSet<&String> = old_nickname.votes.iter().collect();
+            let new_votes: BTreeSet<&String> = new_nickname.votes.iter().collect();
+            let gained: Vec<&str> = new_votes.difference(&old_votes).map(|v| v.as_str()).collect();
+            let lost: Vec<&str> = old_votes.difference(&new_votes).map(|v| v.as_str()).collect();
+            if !gained.is_empty() || !lost.is_empty() {
+                lines.push(format!("~ {}: \"{}\" {} -> {} votes (gained: [{}], lost: [{}])",
+                    name, new_nickname.nickname, old_votes.len(), new_votes.len(), gained.join(", "), lost.join(", ")));
+            }
+        }
+    }
+
+    lines
+}
@@ -0,0 +1,63 @@
+use std::net::IpAddr;
+use common::Group;
+use common::packets::c2s::ChangeDisplayName;
+use common::packets::s2c::PersonProfileResponse;
+use crate::app_state::AppState;
+use crate::messages::Message;
+use crate::request_id;
+use crate::suggest::normalize;
+use crate::unix_now;
+
+const MAX_LENGTH: usize = 30; //same limit as the login field of the client
+
+//another participant already goes by that name, either as profil name or as shown name
+fn is_taken(group: &Group, editor: &str, shown: &str) -> bool {
+    let shown = normalize(shown);
+    group.profiles.keys()
+        .filter(|name| *name != editor)
+        .any(|name| normalize(name) == shown || group.display_names.get(name).is_some_and(|(other, _)| normalize(other) == shown))
+}
+
+impl AppState {
+    pub fn change_display_name(&self, change: &ChangeDisplayName, address: Option<IpAddr>) -> PersonProfileResponse {
+        let ChangeDisplayName {
+            class,
+            editor,
+            password,
+            display_name,
+        } = change;
+        println!("[{}] change_display_name: {} to {:?} in class {}", request_id::current(), editor, display_name, class);
+
+        let Some(group) = self.classes.get(class) else {
+            return PersonProfileResponse::default();
+        };
+        let mut lock = group.write().expect("Failed to lock data");
+        if !self.check_login(class, &lock.participants, editor, password) {
+            return PersonProfileResponse::default();
+        }
+        if let Some(refused) = Self::refuse_until_changed(&lock.participants, editor, password, &vec![editor.clone()]) {
+            return refused;
+        }
+        self.record_address(class, editor, address);
+
+        let shown = display_name.trim();
+        let now = unix_now();
+        let refusal = match lock.participants.display_names.get(editor) {
+            _ if shown.chars().count() > MAX_LENGTH => Some(Message::DisplayNameTooLong(MAX_LENGTH).localized()),
+            Some((_, changed)) if now < changed + self.settings.read().expect("Failed to lock settings").display_name_cooldown_secs => Some(Message::DisplayNameTooRecent.localized()),
+            _ if !shown.is_empty() && is_taken(&lock.participants, editor, shown) => Some(Message::DisplayNameTaken.localized()),
+            _ => None,
+        };
+        if refusal.is_none() {
+            //back to the profil name, the time is kept so the cooldown still applies
+            let shown = if shown.is_empty() { editor.as_str() } else { shown };
+            lock.participants.display_names.insert(editor.clone(), (shown.to_string(), now));
+            lock.save();
+            self.notify(class, editor);
+        }
+
+        let mut response = Self::group_to_response_custom(&lock.participants, editor, password, &vec![editor.clone()]);
+        response.error = refusal;
+        response
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::OnceLock;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use serde_json::{json, Value};
+use common::packets::c2s::ClientError;
+use common::version::BuildInfo;
+use crate::config::ErrorReportConfig;
+use crate::request_id::{self, RequestContext};
+use crate::unix_now;
+
+const QUEUE: usize = 64; //events waiting to be sent, the others are dropped so a panic loop can't pile them up
+const MAX_TEXT: usize = 2000; //of what a client sends
+
+static REPORTER: OnceLock<Reporter> = OnceLock::new();
+
+struct Reporter {
+    events: SyncSender<Value>,
+    environment: String,
+}
+
+//where the events of a dsn go, the store endpoint every Sentry-compatible server has
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    key: String,
+}
+
+impl Endpoint {
+    fn parse(dsn: &str) -> anyhow::Result<Self> {
+        let (scheme, rest) = dsn.split_once("://").ok_or_else(|| anyhow::anyhow!("missing scheme"))?;
+        let (key, rest) = rest.split_once('@').ok_or_else(|| anyhow::anyhow!("missing key"))?;
+        let (host, project) = rest.trim_end_matches('/').rsplit_once('/').ok_or_else(|| anyhow::anyhow!("missing project"))?;
+        let key = key.split(':').next().unwrap_or_default(); //the secret of old dsns isn't needed anymore
+        Ok(Self {
+            url: format!("{}://{}/api/{}/store/", scheme, host, project),
+            key: key.to_string(),
+        })
+    }
+
+    fn send(&self, event: &Value) -> anyhow::Result<()> {
+        let auth = format!("Sentry sentry_version=7, sentry_client=sweat_voter/{}, sentry_key={}", env!("CARGO_PKG_VERSION"), self.key);
+        ureq::post(&self.url)
+            .set("X-Sentry-Auth", &auth)
+            .set("Content-Type", "application/json")
+            .send_string(&event.to_string())?;
+        Ok(())
+    }
+}
+
+fn run(endpoint: Endpoint, events: Receiver<Value>) {
+    for event in events {
+        if let Err(e) = endpoint.send(&event) {
+            println!("Failed to send the error report {}: {}", event["event_id"], e);
+        }
+    }
+}
+
+//sends the panics and the failed requests to the dsn from then on, nothing when there is none
+pub fn start(config: &ErrorReportConfig) {
+    let Some(dsn) = &config.dsn else {
+        return;
+    };
+    let endpoint = match Endpoint::parse(dsn) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            println!("Invalid error_report.dsn, errors won't be reported: {}", e);
+            return;
+        }
+    };
+    let (events, receiver) = mpsc::sync_channel(QUEUE);
+    if REPORTER.set(Reporter { events, environment: config.environment.clone() }).is_err() {
+        return;
+    }
+    std::thread::spawn(move || run(endpoint, receiver));
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let message = info.payload().downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic".to_string());
+        let location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let mut event = event("fatal", "panic", &message);
+        event["culprit"] = json!(location);
+        add_request(&mut event, request_id::context());
+        queue(event);
+    }));
+    println!("reporting errors to {}", endpoint_host(dsn));
+}
+
+fn endpoint_host(dsn: &str) -> &str {
+    dsn.split_once('@').map_or(dsn, |(_, rest)| rest)
+}
+
+fn event(level: &str, kind: &str, message: &str) -> Value {
+    let environment = REPORTER.get().map_or("", |r| r.environment.as_str());
+    json!({
+        "event_id": uuid::Uuid::new_v4().simple().to_string(),
+        "timestamp": unix_now(),
+        "platform": "rust",
+        "level": level,
+        "logger": "sweat_voter",
+        "release": BuildInfo::current().to_string(),
+        "environment": environment,
+        "exception": { "values": [{ "type": kind, "value": message }] },
+    })
+}
+
+fn add_request(event: &mut Value, context: Option<RequestContext>) {
+    if let Some(context) = context {
+        event["request"] = json!({ "method": context.method, "url": context.path });
+        event["tags"] = json!({ "request_id": context.id });
+    }
+}
+
+fn queue(event: Value) {
+    if let Some(reporter) = REPORTER.get() {
+        let _ = reporter.events.try_send(event);
+    }
+}
+
+//reports the requests answered with a server error, with what the handler returned
+pub async fn capture(request: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if REPORTER.get().is_none() {
+        return next.call(request).await;
+    }
+    let response = next.call(request).await;
+    let message = match &response {
+        Ok(response) if response.status().is_server_error() => Some(response.response().error()
+            .map_or_else(|| response.status().to_string(), |e| e.to_string())),
+        Ok(_) => None,
+        Err(e) => Some(e.to_string()),
+    };
+    if let Some(message) = message {
+        let mut event = event("error", "request failed", &message);
+        add_request(&mut event, request_id::context());
+        queue(event);
+    }
+    response
+}
+
+fn truncate(text: &str) -> String {
+    text.chars().take(MAX_TEXT).collect()
+}
+
+//a panic of a web client, always logged, forwarded when a dsn is set
+pub fn report_client(error: &ClientError) {
+    println!("[{}] client error: {} at {} ({}, {})", request_id::current(), truncate(&error.message),
+             error.location.as_deref().map_or("?".to_string(), truncate), truncate(&error.build), truncate(&error.page));
+    let mut event = event("fatal", "client panic", &truncate(&error.message));
+    event["platform"] = json!("javascript");
+    event["release"] = json!(truncate(&error.build));
+    event["culprit"] = json!(error.location.as_deref().map(truncate));
+    event["request"] = json!({ "url": truncate(&error.page) });
+    event["tags"] = json!({ "request_id": request_id::current(), "side": "client" });
+    queue(event);
+}
@@ -0,0 +1,29 @@
+use actix_web::http::header::{ETAG, IF_NONE_MATCH};
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+//a hash of the body rather than a version of the data, the same answer twice gives the same tag
+//whichever login or request kind produced it
+fn tag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+fn matches(request: &HttpRequest, tag: &str) -> bool {
+    request.headers().get_all(IF_NONE_MATCH)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|candidate| candidate.trim() == tag || candidate.trim() == "*")
+}
+
+//304 without a body when the client already holds this answer
+pub fn respond(request: &HttpRequest, value: &impl Serialize) -> HttpResponse {
+    let body = serde_json::to_vec(value).expect("Failed to serialize response");
+    let tag = tag(&body);
+    if matches(request, &tag) {
+        return HttpResponse::NotModified().insert_header((ETAG, tag)).finish();
+    }
+    HttpResponse::Ok().insert_header((ETAG, tag)).content_type("application/json").body(body)
+}
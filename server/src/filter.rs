@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::memory::HeapSize;
+use crate::storage::Storage;
+
+const DOCUMENT: &str = "filter";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Mild, //accepted but flagged for a moderator to look at
+    Severe, //rejected outright
+}
+
+impl Severity {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_lowercase().as_str() {
+            "mild" => Some(Self::Mild),
+            "severe" => Some(Self::Severe),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+struct Terms {
+    mild: BTreeSet<String>,
+    severe: BTreeSet<String>,
+}
+
+pub struct ContentFilter {
+    storage: Arc<dyn Storage>,
+    terms: Terms,
+}
+
+//lowercase, without accents and punctuation, words separated by a single space and padded so " term " matches whole words
+fn normalize(text: &str) -> String {
+    let mut normalized = String::from(" ");
+    for c in text.to_lowercase().chars() {
+        let c = match c {
+            'à' | 'â' | 'ä' | 'á' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' | 'í' => 'i',
+            'ô' | 'ö' | 'ó' => 'o',
+            'ù' | 'û' | 'ü' | 'ú' => 'u',
+            'ç' => 'c',
+            'ÿ' => 'y',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        };
+        if c != ' ' || !normalized.ends_with(' ') {
+            normalized.push(c);
+        }
+    }
+    if !normalized.ends_with(' ') {
+        normalized.push(' ');
+    }
+    normalized
+}
+
+impl ContentFilter {
+    pub fn load(storage: Arc<dyn Storage>) -> Self {
+        let terms = storage.load_document(DOCUMENT)
+            .and_then(|d| Ok(d.map(serde_json::from_value::<Terms>).transpose()?));
+        let terms = match terms {
+            Ok(terms) => terms.unwrap_or_default(),
+            Err(e) => {
+                println!("Failed to load {}: {:?}", DOCUMENT, e);
+                Terms::default()
+            }
+        };
+        Self { storage, terms }
+    }
+
+    fn save(&self) {
+        let document = serde_json::to_value(&self.terms).expect("Failed to serialize filter");
+        self.storage.save_document(DOCUMENT, &document)
+            .unwrap_or_else(|e| panic!("Failed to save {}: {:?}", DOCUMENT, e));
+    }
+
+    fn terms_mut(&mut self, severity: Severity) -> &mut BTreeSet<String> {
+        match severity {
+            Severity::Mild => &mut self.terms.mild,
+            Severity::Severe => &mut self.terms.severe,
+        }
+    }
+
+    pub fn terms(&self, severity: Severity) -> &BTreeSet<String> {
+        match severity {
+            Severity::Mild => &self.terms.mild,
+            Severity::Severe => &self.terms.severe,
+        }
+    }
+
+    pub fn add(&mut self, severity: Severity, term: &str) -> bool {
+        let term = normalize(term).trim().to_string();
+        if term.is_empty() {
+            return false;
+        }
+        let added = self.terms_mut(severity).insert(term);
+        if added {
+            self.save();
+        }
+        added
+    }
+
+    pub fn remove(&mut self, severity: Severity, term: &str) -> bool {
+        let term = normalize(term).trim().to_string();
+        let removed = self.terms_mut(severity).remove(&term);
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    //the most severe match, with the term that matched
+    pub fn check(&self, text: &str) -> Option<(Severity, String)> {
+        let text = normalize(text);
+        let matches = |terms: &BTreeSet<String>| terms.iter().find(|t| text.contains(&format!(" {} ", t))).cloned();
+        matches(&self.terms.severe).map(|t| (Severity::Severe, t))
+            .or_else(|| matches(&self.terms.mild).map(|t| (Severity::Mild, t)))
+    }
+}
+
+impl HeapSize for ContentFilter {
+    fn heap_size(&self) -> usize {
+        self.terms.mild.heap_size() + self.terms.severe.heap_size()
+    }
+}
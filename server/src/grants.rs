@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::app_state::AppState;
+use crate::links::ProfilRef;
+use crate::storage::Storage;
+use crate::{unix_now, State};
+
+const DOCUMENT: &str = "guest_grants";
+const EXPIRY_CHECK: Duration = Duration::from_secs(60);
+
+//a profil allowed to vote in another class until expires, exchange students for instance
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct GuestGrant {
+    pub guest: ProfilRef,
+    pub class: String,
+    pub expires: u64, //unix seconds
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct GrantList {
+    grants: Vec<GuestGrant>,
+}
+
+pub struct Grants {
+    storage: Arc<dyn Storage>,
+    grants: Vec<GuestGrant>,
+}
+
+impl Grants {
+    pub fn load(storage: Arc<dyn Storage>) -> Self {
+        let grants = storage.load_document(DOCUMENT)
+            .and_then(|d| Ok(d.map(serde_json::from_value::<GrantList>).transpose()?));
+        let grants = match grants {
+            Ok(grants) => grants.unwrap_or_default().grants,
+            Err(e) => {
+                println!("Failed to load {}: {:?}", DOCUMENT, e);
+                Vec::new()
+            }
+        };
+        Self { storage, grants }
+    }
+
+    fn save(&self) {
+        let document = serde_json::to_value(GrantList { grants: self.grants.clone() }).expect("Failed to serialize grants");
+        self.storage.save_document(DOCUMENT, &document)
+            .unwrap_or_else(|e| panic!("Failed to save {}: {:?}", DOCUMENT, e));
+    }
+
+    //a new grant for the same guest and class replaces the old one, so the expiry can be moved both ways
+    pub fn grant(&mut self, guest: ProfilRef, class: String, expires: u64) {
+        self.grants.retain(|g| g.guest != guest || g.class != class);
+        self.grants.push(GuestGrant { guest, class, expires });
+        self.save();
+    }
+
+    pub fn revoke(&mut self, guest: &ProfilRef, class: &str) -> bool {
+        let before = self.grants.len();
+        self.grants.retain(|g| g.guest != *guest || g.class != class);
+        let found = self.grants.len() != before;
+        if found {
+            self.save();
+        }
+        found
+    }
+
+    pub fn is_granted(&self, guest: &ProfilRef, class: &str, now: u64) -> bool {
+        self.grants.iter().any(|g| g.guest == *guest && g.class == class && now < g.expires)
+    }
+
+    //expired grants already refuse votes, dropping them keeps the document and GuestGrants short
+    pub fn remove_expired(&mut self, now: u64) -> Vec<GuestGrant> {
+        let (expired, kept) = std::mem::take(&mut self.grants).into_iter().partition(|g| g.expires <= now);
+        self.grants = kept;
+        if !expired.is_empty() {
+            self.save();
+        }
+        expired
+    }
+
+    pub fn list(&self) -> &[GuestGrant] {
+        &self.grants
+    }
+}
+
+//what the votes of a guest are kept under, a participant of the class can't be named like that by accident
+pub fn guest_key(guest: &ProfilRef) -> String {
+    format!("{} ({})", guest.name, guest.class)
+}
+
+impl AppState {
+    //voter logs in with their own class and votes in class, through a grant when they are not the same
+    pub fn is_allowed_between(&self, voter: &ProfilRef, password: &str, class: &str) -> bool {
+        let Some(group) = self.classes.get(&voter.class) else {
+            return false;
+        };
+        let allowed = {
+            let group = &group.read().expect("Failed to lock data").participants;
+            self.check_login(&voter.class, group, &voter.name, password) && !group.must_change_password.contains(&voter.name)
+        };
+        if !allowed {
+            return false;
+        }
+        voter.class == class || self.grants.lock().expect("Failed to lock grants").is_granted(voter, class, unix_now())
+    }
+}
+
+//reverts the grants once they expire and logs it, the votes given meanwhile are kept
+pub fn spawn_expiry(state: State) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(EXPIRY_CHECK);
+        let expired = state.grants.lock().expect("Failed to lock grants").remove_expired(unix_now());
+        for grant in expired {
+            println!("guest grant expired: {} ({}) can't vote in {} anymore", grant.guest.name, grant.guest.class, grant.class);
+        }
+    });
+}
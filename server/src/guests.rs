@@ -0,0 +1,116 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use common::Group;
+use crate::app_state::AppState;
+use crate::State;
+
+//what visitors without a valid login may read, logged in participants always see everything
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum GuestAccess {
+    Closed, //nothing
+    Propositions, //the propositions without their vote counts
+    #[default]
+    Counts, //propositions and vote counts, how the server behaved before the setting
+}
+
+//the endpoints readable without a login, the keys of guest_endpoints in the config
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Endpoint {
+    ClassList,
+    PersonProfile,
+    ClassSummary,
+    WordStats,
+    Leaderboard,
+    Suggest,
+    ProfilHistory,
+    NicknameHistory,
+    Job,
+}
+
+impl GuestAccess {
+    pub fn name(self) -> &'static str {
+        match self {
+            GuestAccess::Closed => "closed",
+            GuestAccess::Propositions => "propositions",
+            GuestAccess::Counts => "counts",
+        }
+    }
+}
+
+impl Endpoint {
+    pub const ALL: [Endpoint; 9] = [
+        Endpoint::ClassList,
+        Endpoint::PersonProfile,
+        Endpoint::ClassSummary,
+        Endpoint::WordStats,
+        Endpoint::Leaderboard,
+        Endpoint::Suggest,
+        Endpoint::ProfilHistory,
+        Endpoint::NicknameHistory,
+        Endpoint::Job,
+    ];
+
+    //as written in guest_endpoints
+    pub fn name(self) -> &'static str {
+        match self {
+            Endpoint::ClassList => "class_list",
+            Endpoint::PersonProfile => "person_profile",
+            Endpoint::ClassSummary => "class_summary",
+            Endpoint::WordStats => "word_stats",
+            Endpoint::Leaderboard => "leaderboard",
+            Endpoint::Suggest => "suggest",
+            Endpoint::ProfilHistory => "profil_history",
+            Endpoint::NicknameHistory => "nickname_history",
+            Endpoint::Job => "job",
+        }
+    }
+
+    //endpoints without any login in the request, the middleware takes care of them
+    fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "/class_list" => Some(Endpoint::ClassList),
+            path if path.starts_with("/job/") => Some(Endpoint::Job),
+            _ => None,
+        }
+    }
+}
+
+impl AppState {
+    //the class list stays open unless asked otherwise, without it nobody can pick a class to log in
+    pub fn guest_access(&self, endpoint: Endpoint) -> GuestAccess {
+        let settings = self.settings.read().expect("Failed to lock settings");
+        settings.guest_endpoints.get(&endpoint).copied().unwrap_or(match endpoint {
+            Endpoint::ClassList => GuestAccess::Counts,
+            _ => settings.guest_access,
+        })
+    }
+
+    //what the caller may read of group through endpoint, everything once logged in
+    pub fn access(&self, endpoint: Endpoint, class: &str, group: &Group, editor: &str, password: &str) -> GuestAccess {
+        self.access_if(endpoint, self.check_login(class, group, editor, password))
+    }
+
+    //for a login already checked
+    pub fn access_if(&self, endpoint: Endpoint, logged_in: bool) -> GuestAccess {
+        if logged_in {
+            GuestAccess::Counts
+        } else {
+            self.guest_access(endpoint)
+        }
+    }
+}
+
+//refuses the closed endpoints that carry no login, the others check theirs with AppState::access
+pub async fn refuse_closed(request: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let closed = Endpoint::from_path(request.path()).zip(request.app_data::<web::Data<State>>())
+        .is_some_and(|(endpoint, state)| state.guest_access(endpoint) == GuestAccess::Closed);
+    if closed {
+        return Ok(request.into_response(HttpResponse::Forbidden().finish()).map_into_right_body());
+    }
+    Ok(next.call(request).await?.map_into_left_body())
+}
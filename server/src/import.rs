@@ -0,0 +1,230 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use common::{Group, ProfilKind};
+use crate::app_state::AppState;
+use crate::passwords::{fingerprint, hash};
+use crate::setup::new_password;
+
+//one line of the file, numbered from 1 like an editor shows it
+struct Row {
+    line: usize,
+    name: String,
+    class: String,
+    password: String,
+    kind: ProfilKind,
+}
+
+//"a,b" or "\"Nom, Prénom\",b", a doubled quote inside quotes is a quote
+fn split_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                current.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+//name,class[,password[,kind]] per line, an optional header, a missing password is generated, a missing kind is student
+fn parse(text: &str, errors: &mut Vec<String>) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_line(line);
+        if index == 0 && fields.first().is_some_and(|f| f.eq_ignore_ascii_case("name")) {
+            continue;
+        }
+        let (name, class, password, kind) = match fields.as_slice() {
+            [name, class] => (name, class, "", ""),
+            [name, class, password] => (name, class, password.as_str(), ""),
+            [name, class, password, kind] => (name, class, password.as_str(), kind.as_str()),
+            _ => {
+                errors.push(format!("line {}: expected name,class,password,kind, got {} columns", line_number, fields.len()));
+                continue;
+            }
+        };
+        if name.is_empty() || class.is_empty() {
+            errors.push(format!("line {}: empty name or class", line_number));
+            continue;
+        }
+        if class.contains(['/', '\\']) || class.starts_with('.') {
+            errors.push(format!("line {}: invalid class name: {}", line_number, class));
+            continue;
+        }
+        let kind = if kind.is_empty() {
+            ProfilKind::Student
+        } else if let Some(kind) = ProfilKind::parse(kind) {
+            kind
+        } else {
+            errors.push(format!("line {}: invalid kind: {}, expected student, teacher or staff", line_number, kind));
+            continue;
+        };
+        rows.push(Row { line: line_number, name: name.clone(), class: class.clone(), password: password.to_string(), kind });
+    }
+    rows
+}
+
+impl AppState {
+    //ImportCsv: the profils missing from their class are added, the existing ones are left untouched,
+    //classes the server doesn't have yet are written to the storage and served after a restart,
+    //cancel stops it before the next row, the rows already added stay
+    pub fn import_csv(&self, path: &Path, cancel: &AtomicBool) -> Vec<String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => return vec![format!("Failed to read {}: {:?}", path.display(), e)],
+        };
+        let mut errors = Vec::new();
+        let rows = parse(&text, &mut errors);
+
+        let mut by_class: BTreeMap<String, Vec<Row>> = BTreeMap::new();
+        let mut seen = BTreeSet::new();
+        for row in rows {
+            if !seen.insert((row.class.clone(), row.name.clone())) {
+                errors.push(format!("line {}: {} appears twice in {}, the first one is kept", row.line, row.name, row.class));
+                continue;
+            }
+            by_class.entry(row.class.clone()).or_default().push(row);
+        }
+
+        let mut refused = errors.len();
+        let mut lines = Vec::new();
+        let (mut created, mut existing, mut new_classes) = (0, 0, Vec::new());
+        for (class, rows) in by_class {
+            if cancel.load(Ordering::Relaxed) {
+                lines.push(format!("cancelled, {} and the classes after it not imported", class));
+                break;
+            }
+            let before = lines.len();
+            let (added, found) = match self.classes.get(&class) {
+                Some(group) => {
+                    let mut lock = group.write().expect("Failed to lock data");
+                    let (added, found) = self.add_rows(&mut lock.participants, &rows, &mut lines, cancel);
+                    if added > 0 {
+                        if self.stable_ids {
+                            lock.assign_uuids();
+                        }
+                        lock.save();
+                    }
+                    (added, found)
+                }
+                None => {
+                    //imported again before the restart, the class is already in the storage
+                    let mut group = self.storage.load_classes().into_iter()
+                        .find(|(name, _)| *name == class)
+                        .and_then(|(_, group)| group.ok())
+                        .unwrap_or_default();
+                    let (added, found) = self.add_rows(&mut group, &rows, &mut lines, cancel);
+                    if added > 0 {
+                        if let Err(e) = self.storage.save_class(&class, &group) {
+                            lines.truncate(before); //the generated passwords weren't kept
+                            errors.push(format!("Failed to save {}, none of its {} rows imported: {:?}", class, rows.len(), e));
+                            refused += rows.len();
+                            continue;
+                        }
+                        new_classes.push(class.clone());
+                    }
+                    (added, found)
+                }
+            };
+            created += added;
+            existing += found;
+        }
+
+        lines.extend(errors);
+        lines.push(format!("{} profils created, {} already there, {} rows refused", created, existing, refused));
+        if !new_classes.is_empty() {
+            lines.push(format!("new classes {}, restart the server to serve them", new_classes.join(", ")));
+        }
+        lines
+    }
+
+    //returns how many were added and how many were already there, the generated passwords are printed to be handed out
+    fn add_rows(&self, group: &mut Group, rows: &[Row], lines: &mut Vec<String>, cancel: &AtomicBool) -> (usize, usize) {
+        let (mut added, mut existing) = (0, 0);
+        for row in rows {
+            if cancel.load(Ordering::Relaxed) {
+                lines.push(format!("line {}: cancelled, this row and the next ones of {} not imported", row.line, row.class));
+                break;
+            }
+            if group.profiles.contains_key(&row.name) {
+                lines.push(format!("line {}: {} already in {}, left untouched", row.line, row.name, row.class));
+                existing += 1;
+                continue;
+            }
+            let password = if row.password.is_empty() {
+                let password = new_password();
+                lines.push(format!("password of {} ({}): {}", row.name, row.class, password));
+                password
+            } else {
+                row.password.clone()
+            };
+            let stored = if group.hashed_passwords {
+                if let Some(key) = &self.audit_key {
+                    group.password_fingerprints.insert(row.name.clone(), fingerprint(key, &password));
+                }
+                hash(&password)
+            } else {
+                password
+            };
+            group.profiles.insert(row.name.clone(), (stored, Vec::new()));
+            group.must_change_password.insert(row.name.clone()); //handed out, replaced at the first login like --init
+            if row.kind != ProfilKind::Student {
+                group.kinds.insert(row.name.clone(), row.kind);
+            }
+            added += 1;
+        }
+        (added, existing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::ProfilKind;
+    use super::{parse, split_line};
+
+    #[test]
+    fn splits_quoted_fields() {
+        assert_eq!(split_line("a, b ,c"), vec!["a", "b", "c"]);
+        assert_eq!(split_line("\"Dupont, Jean\",test"), vec!["Dupont, Jean", "test"]);
+        assert_eq!(split_line("\"il dit \"\"oui\"\"\",x"), vec!["il dit \"oui\"", "x"]);
+        assert_eq!(split_line(""), vec![""]);
+    }
+
+    #[test]
+    fn parses_rows_and_reports_the_others() {
+        let text = "name,class,password,kind\nAlice,test\n\nBob,test,bobpass12,Teacher\nCarol\n,test\nDan,../up\nEve,test,x,pilot\n";
+        let mut errors = Vec::new();
+        let rows = parse(text, &mut errors);
+        let found: Vec<(usize, &str, &str, ProfilKind)> = rows.iter().map(|r| (r.line, r.name.as_str(), r.password.as_str(), r.kind)).collect();
+        assert_eq!(found, vec![(2, "Alice", "", ProfilKind::Student), (4, "Bob", "bobpass12", ProfilKind::Teacher)]);
+        assert_eq!(errors, vec![
+            "line 5: expected name,class,password,kind, got 1 columns",
+            "line 6: empty name or class",
+            "line 7: invalid class name: ../up",
+            "line 8: invalid kind: pilot, expected student, teacher or staff",
+        ]);
+    }
+
+    #[test]
+    fn header_only_on_the_first_line() {
+        let mut errors = Vec::new();
+        let rows = parse("Name,test\n", &mut errors);
+        assert!(rows.is_empty() && errors.is_empty(), "a first line starting with name is the header");
+        let rows = parse("Alice,test\nname,test\n", &mut errors);
+        assert_eq!(rows.len(), 2);
+    }
+}
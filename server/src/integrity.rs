@@ -0,0 +1,326 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::Ordering;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use common::{Group, Nickname};
+use crate::app_state::AppState;
+use crate::config::IntegrityConfig;
+use crate::suggest::normalize;
+use crate::State;
+
+//the routes changing the data of a class, refused while the server is read-only, the console stays usable to repair
+const WRITES: [&str; 12] = ["/add_nickname", "/add_comment", "/delete_comment", "/delete_nickname", "/vote_nickname", "/batch_votes",
+    "/transfer_nickname", "/change_display_name", "/change_password", "/avatar/upload", "/admin/restore_nickname", "/admin/unarchive_nickname"];
+
+//what check found in a class, a duplicate is always serious, dangling references only past integrity.max_dangling
+#[derive(Default)]
+pub struct Problems {
+    pub duplicates: Vec<String>,
+    pub dangling: Vec<String>,
+}
+
+impl Problems {
+    pub fn is_empty(&self) -> bool {
+        self.duplicates.is_empty() && self.dangling.is_empty()
+    }
+
+    pub fn is_serious(&self, config: &IntegrityConfig) -> bool {
+        !self.duplicates.is_empty() || self.dangling.len() > config.max_dangling
+    }
+}
+
+//guests vote under "name (class)", see grants::guest_key
+fn is_guest_key(voter: &str) -> bool {
+    voter.ends_with(')') && voter.contains(" (")
+}
+
+fn is_voter(group: &Group, voter: &str) -> bool {
+    group.profiles.contains_key(voter) || is_guest_key(voter)
+}
+
+type Rankings = BTreeMap<String, Vec<String>>; //voter -> propositions, preferred first
+
+//the participants' propositions then the board, with what the lines name them
+fn targets(group: &Group) -> impl Iterator<Item = (String, &Vec<Nickname>, Option<&Rankings>)> {
+    group.profiles.iter()
+        .map(|(name, (_, nicknames))| (name.clone(), nicknames, group.rankings.get(name)))
+        .chain(std::iter::once(("the board".to_string(), &group.board.nicknames, Some(&group.board.rankings))))
+}
+
+//names keyed by profil outside of the profiles, each one should still be a profil
+fn profil_keys(group: &Group) -> Vec<(&'static str, &String)> {
+    let mut keys = Vec::new();
+    keys.extend(group.uuids.keys().map(|k| ("uuid", k)));
+    keys.extend(group.display_names.keys().map(|k| ("display name", k)));
+    keys.extend(group.kinds.keys().map(|k| ("kind", k)));
+    keys.extend(group.avatars.keys().map(|k| ("avatar", k)));
+    keys.extend(group.password_fingerprints.keys().map(|k| ("password fingerprint", k)));
+    keys.extend(group.password_changed.iter().map(|k| ("password changed", k)));
+    keys.extend(group.must_change_password.iter().map(|k| ("must change password", k)));
+    keys.extend(group.rankings.keys().map(|k| ("rankings", k)));
+    keys
+}
+
+//display names taken by another participant, as profil name or as display name, the first one keeps it
+fn taken_display_names(group: &Group) -> Vec<&String> {
+    let mut seen: BTreeSet<String> = BTreeSet::new();
+    let mut taken = Vec::new();
+    for (name, (shown, _)) in &group.display_names {
+        let shown = normalize(shown);
+        let other_profil = group.profiles.keys().any(|other| other != name && normalize(other) == shown);
+        if other_profil || !seen.insert(shown) {
+            taken.push(name);
+        }
+    }
+    taken
+}
+
+pub fn check(group: &Group) -> Problems {
+    let mut problems = Problems::default();
+    for (target, nicknames, rankings) in targets(group) {
+        let mut seen = BTreeSet::new();
+        for nickname in nicknames {
+            if !seen.insert(&nickname.nickname) {
+                problems.duplicates.push(format!("\"{}\" proposed twice for {}", nickname.nickname, target));
+            }
+            let mut voters = BTreeSet::new();
+            for voter in &nickname.votes {
+                if !voters.insert(voter) {
+                    problems.duplicates.push(format!("{} voted twice for \"{}\" ({})", voter, nickname.nickname, target));
+                } else if !is_voter(group, voter) {
+                    problems.dangling.push(format!("vote of unknown {} for \"{}\" ({})", voter, nickname.nickname, target));
+                }
+            }
+        }
+        for (voter, ranking) in rankings.into_iter().flatten() {
+            if !is_voter(group, voter) {
+                problems.dangling.push(format!("ranking of unknown {} ({})", voter, target));
+            }
+            if ranking.iter().collect::<BTreeSet<_>>().len() != ranking.len() {
+                problems.duplicates.push(format!("ranking of {} names a proposition twice ({})", voter, target));
+            }
+        }
+    }
+    for (what, name) in profil_keys(group) {
+        if !group.profiles.contains_key(name) {
+            problems.dangling.push(format!("{} of unknown profil {}", what, name));
+        }
+    }
+    for name in taken_display_names(group) {
+        problems.duplicates.push(format!("display name of {} already used by another participant", name));
+    }
+    problems
+}
+
+//keeps the first of each item, returns the others
+fn dedup<T: Ord + Clone>(list: &mut Vec<T>) -> Vec<T> {
+    let mut seen = BTreeSet::new();
+    let mut removed = Vec::new();
+    list.retain(|item| seen.insert(item.clone()) || {
+        removed.push(item.clone());
+        false
+    });
+    removed
+}
+
+pub fn targets_mut(group: &mut Group) -> impl Iterator<Item = (String, &mut Vec<Nickname>)> {
+    group.profiles.iter_mut()
+        .map(|(name, (_, nicknames))| (name.clone(), nicknames))
+        .chain(std::iter::once(("the board".to_string(), &mut group.board.nicknames)))
+}
+
+fn rankings_mut(group: &mut Group) -> impl Iterator<Item = (String, &mut Rankings)> {
+    group.rankings.iter_mut()
+        .map(|(name, rankings)| (name.clone(), rankings))
+        .chain(std::iter::once(("the board".to_string(), &mut group.board.rankings)))
+}
+
+//the duplicates check finds, as diff lines: a proposition made twice is merged into the first one with
+//the votes of both, votes and rankings are kept once, a taken display name goes back to the profil name
+pub fn dedupe_names(group: &mut Group) -> Vec<String> {
+    let mut diff = Vec::new();
+    for (target, nicknames) in targets_mut(group) {
+        let mut merged: Vec<Nickname> = Vec::with_capacity(nicknames.len());
+        for nickname in nicknames.drain(..) {
+            match merged.iter_mut().find(|n| n.nickname == nickname.nickname) {
+                Some(first) => {
+                    diff.push(format!("- \"{}\" for {} proposed again, its {} votes go to the first one", nickname.nickname, target, nickname.votes.len()));
+                    first.votes.extend(nickname.votes);
+                    for (voter, time) in nickname.voted_at {
+                        first.voted_at.entry(voter).or_insert(time);
+                    }
+                }
+                None => merged.push(nickname),
+            }
+        }
+        for nickname in &mut merged {
+            for voter in dedup(&mut nickname.votes) {
+                diff.push(format!("- vote of {} for \"{}\" ({}) counted twice", voter, nickname.nickname, target));
+            }
+        }
+        *nicknames = merged;
+    }
+    for (target, rankings) in rankings_mut(group) {
+        for (voter, ranking) in rankings.iter_mut() {
+            for nickname in dedup(ranking) {
+                diff.push(format!("- \"{}\" ranked twice by {} ({})", nickname, voter, target));
+            }
+        }
+    }
+    let taken: Vec<String> = taken_display_names(group).into_iter().cloned().collect();
+    for name in taken {
+        if let Some((shown, _)) = group.display_names.remove(&name) {
+            diff.push(format!("- display name \"{}\" of {}, already used by another participant", shown, name));
+        }
+    }
+    diff
+}
+
+//the dangling references check finds, as diff lines: votes and rankings of voters who are neither
+//a participant nor a guest, and whatever is still kept for a profil that was removed
+pub fn drop_orphans(group: &mut Group) -> Vec<String> {
+    let profiles: BTreeSet<String> = group.profiles.keys().cloned().collect();
+    let is_voter = |voter: &String| profiles.contains(voter) || is_guest_key(voter);
+    let mut diff = Vec::new();
+    for (target, nicknames) in targets_mut(group) {
+        for nickname in nicknames {
+            let (kept, dropped) = nickname.votes.drain(..).partition(is_voter);
+            nickname.votes = kept;
+            nickname.prune_vote_times();
+            diff.extend(dropped.iter().map(|voter: &String| format!("- vote of unknown {} for \"{}\" ({})", voter, nickname.nickname, target)));
+        }
+    }
+    for (target, rankings) in rankings_mut(group) {
+        rankings.retain(|voter, _| is_voter(voter) || {
+            diff.push(format!("- ranking of unknown {} ({})", voter, target));
+            false
+        });
+    }
+    diff.extend(profil_keys(group).into_iter()
+        .filter(|(_, name)| !profiles.contains(*name))
+        .map(|(what, name)| format!("- {} of unknown profil {}", what, name)));
+    group.uuids.retain(|k, _| profiles.contains(k));
+    group.display_names.retain(|k, _| profiles.contains(k));
+    group.kinds.retain(|k, _| profiles.contains(k));
+    group.avatars.retain(|k, _| profiles.contains(k));
+    group.password_fingerprints.retain(|k, _| profiles.contains(k));
+    group.password_changed.retain(|k| profiles.contains(k));
+    group.must_change_password.retain(|k| profiles.contains(k));
+    group.rankings.retain(|k, _| profiles.contains(k));
+    diff
+}
+
+//everything check finds, what RepairData applies
+pub fn repair(group: &mut Group) -> Vec<String> {
+    let mut diff = dedupe_names(group);
+    diff.extend(drop_orphans(group));
+    diff
+}
+
+fn describe(class: &str, problems: &Problems) -> Vec<String> {
+    let mut lines = vec![format!("{}: {} duplicates, {} dangling references", class, problems.duplicates.len(), problems.dangling.len())];
+    lines.extend(problems.duplicates.iter().chain(&problems.dangling).map(|p| format!("  {}", p)));
+    lines
+}
+
+impl AppState {
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    //CheckData, and what the server runs at startup, true when a class has serious problems
+    pub fn check_data(&self) -> (Vec<String>, bool) {
+        let mut classes: Vec<&String> = self.classes.keys().collect();
+        self.collation.sort(&mut classes);
+        let mut lines = Vec::new();
+        let mut serious = false;
+        for class in classes {
+            let problems = check(&self.classes[class].read().expect("Failed to lock data").participants);
+            if !problems.is_empty() {
+                serious |= problems.is_serious(&self.integrity);
+                lines.extend(describe(class, &problems));
+            }
+        }
+        if lines.is_empty() {
+            lines.push("no problem found".to_string());
+        }
+        (lines, serious)
+    }
+
+    //RepairData, the server stays read-only until ReadOnly off
+    pub fn repair_data(&self, class: &str) -> Vec<String> {
+        let Some(group) = self.classes.get(class) else {
+            return vec![format!("unknown class: {}", class)];
+        };
+        let mut lock = group.write().expect("Failed to lock data");
+        let mut lines = repair(&mut lock.participants);
+        if !lines.is_empty() {
+            lock.save_anyway();
+        }
+        let left = check(&lock.participants);
+        lines.push(format!("{} entries repaired in {}", lines.len(), class));
+        if !left.is_empty() {
+            lines.extend(describe(class, &left));
+        }
+        lines
+    }
+
+    pub fn set_read_only(&self, read_only: bool) -> Vec<String> {
+        self.read_only.store(read_only, Ordering::Relaxed);
+        if read_only {
+            vec!["the server is read-only, only the repair commands change the data".to_string()]
+        } else {
+            vec!["the server accepts changes again".to_string()]
+        }
+    }
+}
+
+//answers 503 to the WRITES while the server is read-only
+pub async fn refuse_writes(request: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let read_only = request.app_data::<web::Data<State>>().is_some_and(|state| state.is_read_only());
+    if read_only && WRITES.contains(&request.path()) {
+        let response = HttpResponse::ServiceUnavailable().body("the server is read-only until its data is repaired");
+        return Ok(request.into_response(response).map_into_right_body());
+    }
+    Ok(next.call(request).await?.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{Group, Nickname};
+    use super::*;
+
+    //Bobby proposed twice for Bob, a vote counted twice, a vote of a removed profil and a guest's vote
+    fn damaged() -> Group {
+        let mut group = Group::default();
+        let bobby = |votes: &[&str]| Nickname { nickname: "Bobby".to_string(), votes: votes.iter().map(|v| v.to_string()).collect(), ..Nickname::default() };
+        let nicknames = vec![bobby(&["Alice", "Alice"]), bobby(&["Gone", "Zoe (other)"])];
+        group.profiles.insert("Alice".to_string(), ("alicepass1".to_string(), Vec::new()));
+        group.profiles.insert("Bob".to_string(), ("bobpass12".to_string(), nicknames));
+        group.display_names.insert("Gone".to_string(), ("G".to_string(), 0));
+        group
+    }
+
+    #[test]
+    fn check_finds_duplicates_and_dangling() {
+        let problems = check(&damaged());
+        assert_eq!(problems.duplicates, vec!["Alice voted twice for \"Bobby\" (Bob)", "\"Bobby\" proposed twice for Bob"]);
+        assert_eq!(problems.dangling, vec!["vote of unknown Gone for \"Bobby\" (Bob)", "display name of unknown profil Gone"]);
+        assert!(problems.is_serious(&IntegrityConfig { enabled: true, max_dangling: 10 }), "a duplicate is always serious");
+        assert!(check(&Group::default()).is_empty());
+    }
+
+    #[test]
+    fn repair_leaves_nothing_to_check() {
+        let mut group = damaged();
+        let diff = repair(&mut group);
+        assert_eq!(diff.len(), 4);
+        assert!(check(&group).is_empty());
+        let nicknames = &group.profiles["Bob"].1;
+        assert_eq!(nicknames.len(), 1);
+        assert_eq!(nicknames[0].votes, vec!["Alice".to_string(), "Zoe (other)".to_string()], "merged, counted once, the guest kept");
+        assert!(repair(&mut group).is_empty());
+    }
+}
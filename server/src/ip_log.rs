@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use crate::config::IpLogConfig;
+use crate::memory::HeapSize;
+use crate::unix_now as now;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AddressUse {
+    pub first_seen: u64, //unix seconds
+    pub last_seen: u64,
+    pub requests: usize,
+}
+
+//addresses used by each account, only kept in memory and only while ip_log.enabled is set
+pub struct IpLog {
+    config: IpLogConfig,
+    accounts: HashMap<(String, String), HashMap<IpAddr, AddressUse>>, //(class, name)
+}
+
+impl IpLog {
+    pub fn new(config: IpLogConfig) -> Self {
+        Self {
+            config,
+            accounts: HashMap::new(),
+        }
+    }
+
+    fn prune(&mut self) {
+        let oldest = now().saturating_sub(self.config.retention_secs);
+        for addresses in self.accounts.values_mut() {
+            addresses.retain(|_, u| u.last_seen >= oldest);
+        }
+        self.accounts.retain(|_, addresses| !addresses.is_empty());
+    }
+
+    pub fn record(&mut self, class: &str, name: &str, address: Option<IpAddr>) {
+        let Some(address) = address.filter(|_| self.config.enabled) else {
+            return;
+        };
+        let now = now();
+        let entry = self.accounts.entry((class.to_string(), name.to_string())).or_default()
+            .entry(address)
+            .or_insert(AddressUse { first_seen: now, last_seen: now, requests: 0 });
+        entry.last_seen = now;
+        entry.requests += 1;
+        self.prune();
+    }
+
+    pub fn addresses_of(&mut self, class: &str, name: &str) -> Vec<(IpAddr, AddressUse)> {
+        self.prune();
+        let mut addresses: Vec<(IpAddr, AddressUse)> = self.accounts.get(&(class.to_string(), name.to_string()))
+            .map(|a| a.iter().map(|(ip, u)| (*ip, *u)).collect())
+            .unwrap_or_default();
+        addresses.sort_by_key(|(_, u)| std::cmp::Reverse(u.last_seen));
+        addresses
+    }
+
+    //addresses used by more than one account of the class, with those accounts
+    pub fn shared_addresses(&mut self, class: &str) -> Vec<(IpAddr, Vec<String>)> {
+        self.prune();
+        let mut by_address: HashMap<IpAddr, Vec<String>> = HashMap::new();
+        for ((account_class, name), addresses) in &self.accounts {
+            if account_class == class {
+                for address in addresses.keys() {
+                    by_address.entry(*address).or_default().push(name.clone());
+                }
+            }
+        }
+        let mut shared: Vec<(IpAddr, Vec<String>)> = by_address.into_iter()
+            .filter(|(_, names)| names.len() > 1)
+            .map(|(address, mut names)| {
+                names.sort();
+                (address, names)
+            })
+            .collect();
+        shared.sort_by_key(|(_, names)| std::cmp::Reverse(names.len()));
+        shared
+    }
+}
+
+impl HeapSize for AddressUse {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for IpLog {
+    fn heap_size(&self) -> usize {
+        self.accounts.heap_size()
+    }
+}
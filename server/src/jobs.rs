@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::{unix_now, State};
+
+const KEPT_FINISHED: usize = 50; //finished jobs remembered for Jobs and /job/{id}
+
+//the admin_token of the config, the description of a job can name files of the server
+#[derive(Deserialize)]
+pub struct JobQuery {
+    #[serde(default)]
+    pub token: String,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Done,
+    Cancelled,
+}
+
+//the output is only printed on the console, it can hold addresses and other personal data
+#[derive(Serialize, Debug, Clone)]
+pub struct JobStatus {
+    pub id: u64,
+    pub description: String,
+    pub state: JobState,
+    pub started: u64,
+    pub finished: Option<u64>,
+    #[serde(skip)]
+    cancel: Arc<AtomicBool>, //set by CancelJob, the work looks at it between its steps
+}
+
+//long operations run on their own thread so the console and the requests are not blocked
+#[derive(Default)]
+pub struct Jobs {
+    next_id: u64,
+    jobs: BTreeMap<u64, JobStatus>,
+}
+
+impl Jobs {
+    fn start(&mut self, description: String) -> (u64, Arc<AtomicBool>) {
+        self.next_id += 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.jobs.insert(self.next_id, JobStatus {
+            id: self.next_id,
+            description,
+            state: JobState::Running,
+            started: unix_now(),
+            finished: None,
+            cancel: cancel.clone(),
+        });
+        (self.next_id, cancel)
+    }
+
+    //false if the job was cancelled meanwhile
+    fn finish(&mut self, id: u64) -> bool {
+        let done = match self.jobs.get_mut(&id) {
+            Some(job) if job.state == JobState::Running => {
+                job.state = JobState::Done;
+                job.finished = Some(unix_now());
+                true
+            }
+            _ => false,
+        };
+
+        let finished: Vec<u64> = self.jobs.values().filter(|j| j.state != JobState::Running).map(|j| j.id).collect();
+        for id in finished.iter().take(finished.len().saturating_sub(KEPT_FINISHED)) {
+            self.jobs.remove(id);
+        }
+        done
+    }
+
+    //the work stops at its next step, what it did until then stays done
+    pub fn cancel(&mut self, id: u64) -> bool {
+        match self.jobs.get_mut(&id) {
+            Some(job) if job.state == JobState::Running => {
+                job.state = JobState::Cancelled;
+                job.finished = Some(unix_now());
+                job.cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn status(&self, id: u64) -> Option<&JobStatus> {
+        self.jobs.get(&id)
+    }
+
+    pub fn list(&self) -> impl Iterator<Item = &JobStatus> {
+        self.jobs.values()
+    }
+}
+
+//runs work on a new thread with the flag CancelJob sets, its output is printed on the console when it ends
+pub fn spawn<F>(state: &State, description: String, work: F) -> u64
+    where F: FnOnce(&State, &AtomicBool) -> Vec<String> + Send + 'static
+{
+    let (id, cancel) = state.jobs.lock().expect("Failed to lock jobs").start(description);
+    let state = state.clone();
+    std::thread::spawn(move || {
+        let output = work(&state, &cancel);
+        let done = state.jobs.lock().expect("Failed to lock jobs").finish(id);
+        println!("job {} {}:", id, if done { "done" } else { "cancelled" });
+        for line in output {
+            println!("{}", line);
+        }
+    });
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use super::{JobState, Jobs, KEPT_FINISHED};
+
+    #[test]
+    fn cancel_sets_the_flag_of_the_work() {
+        let mut jobs = Jobs::default();
+        let (id, cancel) = jobs.start("ExportYearbook".to_string());
+        assert!(!cancel.load(Ordering::Relaxed));
+        assert!(jobs.cancel(id));
+        assert!(cancel.load(Ordering::Relaxed));
+        assert_eq!(jobs.status(id).map(|j| j.state), Some(JobState::Cancelled));
+
+        assert!(!jobs.cancel(id), "already cancelled");
+        assert!(!jobs.finish(id), "the work ending after the cancel doesn't make it done");
+        assert_eq!(jobs.status(id).map(|j| j.state), Some(JobState::Cancelled));
+    }
+
+    #[test]
+    fn finished_jobs_are_forgotten_past_the_limit() {
+        let mut jobs = Jobs::default();
+        let (running, _) = jobs.start("running".to_string());
+        for _ in 0..KEPT_FINISHED + 5 {
+            let (id, _) = jobs.start("done".to_string());
+            assert!(jobs.finish(id));
+        }
+        assert_eq!(jobs.list().count(), KEPT_FINISHED + 1);
+        assert_eq!(jobs.status(running).map(|j| j.state), Some(JobState::Running));
+        assert!(!jobs.cancel(running + 1), "the oldest finished one is gone");
+    }
+}
@@ -0,0 +1,33 @@
+use common::packets::c2s::AskForLeaderboard;
+use common::packets::s2c::Leaderboard;
+use crate::app_state::AppState;
+use crate::guests::{Endpoint, GuestAccess};
+
+const MAX_TOP: usize = 100;
+
+impl AppState {
+    //the login only counts in its own class, the others are read as a guest and only what a public view shows of them,
+    //a class with its counts hidden would give them away through the order, so it is left out
+    pub fn leaderboard(&self, asked: &AskForLeaderboard) -> Leaderboard {
+        let top = asked.top.min(MAX_TOP);
+        let mut nicknames = Vec::new();
+        let mut proposers = Vec::new();
+        for (class, group) in self.classes.iter().filter(|(c, _)| asked.only.as_ref().is_none_or(|only| only == *c)) {
+            let logged_in = *class == asked.class
+                && self.check_login(class, &group.read().expect("Failed to lock data").participants, &asked.editor, &asked.password);
+            if !logged_in && self.guest_access(Endpoint::Leaderboard) != GuestAccess::Counts {
+                continue;
+            }
+
+            let stats = self.class_stats(class);
+            nicknames.extend(stats.nicknames.iter().filter(|(_, public)| logged_in || *public).map(|(entry, _)| entry.clone()));
+            proposers.extend_from_slice(if logged_in { &stats.proposers } else { &stats.public_proposers });
+        }
+
+        nicknames.sort_by(|a, b| b.score().total_cmp(&a.score()).then_with(|| self.collation.compare(&a.nickname, &b.nickname)));
+        nicknames.truncate(top);
+        proposers.sort_by(|a, b| b.votes.cmp(&a.votes).then(b.propositions.cmp(&a.propositions)).then_with(|| self.collation.compare(&a.name, &b.name)));
+        proposers.truncate(top);
+        Leaderboard { only: asked.only.clone(), nicknames, proposers }
+    }
+}
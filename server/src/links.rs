@@ -0,0 +1,95 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::memory::HeapSize;
+use crate::storage::Storage;
+
+const DOCUMENT: &str = "links";
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProfilRef {
+    pub class: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct LinkGroups {
+    groups: Vec<BTreeSet<ProfilRef>>,
+}
+
+//groups of profils belonging to the same person, usually the same student in the classes of different years
+pub struct Links {
+    storage: Arc<dyn Storage>,
+    groups: Vec<BTreeSet<ProfilRef>>,
+}
+
+impl Links {
+    pub fn load(storage: Arc<dyn Storage>) -> Self {
+        let groups = storage.load_document(DOCUMENT)
+            .and_then(|d| Ok(d.map(serde_json::from_value::<LinkGroups>).transpose()?));
+        let groups = match groups {
+            Ok(groups) => groups.unwrap_or_default().groups,
+            Err(e) => {
+                println!("Failed to load {}: {:?}", DOCUMENT, e);
+                Vec::new()
+            }
+        };
+        Self { storage, groups }
+    }
+
+    fn save(&self) {
+        let document = serde_json::to_value(LinkGroups { groups: self.groups.clone() }).expect("Failed to serialize links");
+        self.storage.save_document(DOCUMENT, &document)
+            .unwrap_or_else(|e| panic!("Failed to save {}: {:?}", DOCUMENT, e));
+    }
+
+    pub fn link(&mut self, a: ProfilRef, b: ProfilRef) {
+        let mut merged: BTreeSet<ProfilRef> = [a.clone(), b.clone()].into();
+        self.groups.retain(|group| {
+            if group.contains(&a) || group.contains(&b) {
+                merged.extend(group.iter().cloned());
+                false
+            } else {
+                true
+            }
+        });
+        self.groups.push(merged);
+        self.save();
+    }
+
+    pub fn unlink(&mut self, profil: &ProfilRef) -> bool {
+        let mut found = false;
+        for group in self.groups.iter_mut() {
+            found |= group.remove(profil);
+        }
+        self.groups.retain(|group| group.len() > 1);
+        if found {
+            self.save();
+        }
+        found
+    }
+
+    //every profil linked to this one, itself included
+    pub fn linked(&self, profil: &ProfilRef) -> BTreeSet<ProfilRef> {
+        self.groups.iter()
+            .find(|group| group.contains(profil))
+            .cloned()
+            .unwrap_or_else(|| [profil.clone()].into())
+    }
+
+    pub fn groups(&self) -> &[BTreeSet<ProfilRef>] {
+        &self.groups
+    }
+}
+
+impl HeapSize for ProfilRef {
+    fn heap_size(&self) -> usize {
+        self.class.heap_size() + self.name.heap_size()
+    }
+}
+
+impl HeapSize for Links {
+    fn heap_size(&self) -> usize {
+        self.groups.heap_size()
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use common::time::format_unix_time;
+use crate::config::LockoutConfig;
+use crate::links::ProfilRef;
+
+const MAX_TRACKED: usize = 10_000; //profils with failures kept before the oldest windows are dropped
+
+//failed logins per profil in a sliding window, fed by AppState::check_login, nothing here is saved to disk: a restart unlocks everybody
+pub struct Lockouts {
+    config: LockoutConfig,
+    failures: HashMap<ProfilRef, VecDeque<(u64, u64)>>, //unix seconds and hash of the wrong password
+    locked: HashMap<ProfilRef, u64>, //until, unix seconds
+}
+
+//a client polling with a wrong saved password sends the same one again and again, that is one attempt
+fn password_hash(password: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    password.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Lockouts {
+    pub fn new(config: LockoutConfig) -> Self {
+        Self { config, failures: HashMap::new(), locked: HashMap::new() }
+    }
+
+    //until when profil is locked, none when it may log in
+    pub fn locked_until(&self, profil: &ProfilRef, now: u64) -> Option<u64> {
+        self.locked.get(profil).copied().filter(|until| *until > now)
+    }
+
+    //locks profil when it makes max_failures different wrong passwords within window_secs, returns until when
+    fn failed(&mut self, profil: &ProfilRef, password: &str, now: u64) -> Option<u64> {
+        if !self.config.enabled {
+            return None;
+        }
+        if self.failures.len() >= MAX_TRACKED && !self.failures.contains_key(profil) {
+            let window = self.config.window_secs;
+            self.failures.retain(|_, w| w.back().is_some_and(|(time, _)| now.saturating_sub(*time) <= window));
+        }
+        let window = self.failures.entry(profil.clone()).or_default();
+        while window.front().is_some_and(|(time, _)| now.saturating_sub(*time) > self.config.window_secs) {
+            window.pop_front();
+        }
+        let hash = password_hash(password);
+        if !window.iter().any(|(_, h)| *h == hash) {
+            window.push_back((now, hash));
+        }
+        if window.len() < self.config.max_failures {
+            return None;
+        }
+        self.failures.remove(profil);
+        let until = now + self.config.lock_secs;
+        self.locked.insert(profil.clone(), until);
+        Some(until)
+    }
+
+    //until when profil is locked after this login, even a right password is refused while it is
+    pub fn attempt(&mut self, profil: &ProfilRef, password: &str, allowed: bool, now: u64) -> Option<u64> {
+        match self.locked_until(profil, now) {
+            Some(until) => Some(until),
+            None if allowed => {
+                self.failures.remove(profil);
+                None
+            }
+            None => self.failed(profil, password, now).inspect(|until| {
+                println!("{} in {} locked until {} UTC after too many failed logins", profil.name, profil.class, format_unix_time(*until));
+            }),
+        }
+    }
+
+    //Unlock, also forgets the failures so the next wrong password doesn't lock again
+    pub fn unlock(&mut self, profil: &ProfilRef) -> bool {
+        self.failures.remove(profil);
+        self.locked.remove(profil).is_some()
+    }
+
+    pub fn list(&mut self, now: u64) -> Vec<(ProfilRef, u64)> {
+        self.locked.retain(|_, until| *until > now);
+        let mut locked: Vec<(ProfilRef, u64)> = self.locked.iter().map(|(p, until)| (p.clone(), *until)).collect();
+        locked.sort();
+        locked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::LockoutConfig;
+    use crate::links::ProfilRef;
+    use super::Lockouts;
+
+    fn alice() -> ProfilRef {
+        ProfilRef { class: "test".to_string(), name: "Alice".to_string() }
+    }
+
+    fn lockouts() -> Lockouts {
+        Lockouts::new(LockoutConfig { enabled: true, max_failures: 3, window_secs: 60, lock_secs: 600 })
+    }
+
+    #[test]
+    fn locks_after_different_wrong_passwords() {
+        let mut lockouts = lockouts();
+        assert_eq!(lockouts.attempt(&alice(), "a", false, 100), None);
+        assert_eq!(lockouts.attempt(&alice(), "b", false, 101), None);
+        assert_eq!(lockouts.attempt(&alice(), "c", false, 102), Some(702));
+        assert_eq!(lockouts.attempt(&alice(), "right", true, 103), Some(702), "even the right password waits for the end");
+        assert_eq!(lockouts.locked_until(&alice(), 702), None);
+        assert_eq!(lockouts.attempt(&alice(), "right", true, 702), None);
+    }
+
+    #[test]
+    fn same_wrong_password_is_one_attempt() {
+        let mut lockouts = lockouts();
+        for time in 100..110 {
+            assert_eq!(lockouts.attempt(&alice(), "saved", false, time), None);
+        }
+    }
+
+    #[test]
+    fn failures_leave_the_window_and_reset_on_success() {
+        let mut lockouts = lockouts();
+        lockouts.attempt(&alice(), "a", false, 100);
+        lockouts.attempt(&alice(), "b", false, 101);
+        assert_eq!(lockouts.attempt(&alice(), "c", false, 200), None, "the first two are older than the window");
+
+        lockouts.attempt(&alice(), "d", false, 201);
+        lockouts.attempt(&alice(), "right", true, 202);
+        assert_eq!(lockouts.attempt(&alice(), "e", false, 203), None, "a right password forgets the failures");
+    }
+
+    #[test]
+    fn unlock_and_disabled() {
+        let mut lockouts = lockouts();
+        for (time, password) in [(100, "a"), (101, "b"), (102, "c")] {
+            lockouts.attempt(&alice(), password, false, time);
+        }
+        assert_eq!(lockouts.list(103), vec![(alice(), 702)]);
+        assert!(lockouts.unlock(&alice()));
+        assert_eq!(lockouts.attempt(&alice(), "d", false, 104), None);
+
+        let mut disabled = Lockouts::new(LockoutConfig { enabled: false, ..LockoutConfig::default() });
+        for time in 0..100 {
+            assert_eq!(disabled.attempt(&alice(), &time.to_string(), false, time), None);
+        }
+    }
+}
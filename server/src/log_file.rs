@@ -0,0 +1,86 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing_subscriber::fmt::MakeWriter;
+use common::time::{format_unix_time, parse_unix_time};
+use crate::config::LogFileConfig;
+use crate::unix_now;
+
+const PREFIX: &str = "sweat_voter.";
+const SUFFIX: &str = ".log";
+
+//one file per UTC day, "sweat_voter.YYYY-MM-DD.log", the ones older than retention_days are removed when the day changes
+pub struct RollingFile {
+    directory: PathBuf,
+    retention_days: u64, //0 keeps them all
+    current: Mutex<Option<(String, File)>>, //day of the open file
+}
+
+impl RollingFile {
+    pub fn open(config: &LogFileConfig) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&config.directory)?;
+        let file = Self {
+            directory: PathBuf::from(&config.directory),
+            retention_days: config.retention_days,
+            current: Mutex::new(None),
+        };
+        file.remove_old(&today());
+        Ok(file)
+    }
+
+    fn remove_old(&self, today: &str) {
+        if self.retention_days == 0 {
+            return;
+        }
+        let Some(today) = parse_unix_time(today) else {
+            return;
+        };
+        let Ok(entries) = std::fs::read_dir(&self.directory) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let day = name.strip_prefix(PREFIX).and_then(|n| n.strip_suffix(SUFFIX)).and_then(parse_unix_time);
+            if day.is_some_and(|day| day + self.retention_days * 86400 <= today) {
+                match std::fs::remove_file(entry.path()) {
+                    Ok(()) => println!("log file {} removed, older than {} days", name, self.retention_days),
+                    Err(e) => println!("Failed to remove the old log file {}: {:?}", name, e),
+                }
+            }
+        }
+    }
+}
+
+fn today() -> String {
+    format_unix_time(unix_now())[..10].to_string()
+}
+
+impl Write for &RollingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut current = self.current.lock().expect("Failed to lock log file");
+        let today = today();
+        if current.as_ref().is_none_or(|(day, _)| *day != today) {
+            let path = self.directory.join(format!("{}{}{}", PREFIX, today, SUFFIX));
+            *current = Some((today.clone(), OpenOptions::new().create(true).append(true).open(path)?));
+            self.remove_old(&today);
+        }
+        let (_, file) = current.as_mut().expect("opened above");
+        file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.current.lock().expect("Failed to lock log file").as_mut() {
+            Some((_, file)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for RollingFile {
+    type Writer = &'a RollingFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::OnceLock;
+use serde::Deserialize;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+use crate::config::LogFileConfig;
+use crate::log_file::RollingFile;
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+//body of /admin/log_level, an empty filter only reads the current one
+#[derive(Deserialize)]
+pub struct LogLevelRequest {
+    pub token: String,
+    #[serde(default)]
+    pub filter: String,
+}
+
+//RUST_LOG at start, SetLogLevel or /admin/log_level afterwards, for the standard output only:
+//the log file keeps its own filter so the history it holds doesn't depend on what is being looked into
+pub fn init(log_file: &LogFileConfig) {
+    let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
+    let file = log_file.enabled.then(|| match RollingFile::open(log_file) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            println!("Failed to open the log directory {}, logging to the standard output only: {:?}", log_file.directory, e);
+            None
+        }
+    }).flatten();
+    let file_filter = EnvFilter::try_new(&log_file.filter).unwrap_or_else(|e| {
+        println!("invalid log_file.filter {}, using info: {}", log_file.filter, e);
+        EnvFilter::new("info")
+    });
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_filter(filter))
+        .with(file.map(|file| fmt::layer().with_ansi(false).with_writer(file).with_filter(file_filter)))
+        .init();
+    let _ = FILTER.set(handle);
+}
+
+pub fn current() -> String {
+    FILTER.get()
+        .and_then(|handle| handle.with_current(|filter| filter.to_string()).ok())
+        .unwrap_or_default()
+}
+
+//same syntax as RUST_LOG, "info,actix_web=debug", the previous filter stays when this one doesn't parse
+pub fn set(filter: &str) -> Result<String, String> {
+    let parsed = EnvFilter::try_new(filter).map_err(|e| format!("invalid filter {}: {}", filter, e))?;
+    let handle = FILTER.get().ok_or("the log filter isn't reloadable".to_string())?;
+    let previous = current();
+    handle.reload(parsed).map_err(|e| format!("Failed to change the log filter: {}", e))?;
+    println!("log filter changed from \"{}\" to \"{}\"", previous, filter);
+    Ok(format!("log filter is now \"{}\", was \"{}\"", current(), previous))
+}
@@ -0,0 +1,82 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::HttpResponse;
+use common::Group;
+use common::packets::s2c::Locked;
+use crate::app_state::AppState;
+use crate::links::ProfilRef;
+use crate::profils::is_allowed;
+use crate::unix_now;
+
+//what check_login refused while the route ran, answered by refusals instead of the route
+#[derive(Default, Clone, Copy)]
+struct Refused {
+    locked_until: Option<u64>,
+    retry_after: Option<Duration>,
+}
+
+tokio::task_local! {
+    static REFUSED: Cell<Refused>;
+}
+
+//outside of a request, the /ws messages, the refusal is only in the answer of check_login
+fn refuse(refused: Refused) {
+    let _ = REFUSED.try_with(|cell| cell.set(refused));
+}
+
+impl AppState {
+    //is_allowed for the routes: the per profil rate limit and the lockout see every login, a limited or locked one is
+    //refused before its password is looked at, the guests without a login and the unknown names aren't counted
+    pub fn check_login(&self, class: &str, group: &Group, name: &str, password: &str) -> bool {
+        if name.is_empty() || password.is_empty() || !group.profiles.contains_key(name) {
+            return is_allowed(group, name, password);
+        }
+        let profil = ProfilRef { class: class.to_string(), name: name.to_string() };
+
+        if self.settings.read().expect("Failed to lock settings").rate_limit_enabled {
+            let key = format!("{}/{}", profil.class, profil.name);
+            let wait = self.rate_limiter.lock().expect("Failed to lock rate limiter").check(None, Some(key), Instant::now());
+            if let Some(wait) = wait {
+                println!("rate limited: {} in {}, retry in {:.1?}", profil.name, profil.class, wait);
+                refuse(Refused { retry_after: Some(wait), ..Refused::default() });
+                return false;
+            }
+        }
+
+        let mut lockouts = self.lockouts.lock().expect("Failed to lock lockouts");
+        if let Some(until) = lockouts.locked_until(&profil, unix_now()) {
+            refuse(Refused { locked_until: Some(until), ..Refused::default() });
+            return false;
+        }
+        let allowed = is_allowed(group, name, password);
+        if let Some(until) = lockouts.attempt(&profil, password, allowed, unix_now()) {
+            refuse(Refused { locked_until: Some(until), ..Refused::default() });
+            return false;
+        }
+        allowed
+    }
+}
+
+//answers 423 with the end of the lock, or 429 with Retry-After, when check_login refused the login of the request
+pub async fn refusals(request: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let (response, refused) = REFUSED.scope(Cell::new(Refused::default()), async {
+        let response = next.call(request).await;
+        (response, REFUSED.with(Cell::get))
+    }).await;
+    let response = response?;
+
+    let answer = match refused {
+        Refused { locked_until: Some(locked_until), .. } => HttpResponse::build(StatusCode::LOCKED).json(Locked { locked_until }),
+        Refused { retry_after: Some(wait), .. } => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            HttpResponse::TooManyRequests().insert_header((header::RETRY_AFTER, retry_after.to_string())).finish()
+        }
+        Refused { .. } => return Ok(response.map_into_left_body()),
+    };
+    let (request, _) = response.into_parts();
+    Ok(ServiceResponse::new(request, answer).map_into_right_body())
+}
@@ -1,307 +1,472 @@
-use std::collections::{HashMap, BTreeMap};
-use std::fs::File;
-use std::path::{PathBuf};
-use std::sync::{Arc, Mutex};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use actix_cors::Cors;
 use actix_files::Files;
-use actix_web::{web, web::ServiceConfig, App, HttpServer, Responder};
-use actix_web::http::{KeepAlive};
-use actix_web::middleware::Logger;
-use tracing_subscriber::EnvFilter;
-use common::{Group, Nickname};
-use common::packets::c2s::{AddNickname, AskForPersonProfile, DeleteNickname, RequestKind, VoteNickname};
-use common::packets::s2c::{ClassList, PersonProfileResponse, VoteCount};
+use actix_web::{web, web::ServiceConfig, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use actix_web::http::{header, KeepAlive};
+use actix_web::middleware::{from_fn, Logger};
+use common::packets::c2s::{AddComment, AddNickname, ChangeDisplayName, ChangePassword, ClientError, AskForClassSummary, AskForHistory, AskForLeaderboard, AskForNicknameHistory, AskForPersonProfile, AskForSuggestions, AskForVoteSummary, AskForWhoAmI, AskForWordStats, BatchVotes, DeleteComment, DeleteNickname, TransferNickname, VoteNickname};
+use common::packets::s2c::Capabilities;
+use common::time::parse_unix_time;
+use common::version::BuildInfo;
+use crate::app_state::AppState;
+use crate::as_of::AsOfQuery;
+use crate::config::{config_path, ServerConfig};
+use crate::console::CommandInput;
+use crate::jobs::JobQuery;
+use crate::log_level::LogLevelRequest;
+use crate::qr::QrQuery;
+use crate::storage::SaveFormat;
+use crate::trash::RestoreRequest;
+
+mod abuse;
+mod anonymity;
+mod app_state;
+mod archive;
+mod as_of;
+mod avatars;
+mod classes;
+mod comments;
+mod config;
+mod console;
+mod decay;
+mod diff;
+mod display_names;
+mod error_report;
+mod etag;
+mod filter;
+mod grants;
+mod guests;
+mod import;
+mod integrity;
+mod ip_log;
+mod jobs;
+mod leaderboard;
+mod links;
+mod lockout;
+mod login;
+mod log_file;
+mod log_level;
+mod memory;
+mod messages;
+mod notifications;
+mod passwords;
+mod permissions;
+mod profils;
+mod propositions;
+mod push;
+mod qr;
+mod rate_limit;
+mod reload;
+mod repair;
+mod roundtrip;
+mod request_id;
+mod schedule;
+mod setup;
+mod sqlite;
+mod stats;
+mod storage;
+mod suggest;
+mod timing;
+mod trash;
+mod word_stats;
+mod yearbook;
 
 extern crate tracing;
 
 type State = Arc<AppState>;
 
-struct Class {
-    path: PathBuf,
-    participants: Group,
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
 }
 
-impl Class {
-    fn new(path: PathBuf) -> anyhow::Result<Self> {
-        let json = File::open(&path)?;
-        let participants: Group = serde_json::from_reader(json)?;
-        Ok(Self {
-            path,
-            participants,
-        })
-    }
+const CLIENT_BUILD_INFO: &str = "client/dist/build_info.json"; //written by the post_build hook of client/Trunk.toml
+
+//read at every request, the client can be rebuilt while the server runs; an empty one when it was built without the hook
+fn served_client() -> BuildInfo {
+    std::fs::read_to_string(CLIENT_BUILD_INFO).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+#[actix_web::get("/capabilities")]
+async fn capabilities() -> impl Responder {
+    web::Json(Capabilities { server: BuildInfo::current(), client: served_client() })
+}
+
+#[actix_web::get("/class_list")]
+async fn list_class(request: HttpRequest, state: web::Data<State>) -> impl Responder {
+    etag::respond(&request, &state.list_classes())
+}
+
+#[actix_web::post("/person_profile")]
+async fn person_profiles(request: HttpRequest, asked: web::Json<AskForPersonProfile>, state: web::Data<State>) -> impl Responder {
+    etag::respond(&request, &state.person_profiles(&asked))
+}
 
-    fn save(&self) {
-        let file = File::create(&self.path).expect(format!("Failed to create {}", self.path.display()).as_str());
-        serde_json::to_writer_pretty(file, &self.participants).expect(format!("Failed to write {}", self.path.display()).as_str());
+#[actix_web::post("/whoami")]
+async fn who_am_i(asked: web::Json<AskForWhoAmI>, state: web::Data<State>) -> impl Responder {
+    match state.who_am_i(&asked) {
+        Some(identity) => HttpResponse::Ok().json(identity),
+        None => HttpResponse::Unauthorized().finish(),
     }
 }
 
-struct AppState {
-    classes: HashMap<String, Mutex<Class>>, //class name -> Class
+#[actix_web::post("/my_vote_summary")]
+async fn vote_summary(asked: web::Json<AskForVoteSummary>, state: web::Data<State>) -> impl Responder {
+    web::Json(state.vote_summary(&asked))
 }
 
-impl AppState {
-    fn new() -> Self {
-        println!("Creating new AppState");
+#[actix_web::post("/class_summary")]
+async fn class_summary(asked: web::Json<AskForClassSummary>, state: web::Data<State>) -> impl Responder {
+    web::Json(state.class_summary(&asked))
+}
 
-        let mut groups = HashMap::new();
+#[actix_web::post("/word_stats")]
+async fn class_word_stats(asked: web::Json<AskForWordStats>, state: web::Data<State>) -> impl Responder {
+    web::Json(state.word_stats(&asked))
+}
 
-        let files = std::fs::read_dir("./classes").expect("Failed to read dir");
-        for file in files.flatten() {
-            let path = file.path();
-            if path.is_file() && path.extension() == Some("json".as_ref()) {
-                let name = path.file_stem().get_or_insert("unknown".as_ref()).to_string_lossy().to_string();
-                println!("found: {} at {:?}", name, path);
+#[actix_web::post("/leaderboard")]
+async fn top_leaderboard(asked: web::Json<AskForLeaderboard>, state: web::Data<State>) -> impl Responder {
+    web::Json(state.leaderboard(&asked))
+}
 
-                match Class::new(path) {
-                    Ok(class) => {
-                        groups.insert(name, Mutex::new(class));
-                    }
-                    Err(e) => println!("Failed to load class: {:?}", e),
-                }
-            }
-        }
+#[actix_web::post("/suggest")]
+async fn suggest_nicknames(asked: web::Json<AskForSuggestions>, state: web::Data<State>) -> impl Responder {
+    web::Json(state.suggestions(&asked))
+}
 
-        AppState { classes: groups }
-    }
+#[actix_web::post("/profil_history")]
+async fn profil_history(asked: web::Json<AskForHistory>, state: web::Data<State>) -> impl Responder {
+    web::Json(state.history(&asked))
+}
 
-    fn list_classes(&self) -> ClassList {
-        let names = self.classes.keys().cloned().collect::<Vec<String>>();
-        ClassList { names }
-    }
+#[actix_web::post("/nickname_history")]
+async fn nickname_history(asked: web::Json<AskForNicknameHistory>, state: web::Data<State>) -> impl Responder {
+    web::Json(state.nickname_history(&asked))
+}
 
-    fn make_nickname_map(nickname_list: &Vec<Nickname>, editor_name: &str) -> BTreeMap<String, VoteCount> {
-        let mut map = BTreeMap::new();
-        for nickname in nickname_list {
-            map.insert(nickname.nickname.clone(), VoteCount {
-                count: nickname.votes.len(),
-                contain_you: nickname.votes.iter().any(|v| *v == editor_name)
-            });
-        }
-        return map;
-    }
+#[actix_web::post("/add_nickname")]
+async fn add_nickname(add_nickname: web::Json<AddNickname>, state:  web::Data<State>, request: HttpRequest) -> impl Responder {
+    web::Json(request_id::tag(state.add_nickname(&add_nickname, state.client_address(&request))))
+}
 
-    fn convert_group(group: &Group, editor_name: &str) -> BTreeMap<String, BTreeMap<String, VoteCount>> {
-        let mut map = BTreeMap::new();
-        for (name, (_, nicknames)) in &group.profiles {
-            map.insert(name.clone(), Self::make_nickname_map(nicknames, editor_name));
-        }
-        return map;
-    }
+#[actix_web::post("/add_comment")]
+async fn add_comment(add_comment: web::Json<AddComment>, state: web::Data<State>, request: HttpRequest) -> impl Responder {
+    web::Json(request_id::tag(state.add_comment(&add_comment, state.client_address(&request))))
+}
 
-    fn convert_group_custom(group: &Group, editor_name: &str, requested: &Vec<String>) -> BTreeMap<String, BTreeMap<String, VoteCount>> {
-        let mut map = BTreeMap::new();
-        for requested_name in requested {
-            if let Some(( _,nicknames)) = group.profiles.get(requested_name) {
-                map.insert(requested_name.clone(), Self::make_nickname_map(nicknames, editor_name));
-            }
-        }
-        return map;
-    }
+#[actix_web::post("/delete_comment")]
+async fn delete_comment(delete_comment: web::Json<DeleteComment>, state: web::Data<State>, request: HttpRequest) -> impl Responder {
+    web::Json(request_id::tag(state.delete_comment(&delete_comment, state.client_address(&request))))
+}
 
-    fn group_to_response(group: &Group, editor_name: &str, password: &str) -> PersonProfileResponse {
-        let allowed_to_modify = group.profiles.get(editor_name).map_or(false, |(p, _)| p == password);
-        let editor_name = if allowed_to_modify { editor_name } else { "" };
-        PersonProfileResponse {
-            partial_response: false,
-            allowed_to_modify,
-            profiles: Self::convert_group(group, editor_name),
-        }
+#[actix_web::post("/vote_nickname")]
+async fn vote_nickname(vote_nickname: web::Json<VoteNickname>, state:  web::Data<State>, request: HttpRequest) -> impl Responder {
+    web::Json(request_id::tag(state.vote_nickname(&vote_nickname, state.client_address(&request))))
+}
+
+#[actix_web::post("/batch_votes")]
+async fn batch_votes(batch_votes: web::Json<BatchVotes>, state:  web::Data<State>, request: HttpRequest) -> impl Responder {
+    web::Json(request_id::tag(state.batch_votes(&batch_votes, state.client_address(&request))))
+}
+
+#[actix_web::post("/delete_nickname")]
+async fn delete_nickname(delete_nickname: web::Json<DeleteNickname>, state:  web::Data<State>, request: HttpRequest) -> impl Responder {
+    web::Json(request_id::tag(state.delete_nickname(&delete_nickname, state.client_address(&request))))
+}
+
+#[actix_web::post("/transfer_nickname")]
+async fn transfer_nickname(transfer_nickname: web::Json<TransferNickname>, state:  web::Data<State>, request: HttpRequest) -> impl Responder {
+    web::Json(request_id::tag_history(state.transfer_nickname(&transfer_nickname, state.client_address(&request))))
+}
+
+#[actix_web::post("/change_display_name")]
+async fn change_display_name(change: web::Json<ChangeDisplayName>, state:  web::Data<State>, request: HttpRequest) -> impl Responder {
+    web::Json(request_id::tag(state.change_display_name(&change, state.client_address(&request))))
+}
+
+//multipart form with class, editor, password and the png as avatar
+#[actix_web::post("/avatar/upload")]
+async fn upload_avatar(body: web::Bytes, state: web::Data<State>, request: HttpRequest) -> impl Responder {
+    let content_type = request.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    match avatars::multipart_fields(content_type, &body) {
+        Some(fields) => HttpResponse::Ok().json(request_id::tag(state.upload_avatar(&fields, state.client_address(&request)))),
+        None => HttpResponse::BadRequest().body("expected a multipart/form-data body"),
     }
+}
 
-    fn group_to_response_custom(group: &Group, editor_name: &str, password: &str, requested: &Vec<String>) -> PersonProfileResponse {
-        let allowed_to_modify = group.profiles.get(editor_name).map_or(false, |(p, _)| p == password);
-        let editor_name = if allowed_to_modify { editor_name } else { "" };
-        PersonProfileResponse {
-            partial_response: true,
-            allowed_to_modify,
-            profiles: Self::convert_group_custom(group, editor_name, requested),
-        }
+//the hash changes with the image, an answer never goes stale
+#[actix_web::get("/avatar/{hash}")]
+async fn avatar(hash: web::Path<String>) -> impl Responder {
+    match avatars::path(&hash).map(std::fs::read) {
+        Some(Ok(bytes)) => HttpResponse::Ok()
+            .content_type("image/png")
+            .insert_header((header::CACHE_CONTROL, "public, max-age=31536000, immutable"))
+            .body(bytes),
+        _ => HttpResponse::NotFound().finish(),
     }
+}
 
-    fn person_profiles(&self, asked: &AskForPersonProfile) -> PersonProfileResponse {
-        println!("asked: {:?}", asked);
-
-        match (self.classes.get(&asked.class), &asked.kind) {
-            (Some(class), RequestKind::All) => {
-                let lock = class.lock().expect("Failed to lock data");
-                Self::group_to_response(&lock.participants, &asked.editor, &asked.password)
-            },
-            (Some(class), RequestKind::Custom(requested)) => {
-                let lock = class.lock().expect("Failed to lock data");
-                Self::group_to_response_custom(&lock.participants, &asked.editor, &asked.password, &requested)
-            },
-            (None, _) => {
-                PersonProfileResponse {
-                    partial_response: false,
-                    allowed_to_modify: false,
-                    profiles: BTreeMap::new(),
-                }
-            }
-        }
+//panics of the web clients, see error_report
+#[actix_web::post("/client_error")]
+async fn client_error(error: web::Json<ClientError>, state: web::Data<State>) -> impl Responder {
+    if !state.client_errors {
+        return HttpResponse::NotFound().finish();
     }
+    error_report::report_client(&error);
+    HttpResponse::NoContent().finish()
+}
 
-fn add_nickname(&self, add: &AddNickname) -> PersonProfileResponse {
-        let AddNickname {
-            class,
-            editor,
-            password,
-            name,
-            nickname
-        } = add;
-        println!("add_nickname: {} to {} by {} in class {}", nickname, name, editor, class);
-
-        match self.classes.get(class) {
-            None => PersonProfileResponse::default(),
-            Some(class) => { //class exists
-                //check if editor is allowed to modify
-                let mut lock = class.lock().expect("Failed to lock data");
-                let allowed_to_modify = lock.participants.profiles.get(editor).map_or(false, |(p, _)| p == password);
-                if !allowed_to_modify {
-                    return PersonProfileResponse::default();
-                }
-
-                let (_, nicknames) = lock.participants.profiles.get_mut(name).expect("Failed to find name");
-
-                //check if nickname is not already present and add it
-                let trim = nickname.trim();
-                if !trim.is_empty() && nicknames.iter().find(|n| n.nickname == trim).is_none() { //add only if not already present
-                    nicknames.push(Nickname {
-                        nickname: nickname.trim().to_string(),
-                        votes: Vec::new(),
-                    });
-
-                    lock.save();
-                }
-
-                Self::group_to_response_custom(&lock.participants, editor, password, &vec![name.clone()])
-            }
-        }
+#[actix_web::post("/change_password")]
+async fn change_password(change: web::Json<ChangePassword>, state: web::Data<State>) -> impl Responder {
+    match state.change_password(&change) {
+        Some(change) => HttpResponse::Ok().json(request_id::tag_password_change(change)),
+        None => HttpResponse::Unauthorized().finish(),
     }
+}
 
-    fn vote_nickname(&self, vote: &VoteNickname) -> PersonProfileResponse {
-        let VoteNickname {
-            class,
-            name,
-            nickname,
-            voter,
-            password,
-        } = vote;
-        println!("vote_nickname: name: {}, nickname: {}, voter: {}", name, nickname, voter);
-
-        match self.classes.get(class) {
-            None => PersonProfileResponse::default(),
-            Some(class) => { //class exists
-                //check if editor is allowed to modify
-                let mut lock = class.lock().expect("Failed to lock data");
-                let allowed_to_modify = lock.participants.profiles.get(voter).map_or(false, |(p, _)| password == p);
-                if !allowed_to_modify {
-                    return PersonProfileResponse::default();
-                }
-
-                let (_, nicknames) = lock.participants.profiles.get_mut(name).expect("Failed to find name");
-
-                //remove from all other nicknames
-                for nickname in nicknames.iter_mut() {
-                    nickname.votes.retain(|v| *v != *voter);
-                }
-
-                if let Some(nickname) = nicknames.iter_mut().find(|n| n.nickname == *nickname) {
-                    nickname.votes.push(voter.clone());
-                }
-                lock.save();
-
-                Self::group_to_response_custom(&lock.participants, voter, password, &vec![name.clone()])
-            }
-        }
+//live updates of the profiles of a class, see push.rs
+#[actix_web::get("/ws")]
+async fn push_channel(request: HttpRequest, body: web::Payload, state: web::Data<State>) -> actix_web::Result<HttpResponse> {
+    let (response, session, messages) = actix_ws::handle(&request, body)?;
+    actix_web::rt::spawn(push::run_session(state.get_ref().clone(), session, messages));
+    Ok(response)
+}
+
+#[actix_web::get("/job/{id}")]
+async fn job_status(id: web::Path<u64>, query: web::Query<JobQuery>, state: web::Data<State>) -> impl Responder {
+    match &state.admin_token {
+        None => return HttpResponse::NotFound().finish(),
+        Some(token) if *token != query.token => return HttpResponse::Unauthorized().finish(),
+        Some(_) => {}
     }
+    match state.jobs.lock().expect("Failed to lock jobs").status(*id) {
+        Some(status) => HttpResponse::Ok().json(status),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
 
-    fn delete_nickname(&self, delete: &DeleteNickname) -> PersonProfileResponse {
-        let DeleteNickname {
-            class,
-            editor,
-            password,
-            nickname
-        } = delete;
-
-        println!("delete_nickname: name: {}, nickname: {}", editor, nickname);
-
-        match self.classes.get(class) {
-            None => PersonProfileResponse::default(),
-            Some(class) => { //class exists
-                let mut lock = class.lock().expect("Failed to lock data");
-                let allowed_to_modify = lock.participants.profiles.get(editor).map_or(false, |(p, _)| p == password);
-                if !allowed_to_modify {
-                    return PersonProfileResponse::default();
-                }
-
-                let (_ , nicknames) = lock.participants.profiles.get_mut(editor).expect("Failed to find name");
-                nicknames.retain(|n| n.nickname != *nickname);
-                lock.save();
-
-                Self::group_to_response_custom(&lock.participants, &editor, &password, &vec![editor.clone()])
-            }
-        }
+//the classes as they were at some point, to settle when a proposition appeared or got its votes
+#[actix_web::get("/admin/as_of")]
+async fn classes_as_of(query: web::Query<AsOfQuery>, state: web::Data<State>) -> impl Responder {
+    match &state.admin_token {
+        None => return HttpResponse::NotFound().finish(),
+        Some(token) if *token != query.token => return HttpResponse::Unauthorized().finish(),
+        Some(_) => {}
     }
+    let (Some(class), Some(time)) = (state.classes.get(&query.class), parse_unix_time(&query.time)) else {
+        return HttpResponse::NotFound().finish();
+    };
+    let lock = class.read().expect("Failed to lock data");
+    HttpResponse::Ok().json(as_of::as_of(&lock.participants, time))
 }
 
-#[actix_web::get("/class_list")]
-async fn list_class(state: web::Data<State>) -> impl Responder {
-    web::Json(state.list_classes())
+//UndoDelete from outside the console
+#[actix_web::post("/admin/restore_nickname")]
+async fn restore_nickname(restore: web::Json<RestoreRequest>, state: web::Data<State>) -> impl Responder {
+    match &state.admin_token {
+        None => HttpResponse::NotFound().finish(),
+        Some(token) if *token != restore.token => HttpResponse::Unauthorized().finish(),
+        Some(_) => match state.restore_nickname(restore.id, "admin") {
+            Ok(message) => HttpResponse::Ok().body(message),
+            Err(e) => HttpResponse::Conflict().body(e),
+        },
+    }
 }
 
-#[actix_web::post("/person_profile")]
-async fn person_profiles(asked: web::Json<AskForPersonProfile>, state: web::Data<State>) -> impl Responder {
-    web::Json(state.person_profiles(&asked))
+//Unarchive from outside the console
+#[actix_web::post("/admin/unarchive_nickname")]
+async fn unarchive_nickname(restore: web::Json<RestoreRequest>, state: web::Data<State>) -> impl Responder {
+    match &state.admin_token {
+        None => HttpResponse::NotFound().finish(),
+        Some(token) if *token != restore.token => HttpResponse::Unauthorized().finish(),
+        Some(_) => match state.unarchive(restore.id, "admin") {
+            Ok(message) => HttpResponse::Ok().body(message),
+            Err(e) => HttpResponse::Conflict().body(e),
+        },
+    }
 }
 
-#[actix_web::post("/add_nickname")]
-async fn add_nickname(add_nickname: web::Json<AddNickname>, state:  web::Data<State>) -> impl Responder {
-    web::Json(state.add_nickname(&add_nickname))
+//the console commands from outside the server, one line like it is typed
+#[actix_web::post("/admin/cmd_input")]
+async fn command_input(input: web::Json<CommandInput>, state: web::Data<State>) -> impl Responder {
+    match &state.admin_token {
+        None => HttpResponse::NotFound().finish(),
+        Some(token) if *token != input.token => HttpResponse::Unauthorized().finish(),
+        Some(_) => {
+            println!("[{}] cmd_input: {}", request_id::current(), input.line);
+            HttpResponse::Ok().json(console::run(&state, &input.line, &AtomicBool::new(false)))
+        }
+    }
 }
 
-#[actix_web::post("/vote_nickname")]
-async fn vote_nickname(vote_nickname: web::Json<VoteNickname>, state:  web::Data<State>) -> impl Responder {
-    web::Json(state.vote_nickname(&vote_nickname))
+//SetLogLevel from outside the console, to turn on debug logging while looking into a problem
+#[actix_web::post("/admin/log_level")]
+async fn set_log_level(request: web::Json<LogLevelRequest>, state: web::Data<State>) -> impl Responder {
+    match &state.admin_token {
+        None => HttpResponse::NotFound().finish(),
+        Some(token) if *token != request.token => HttpResponse::Unauthorized().finish(),
+        Some(_) if request.filter.is_empty() => HttpResponse::Ok().body(log_level::current()),
+        Some(_) => match log_level::set(&request.filter) {
+            Ok(message) => HttpResponse::Ok().body(message),
+            Err(e) => HttpResponse::BadRequest().body(e),
+        },
+    }
 }
 
-#[actix_web::post("/delete_nickname")]
-async fn delete_nickname(delete_nickname: web::Json<DeleteNickname>, state:  web::Data<State>) -> impl Responder {
-    web::Json(state.delete_nickname(&delete_nickname))
+#[actix_web::get("/qr")]
+async fn qr_code(query: web::Query<QrQuery>, request: HttpRequest) -> impl Responder {
+    let connection = request.connection_info();
+    let url = query.url(&format!("{}://{}", connection.scheme(), connection.host()));
+    match qr::render_svg(&url) {
+        Ok(svg) => HttpResponse::Ok().content_type("image/svg+xml").body(svg),
+        Err(e) => {
+            println!("Failed to render qr code for {}: {:?}", url, e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // install global subscriber configured based on RUST_LOG envvar.
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
-
-    let state = Arc::new(AppState::new());
 
-    HttpServer::new(move || {
+    println!("sweat voter server {}", BuildInfo::current());
+    let config_path = config_path();
+    if std::env::args().any(|a| a == "--init") {
+        if let Err(e) = setup::run(config_path) {
+            println!("Failed to set up the instance: {:?}", e);
+            std::process::exit(1);
+        }
+    }
+    if std::env::args().any(|a| a == "--check-config") {
+        let (_, report) = ServerConfig::check(config_path, true);
+        for warning in &report.warnings {
+            println!("warning: {}", warning);
+        }
+        for error in &report.errors {
+            println!("error: {}", error);
+        }
+        if !report.errors.is_empty() {
+            std::process::exit(1);
+        }
+        println!("{} is valid", config_path.display());
+        return Ok(());
+    }
+    let config = ServerConfig::load(config_path);
+    // install global subscriber configured based on RUST_LOG envvar, SetLogLevel changes it afterwards.
+    log_level::init(&config.log_file);
+    if std::env::args().any(|a| a == "--migrate-passwords") {
+        if let Err(e) = passwords::run(config.save_format, config.audit_key.as_deref()) {
+            println!("Failed to migrate the passwords: {:?}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    error_report::start(&config.error_report);
+    let has_database = config.save_format == SaveFormat::Sqlite && Path::new(sqlite::DATABASE_PATH).is_file();
+    if !Path::new(setup::CLASSES_DIR).is_dir() && !has_database {
+        println!("No {} directory, run the server with --init to create a first instance", setup::CLASSES_DIR);
+        std::process::exit(1);
+    }
+    if let Err(e) = storage::quarantine_corrupt(config.save_format, std::env::args().any(|a| a == "--accept-data-loss")) {
+        println!("Refusing to start: {}", e);
+        std::process::exit(1);
+    }
+    let state = match AppState::new(&config) {
+        Ok(state) => Arc::new(state),
+        Err(e) => {
+            println!("Failed to open the data: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    if config.integrity.enabled {
+        let (problems, serious) = state.check_data();
+        if serious {
+            for line in problems {
+                println!("{}", line);
+            }
+            println!("Starting read-only: the data failed its check, repair it with RepairData then ReadOnly off");
+            state.set_read_only(true);
+        }
+    }
+    console::spawn(state.clone());
+    grants::spawn_expiry(state.clone());
+    stats::spawn_refresh(state.clone());
+    archive::spawn(state.clone(), config.archive.clone());
+    reload::spawn_on_hangup(state.clone());
+
+    let http = &config.http;
+    let mut server = HttpServer::new(move || {
         let cors = Cors::permissive();
 
         App::new()
             .app_data(web::Data::new(state.clone()))
-            .wrap(Logger::default())
+            .wrap(from_fn(integrity::refuse_writes))
+            .wrap(from_fn(login::refusals))
+            .wrap(from_fn(rate_limit::limit))
+            .wrap(from_fn(guests::refuse_closed))
+            .wrap(from_fn(timing::log_slow_requests))
+            .wrap(from_fn(error_report::capture))
+            .wrap(from_fn(request_id::assign))
+            .wrap(Logger::new(r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T id=%{x-request-id}o"#))
             .wrap(cors)
             .configure(routes)
             .service(Files::new("assets", "client/dist/assets").show_files_listing())
             .service(Files::new("", "client/dist/").index_file("index.html"))
 
     })
-        .keep_alive(KeepAlive::Os)
-        .bind(("0.0.0.0", 8080))?
+        .keep_alive(match http.keep_alive_secs {
+            None => KeepAlive::Os,
+            Some(0) => KeepAlive::Disabled,
+            Some(secs) => KeepAlive::Timeout(Duration::from_secs(secs)),
+        })
+        .client_request_timeout(Duration::from_millis(http.client_request_timeout_ms))
+        .max_connections(http.max_connections)
+        .backlog(http.backlog);
+    if http.workers > 0 {
+        server = server.workers(http.workers);
+    }
+
+    server
+        .bind(config.bind.as_str())?
         .run()
         .await
 }
 
 fn routes(cfg: &mut ServiceConfig) {
+    cfg.service(capabilities);
     cfg.service(list_class);
     cfg.service(person_profiles);
+    cfg.service(who_am_i);
+    cfg.service(vote_summary);
+    cfg.service(class_summary);
+    cfg.service(class_word_stats);
+    cfg.service(top_leaderboard);
+    cfg.service(suggest_nicknames);
+    cfg.service(profil_history);
+    cfg.service(nickname_history);
     cfg.service(add_nickname);
+    cfg.service(add_comment);
+    cfg.service(delete_comment);
     cfg.service(delete_nickname);
     cfg.service(vote_nickname);
+    cfg.service(batch_votes);
+    cfg.service(transfer_nickname);
+    cfg.service(change_display_name);
+    cfg.service(upload_avatar);
+    cfg.service(avatar);
+    cfg.service(change_password);
+    cfg.service(client_error);
+    cfg.service(push_channel);
+    cfg.service(job_status);
+    cfg.service(classes_as_of);
+    cfg.service(restore_nickname);
+    cfg.service(unarchive_nickname);
+    cfg.service(command_input);
+    cfg.service(set_log_level);
+    cfg.service(qr_code);
 }
\ No newline at end of file
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::mem::size_of;
+use std::net::IpAddr;
+use std::time::Instant;
+use common::{Comment, Group, Nickname, NicknameEvent, NicknameEventKind, ProfilKind};
+
+//rough heap usage of a store: allocated capacity times element size, plus what the elements own,
+//map nodes and hashing overhead are ignored so it's a lower bound
+pub trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+macro_rules! no_heap {
+    ($($t:ty),*) => {
+        $(impl HeapSize for $t {
+            fn heap_size(&self) -> usize {
+                0
+            }
+        })*
+    };
+}
+
+no_heap!(bool, u64, usize, Instant, IpAddr, ProfilKind);
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for VecDeque<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for BTreeSet<T> {
+    fn heap_size(&self) -> usize {
+        self.len() * size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.len() * size_of::<(K, V)>() + self.iter().map(|(k, v)| k.heap_size() + v.heap_size()).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for HashMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<(K, V)>() + self.iter().map(|(k, v)| k.heap_size() + v.heap_size()).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_size)
+    }
+}
+
+impl<A: HeapSize, B: HeapSize> HeapSize for (A, B) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size()
+    }
+}
+
+impl<A: HeapSize, B: HeapSize, C: HeapSize> HeapSize for (A, B, C) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size() + self.2.heap_size()
+    }
+}
+
+impl HeapSize for NicknameEvent {
+    fn heap_size(&self) -> usize {
+        match &self.kind {
+            NicknameEventKind::Created { by }
+            | NicknameEventKind::Frozen { by }
+            | NicknameEventKind::Unfrozen { by }
+            | NicknameEventKind::Protection { by, .. }
+            | NicknameEventKind::Deleted { by }
+            | NicknameEventKind::Restored { by } => by.heap_size(),
+            NicknameEventKind::Transferred { by, to } => by.heap_size() + to.heap_size(),
+            NicknameEventKind::Merged { by, from } => by.heap_size() + from.heap_size(),
+            NicknameEventKind::VoteCount { .. } => 0,
+        }
+    }
+}
+
+impl HeapSize for Nickname {
+    fn heap_size(&self) -> usize {
+        self.nickname.heap_size() + self.votes.heap_size() + self.history.heap_size() + self.uuid.heap_size() + self.comments.heap_size() + self.voted_at.heap_size()
+    }
+}
+
+impl HeapSize for Comment {
+    fn heap_size(&self) -> usize {
+        self.id.heap_size() + self.author.heap_size() + self.text.heap_size()
+    }
+}
+
+impl HeapSize for Group {
+    fn heap_size(&self) -> usize {
+        self.profiles.heap_size() + self.uuids.heap_size() + self.display_names.heap_size() + self.prompts.heap_size()
+            + self.password_fingerprints.heap_size() + self.password_changed.heap_size() + self.must_change_password.heap_size()
+            + self.rankings.heap_size() + self.board.nicknames.heap_size() + self.board.rankings.heap_size() + self.kinds.heap_size()
+            + self.avatars.heap_size()
+    }
+}
+
+//drops the propositions left without text and gives back the spare capacity, returns how many were dropped
+pub fn compact(group: &mut Group) -> usize {
+    let mut removed = 0;
+    for nicknames in group.all_nicknames_mut() {
+        let before = nicknames.len();
+        nicknames.retain(|n| !n.nickname.trim().is_empty());
+        removed += before - nicknames.len();
+
+        for nickname in nicknames.iter_mut() {
+            nickname.votes.shrink_to_fit();
+            nickname.history.shrink_to_fit();
+            nickname.comments.shrink_to_fit();
+        }
+        nicknames.shrink_to_fit();
+    }
+    removed
+}
@@ -0,0 +1,91 @@
+use common::language::Language;
+use common::ProfilKind;
+use common::time::format_unix_time;
+use crate::request_id;
+
+//the errors a participant can read in the client, worded in the language the request asked for
+pub enum Message {
+    ForbiddenTerm,
+    BatchRefused,
+    ChangePasswordFirst,
+    PasswordTooShort(usize),
+    PasswordUnchanged,
+    DisplayNameTooLong(usize),
+    DisplayNameTooRecent,
+    DisplayNameTaken,
+    VotingOpens(u64), //unix seconds
+    VotingClosed(u64), //unix seconds
+    CommentTooLong(usize),
+    TooManyComments,
+    CommentNotYours,
+    TooManyPropositions(usize),
+    NotATarget(ProfilKind),
+    KindCannotVote(ProfilKind),
+    AvatarNotPng,
+    AvatarTooLarge(usize), //kilobytes
+    AvatarTooWide(u32), //pixels
+    TransferRefused,
+    TransferAnonymous,
+}
+
+impl Message {
+    pub fn text(&self, language: Language) -> String {
+        match (self, language) {
+            (Message::ForbiddenTerm, Language::French) => "Ce surnom contient un terme interdit".to_string(),
+            (Message::ForbiddenTerm, Language::English) => "This nickname contains a forbidden term".to_string(),
+            (Message::BatchRefused, Language::French) => "Certains votes n'ont pas pu être appliqués, aucun n'a été enregistré".to_string(),
+            (Message::BatchRefused, Language::English) => "Some votes could not be applied, none were recorded".to_string(),
+            (Message::ChangePasswordFirst, Language::French) => "Choisissez un nouveau mot de passe avant de continuer".to_string(),
+            (Message::ChangePasswordFirst, Language::English) => "Choose a new password before going on".to_string(),
+            (Message::PasswordTooShort(min), Language::French) => format!("Le mot de passe doit faire au moins {} caractères", min),
+            (Message::PasswordTooShort(min), Language::English) => format!("The password must be at least {} characters long", min),
+            (Message::PasswordUnchanged, Language::French) => "Le nouveau mot de passe doit être différent de l'ancien".to_string(),
+            (Message::PasswordUnchanged, Language::English) => "The new password must differ from the old one".to_string(),
+            (Message::DisplayNameTooLong(max), Language::French) => format!("Le nom affiché est limité à {} caractères", max),
+            (Message::DisplayNameTooLong(max), Language::English) => format!("The display name is limited to {} characters", max),
+            (Message::DisplayNameTooRecent, Language::French) => "Le nom affiché a été changé trop récemment".to_string(),
+            (Message::DisplayNameTooRecent, Language::English) => "The display name was changed too recently".to_string(),
+            (Message::DisplayNameTaken, Language::French) => "Ce nom est déjà utilisé dans la classe".to_string(),
+            (Message::DisplayNameTaken, Language::English) => "This name is already used in the class".to_string(),
+            (Message::VotingOpens(opens), Language::French) => format!("Le vote de cette classe ouvre le {} UTC", format_unix_time(*opens)),
+            (Message::VotingOpens(opens), Language::English) => format!("Voting in this class opens on {} UTC", format_unix_time(*opens)),
+            (Message::VotingClosed(closed), Language::French) => format!("Le vote de cette classe est clos depuis le {} UTC", format_unix_time(*closed)),
+            (Message::VotingClosed(closed), Language::English) => format!("Voting in this class closed on {} UTC", format_unix_time(*closed)),
+            (Message::CommentTooLong(max), Language::French) => format!("Un commentaire est limité à {} caractères", max),
+            (Message::CommentTooLong(max), Language::English) => format!("A comment is limited to {} characters", max),
+            (Message::TooManyComments, Language::French) => "Ce surnom a déjà trop de commentaires".to_string(),
+            (Message::TooManyComments, Language::English) => "This nickname already has too many comments".to_string(),
+            (Message::CommentNotYours, Language::French) => "Seul son auteur peut supprimer ce commentaire".to_string(),
+            (Message::CommentNotYours, Language::English) => "Only its author can delete this comment".to_string(),
+            (Message::TooManyPropositions(max), Language::French) => format!("Cette personne a déjà le maximum de {} surnoms, votez plutôt pour l'un d'eux", max),
+            (Message::TooManyPropositions(max), Language::English) => format!("This person already has the maximum of {} nicknames, vote for one of them instead", max),
+            (Message::NotATarget(kind), Language::French) => format!("Les surnoms sont pour les élèves, ce profil ({}) n'en reçoit pas", french_kind(*kind)),
+            (Message::NotATarget(kind), Language::English) => format!("Nicknames are for the students, this profil ({}) gets none", kind.name()),
+            (Message::KindCannotVote(kind), Language::French) => format!("Un profil {} ne peut ni proposer ni voter", french_kind(*kind)),
+            (Message::KindCannotVote(kind), Language::English) => format!("A {} profil can neither propose nor vote", kind.name()),
+            (Message::AvatarNotPng, Language::French) => "L'avatar doit être une image png".to_string(),
+            (Message::AvatarNotPng, Language::English) => "The avatar must be a png image".to_string(),
+            (Message::AvatarTooLarge(max), Language::French) => format!("L'avatar est limité à {} Ko", max),
+            (Message::AvatarTooLarge(max), Language::English) => format!("The avatar is limited to {} KB", max),
+            (Message::AvatarTooWide(max), Language::French) => format!("L'avatar est limité à {} pixels de côté", max),
+            (Message::AvatarTooWide(max), Language::English) => format!("The avatar is limited to {} pixels per side", max),
+            (Message::TransferRefused, Language::French) => "Seul l'auteur peut transférer ce surnom, à un participant de la classe".to_string(),
+            (Message::TransferRefused, Language::English) => "Only its author can hand this nickname over, to a participant of the class".to_string(),
+            (Message::TransferAnonymous, Language::French) => "Les surnoms d'une classe anonyme ne peuvent pas être transférés".to_string(),
+            (Message::TransferAnonymous, Language::English) => "The nicknames of an anonymous class can't be handed over".to_string(),
+        }
+    }
+
+    //in the language of the request being served
+    pub fn localized(&self) -> String {
+        self.text(request_id::language())
+    }
+}
+
+fn french_kind(kind: ProfilKind) -> &'static str {
+    match kind {
+        ProfilKind::Student => "élève",
+        ProfilKind::Teacher => "enseignant",
+        ProfilKind::Staff => "personnel",
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use serde_json::json;
+use common::language::Language;
+use common::{Group, Nickname, Target};
+use crate::app_state::AppState;
+use crate::config::WebhookConfig;
+use crate::stats::shown_name;
+
+const QUEUE: usize = 256; //messages waiting for Discord, the others are dropped rather than slowing the votes down
+
+//what happened to a proposition, posted to the webhooks that asked for it
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    Proposed,
+    Votes(usize), //count after a vote, posted when it is the threshold of a webhook
+    Deleted,
+}
+
+struct Message {
+    url: String,
+    content: String,
+}
+
+//sends on its own thread like the error reports, the requests never wait for Discord
+pub struct Notifier {
+    webhooks: Vec<WebhookConfig>,
+    messages: Option<SyncSender<Message>>,
+}
+
+impl Notifier {
+    pub fn start(webhooks: &[WebhookConfig]) -> Self {
+        if webhooks.is_empty() {
+            return Self { webhooks: Vec::new(), messages: None };
+        }
+        let (messages, receiver) = mpsc::sync_channel(QUEUE);
+        std::thread::spawn(move || run(receiver));
+        Self { webhooks: webhooks.to_vec(), messages: Some(messages) }
+    }
+
+    //returns whether a webhook wanted it, even when the queue dropped it
+    fn send(&self, class: &str, event: Event, content: &str) -> bool {
+        let Some(messages) = &self.messages else {
+            return false;
+        };
+        let wanted = self.webhooks.iter()
+            .filter(|w| w.classes.is_empty() || w.classes.iter().any(|c| c == class))
+            .filter(|w| match event {
+                Event::Proposed => w.new_propositions,
+                Event::Votes(count) => w.vote_threshold == Some(count),
+                Event::Deleted => w.deletions,
+            });
+        let mut sent = false;
+        for webhook in wanted {
+            sent = true;
+            match messages.try_send(Message { url: webhook.url.clone(), content: content.to_string() }) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => println!("notification queue full, message dropped"),
+                Err(TrySendError::Disconnected(_)) => println!("notification thread stopped, message dropped"),
+            }
+        }
+        sent
+    }
+}
+
+fn run(messages: Receiver<Message>) {
+    for message in messages {
+        //nobody gets pinged by a proposition that happens to contain @everyone
+        let body = json!({ "content": message.content, "allowed_mentions": { "parse": [] } });
+        let sent = ureq::post(&message.url)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string());
+        if let Err(e) = sent {
+            println!("Failed to post the notification: {}", e);
+        }
+    }
+}
+
+fn text(language: Language, event: Event, class: &str, target: &str, nickname: &str) -> String {
+    match (language, event) {
+        (Language::French, Event::Proposed) => format!("Nouvelle proposition pour {} ({}) : « {} »", target, class, nickname),
+        (Language::French, Event::Votes(count)) => format!("« {} » atteint {} votes pour {} ({})", nickname, count, target, class),
+        (Language::French, Event::Deleted) => format!("« {} » a été supprimé pour {} ({})", nickname, target, class),
+        (Language::English, Event::Proposed) => format!("New proposition for {} ({}): \"{}\"", target, class, nickname),
+        (Language::English, Event::Votes(count)) => format!("\"{}\" reached {} votes for {} ({})", nickname, count, target, class),
+        (Language::English, Event::Deleted) => format!("\"{}\" was deleted for {} ({})", nickname, target, class),
+    }
+}
+
+impl AppState {
+    //in the language of the server, the name shown is the one the participant chose; the internal jokes and what the
+    //content filter matches stay inside the class, returns whether a webhook wanted it
+    pub fn announce(&self, class: &str, group: &Group, target: &Target, nickname: &Nickname, event: Event) -> bool {
+        if nickname.internal_joke || self.filter.lock().expect("Failed to lock filter").check(&nickname.nickname).is_some() {
+            return false;
+        }
+        let target = match target {
+            Target::Profil(name) => shown_name(group, name),
+            Target::Class(_) => match self.language {
+                Language::French => "la classe".to_string(),
+                Language::English => "the class".to_string(),
+            },
+        };
+        self.notifier.send(class, event, &text(self.language, event, class, &target, &nickname.nickname))
+    }
+}
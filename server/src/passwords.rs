@@ -0,0 +1,416 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use common::time::format_unix_time;
+use common::Group;
+use common::packets::c2s::ChangePassword;
+use common::packets::s2c::{PasswordChange, PersonProfileResponse};
+use crate::anonymity;
+use crate::app_state::AppState;
+use crate::classes::new_uuid;
+use crate::messages::Message;
+use crate::request_id;
+use crate::storage::{self, SaveFormat, Storage};
+use crate::unix_now;
+
+const PREFIX: &str = "pbkdf2-sha256$";
+const LEGACY_PREFIX: &str = "sha256$"; //single salted hash of the first migrations, replaced when the password changes
+const ITERATIONS: u32 = 100_000; //kept in each hash, raising it leaves the existing ones valid
+const MAX_DERIVED: usize = 10_000; //outputs remembered, forgotten all at once past it
+const REPORT_PATH: &str = "./password_migration.txt";
+const DOCUMENT: &str = "password_audit"; //held the fingerprint key next to the data before it moved to the config
+const MIN_LENGTH: usize = 8; //what --init hands out
+
+//"pbkdf2-sha256$<iterations>$<salt>$<hex>"
+pub fn hash(password: &str) -> String {
+    let salt = new_uuid();
+    format!("{}{}${}${}", PREFIX, ITERATIONS, salt, pbkdf2(password, &salt, ITERATIONS))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+//PBKDF2-HMAC-SHA256 with a single block of output
+fn pbkdf2(password: &str, salt: &str, iterations: u32) -> String {
+    let mac = Hmac::<Sha256>::new_from_slice(password.as_bytes()).expect("Hmac takes keys of any length");
+    let mut block = mac.clone().chain_update(salt.as_bytes()).chain_update(1u32.to_be_bytes()).finalize().into_bytes();
+    let mut output = block;
+    for _ in 1..iterations {
+        block = mac.clone().chain_update(block).finalize().into_bytes();
+        output.iter_mut().zip(block.iter()).for_each(|(o, b)| *o ^= b);
+    }
+    to_hex(&output)
+}
+
+fn legacy_digest(salt: &str, password: &str) -> String {
+    to_hex(&Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(password.as_bytes())
+        .finalize())
+}
+
+//same password, same fingerprint, in every class of the instance, the key comes from the config and never
+//sits in the storage, without it the fingerprints could be brute-forced from a copy of the data
+pub fn fingerprint(key: &str, password: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("Hmac takes keys of any length");
+    mac.update(password.as_bytes());
+    to_hex(&mac.finalize().into_bytes())
+}
+
+//the password is checked on every request, an output already derived since the start is found again with a keyed
+//hash living in memory only instead of going through the iterations again
+struct Derived {
+    key: String,
+    outputs: HashMap<(u32, String, String), String>, //iterations, salt, fingerprint of the password -> output
+}
+
+fn derived() -> &'static Mutex<Derived> {
+    static DERIVED: OnceLock<Mutex<Derived>> = OnceLock::new();
+    DERIVED.get_or_init(|| Mutex::new(Derived { key: new_uuid(), outputs: HashMap::new() }))
+}
+
+//pbkdf2 remembered, the lock isn't held during the iterations
+fn derive_with(password: &str, salt: &str, iterations: u32) -> String {
+    let id = {
+        let derived = derived().lock().expect("Failed to lock derived passwords");
+        let id = (iterations, salt.to_string(), fingerprint(&derived.key, password));
+        if let Some(output) = derived.outputs.get(&id) {
+            return output.clone();
+        }
+        id
+    };
+    let output = pbkdf2(password, salt, iterations);
+    let mut derived = derived().lock().expect("Failed to lock derived passwords");
+    if derived.outputs.len() >= MAX_DERIVED {
+        derived.outputs.clear();
+    }
+    derived.outputs.insert(id, output.clone());
+    output
+}
+
+//as slow to brute-force as the stored hashes, for what must not be computed back from the data alone
+pub fn derive(password: &str, salt: &str) -> String {
+    derive_with(password, salt, ITERATIONS)
+}
+
+fn matches_hash(stored: &str, password: &str) -> bool {
+    stored.strip_prefix(PREFIX)
+        .and_then(|rest| rest.split_once('$'))
+        .and_then(|(iterations, rest)| Some((iterations.parse::<u32>().ok()?, rest.split_once('$')?)))
+        .is_some_and(|(iterations, (salt, hex))| derive_with(password, salt, iterations) == hex)
+}
+
+//the fingerprints made with the key older versions kept in DOCUMENT can be brute-forced by whoever reads the data,
+//the key is wiped and every class drops them, AuditPasswords covers them again once their owners change password
+pub fn forget_stored_key(storage: &dyn Storage) -> anyhow::Result<bool> {
+    if storage.load_document(DOCUMENT)?.is_none_or(|d| d.get("key").is_none()) {
+        return Ok(false);
+    }
+    for (class, group) in storage.load_classes() {
+        if let Ok(mut group) = group {
+            if !group.password_fingerprints.is_empty() {
+                group.password_fingerprints.clear();
+                storage.save_class(&class, &group)?;
+            }
+        }
+    }
+    storage.save_document(DOCUMENT, &serde_json::json!({}))?;
+    Ok(true)
+}
+
+//once a class is migrated a stored value that isn't a hash never matches, even typed as is
+pub fn matches(group: &Group, stored: &str, password: &str) -> bool {
+    if !group.hashed_passwords {
+        return stored == password;
+    }
+    match stored.strip_prefix(LEGACY_PREFIX).and_then(|rest| rest.split_once('$')) {
+        Some((salt, hex)) => legacy_digest(salt, password) == hex,
+        None => matches_hash(stored, password),
+    }
+}
+
+//replaces every plaintext password of group by its hash, returns the accounts that had an empty one
+fn migrate_group(group: &mut Group, key: Option<&str>) -> Vec<String> {
+    let mut empty = Vec::new();
+    for (name, (password, _)) in group.profiles.iter_mut() {
+        if password.is_empty() {
+            empty.push(name.clone());
+        }
+        if let Some(key) = key {
+            group.password_fingerprints.insert(name.clone(), fingerprint(key, password));
+        }
+        *password = hash(password);
+    }
+    group.hashed_passwords = true;
+    empty
+}
+
+//what AuditPasswords compares, none for a migrated account without a fingerprint, or every migrated one
+//when the config has no audit_key
+fn account_fingerprint(group: &Group, key: Option<&str>, name: &str, stored: &str) -> Option<String> {
+    match key {
+        _ if group.hashed_passwords => group.password_fingerprints.get(name).cloned(),
+        Some(key) => Some(fingerprint(key, stored)),
+        None => Some(stored.to_string()),
+    }
+}
+
+//--migrate-passwords: hashes the passwords of every class not migrated yet, then writes what it did to REPORT_PATH,
+//takes the data lock so it can't run next to a server
+pub fn run(save_format: SaveFormat, key: Option<&str>) -> anyhow::Result<()> {
+    //the memory format reads the json files, they are what has to be migrated then
+    let storage = storage::open(if save_format == SaveFormat::Memory { SaveFormat::Json } else { save_format })?;
+    forget_stored_key(storage.as_ref())?;
+    let mut report = format!("password migration, {} UTC\n", format_unix_time(unix_now()));
+    let (mut migrated, mut empty) = (0, Vec::new());
+    for (class, group) in storage.load_classes() {
+        let mut group = match group {
+            Ok(group) => group,
+            Err(e) => {
+                writeln!(report, "{}: failed to load, left untouched: {:?}", class, e)?;
+                continue;
+            }
+        };
+        if group.hashed_passwords {
+            writeln!(report, "{}: already migrated", class)?;
+            continue;
+        }
+        let class_empty = migrate_group(&mut group, key);
+        storage.save_class(&class, &group)?;
+        writeln!(report, "{}: {} accounts migrated", class, group.profiles.len())?;
+        migrated += group.profiles.len();
+        empty.extend(class_empty.into_iter().map(|name| format!("{} ({})", name, class)));
+    }
+    writeln!(report, "{} accounts migrated in total", migrated)?;
+    if key.is_none() {
+        writeln!(report, "no audit_key in the config, AuditPasswords can't compare the migrated accounts")?;
+    }
+    if !empty.is_empty() {
+        //still hashed, they log in with an empty password until someone gives them one
+        writeln!(report, "{} accounts with an empty password: {}", empty.len(), empty.join(", "))?;
+    }
+
+    std::fs::write(Path::new(REPORT_PATH), &report)?;
+    print!("{}", report);
+    println!("report written to {}", REPORT_PATH);
+    Ok(())
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Account {
+    class: String,
+    name: String,
+    changed: bool, //picked their own password since
+}
+
+impl AppState {
+    //the modifications of a login flagged by --init or ForcePasswordChange wait for the new password,
+    //the client asks for it at login already, this covers the other clients
+    pub fn refuse_until_changed(group: &Group, editor: &str, password: &str, names: &Vec<String>) -> Option<PersonProfileResponse> {
+        if !group.must_change_password.contains(editor) {
+            return None;
+        }
+        let mut response = Self::group_to_response_custom(group, editor, password, names);
+        response.error = Some(Message::ChangePasswordFirst.localized());
+        Some(response)
+    }
+
+    //accounts sharing a password with another one, the handed out or imported ones mostly,
+    //and how many couldn't be compared
+    fn shared_passwords(&self) -> (Vec<Vec<Account>>, usize) {
+        let mut by_fingerprint: BTreeMap<String, Vec<Account>> = BTreeMap::new();
+        let mut unknown = 0;
+        for (class, group) in &self.classes {
+            let group = &group.read().expect("Failed to lock data").participants;
+            for (name, (stored, _)) in &group.profiles {
+                match account_fingerprint(group, self.audit_key.as_deref(), name, stored) {
+                    Some(fingerprint) => by_fingerprint.entry(fingerprint).or_default()
+                        .push(Account { class: class.clone(), name: name.clone(), changed: group.password_changed.contains(name) }),
+                    None => unknown += 1,
+                }
+            }
+        }
+        let mut shared: Vec<_> = by_fingerprint.into_values().filter(|accounts| accounts.len() > 1).collect();
+        for accounts in &mut shared {
+            accounts.sort();
+        }
+        shared.sort_by_key(|accounts| std::cmp::Reverse(accounts.len()));
+        (shared, unknown)
+    }
+
+    pub fn audit_passwords(&self) -> Vec<String> {
+        let (shared, unknown) = self.shared_passwords();
+        let mut lines = Vec::new();
+        for accounts in &shared {
+            let never_changed: Vec<String> = accounts.iter().filter(|a| !a.changed).map(|a| format!("{} ({})", a.name, a.class)).collect();
+            lines.push(format!("{} accounts share a password, {} never changed it: {}", accounts.len(), never_changed.len(), never_changed.join(", ")));
+        }
+        if shared.is_empty() {
+            lines.push("no shared password".to_string());
+        } else {
+            lines.push("ForcePasswordChange --shared to ask them for a new one at login".to_string());
+        }
+        if unknown > 0 && self.audit_key.is_none() {
+            lines.push(format!("{} accounts left out, their passwords are hashed and the config has no audit_key", unknown));
+        } else if unknown > 0 {
+            lines.push(format!("{} accounts left out, hashed without a fingerprint, they get one when they change password", unknown));
+        }
+        lines
+    }
+
+    //flags every account found by audit_passwords that never changed its password
+    pub fn force_shared_password_change(&self) -> Vec<String> {
+        let (shared, _) = self.shared_passwords();
+        let mut forced = 0;
+        for account in shared.into_iter().flatten().filter(|a| !a.changed) {
+            let mut lock = self.classes[&account.class].write().expect("Failed to lock data");
+            forced += lock.participants.must_change_password.insert(account.name) as usize;
+            lock.save();
+        }
+        vec![format!("{} accounts will be asked for a new password", forced)]
+    }
+
+    pub fn force_password_change(&self, class: &str, name: &str) -> Vec<String> {
+        let Some(group) = self.classes.get(class) else {
+            return vec![format!("unknown class: {}", class)];
+        };
+        let mut lock = group.write().expect("Failed to lock data");
+        if !lock.participants.profiles.contains_key(name) {
+            return vec![format!("{} not found in {}", name, class)];
+        }
+        lock.participants.must_change_password.insert(name.to_string());
+        lock.save();
+        vec![format!("{} ({}) will be asked for a new password", name, class)]
+    }
+
+    pub fn change_password(&self, change: &ChangePassword) -> Option<PasswordChange> {
+        let ChangePassword {
+            class,
+            editor,
+            password,
+            new_password,
+        } = change;
+        println!("[{}] change_password: {} in class {}", request_id::current(), editor, class);
+
+        let mut lock = self.classes.get(class)?.write().expect("Failed to lock data");
+        if !self.check_login(class, &lock.participants, editor, password) {
+            return None;
+        }
+        let error = if new_password.chars().count() < MIN_LENGTH {
+            Some(Message::PasswordTooShort(MIN_LENGTH).localized())
+        } else if new_password == password {
+            Some(Message::PasswordUnchanged.localized())
+        } else {
+            None
+        };
+        if error.is_none() {
+            let group = &mut lock.participants;
+            let stored = if group.hashed_passwords {
+                match &self.audit_key {
+                    Some(key) => group.password_fingerprints.insert(editor.clone(), fingerprint(key, new_password)),
+                    None => group.password_fingerprints.remove(editor),
+                };
+                hash(new_password)
+            } else {
+                new_password.clone()
+            };
+            if let Some((password, _)) = group.profiles.get_mut(editor) {
+                *password = stored;
+            }
+            anonymity::rekey(group, editor, password, new_password);
+            group.password_changed.insert(editor.clone());
+            group.must_change_password.remove(editor);
+            lock.save();
+        }
+        Some(PasswordChange { error, request_id: None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::Group;
+    use crate::storage::{MemoryStorage, Storage};
+    use super::*;
+
+    fn hashed_group() -> Group {
+        Group { hashed_passwords: true, ..Group::default() }
+    }
+
+    //RFC 7914, section 11
+    #[test]
+    fn pbkdf2_test_vectors() {
+        assert_eq!(pbkdf2("password", "salt", 1), "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b");
+        assert_eq!(pbkdf2("password", "salt", 4096), "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a");
+    }
+
+    #[test]
+    fn hash_matches_only_its_password() {
+        let group = hashed_group();
+        let stored = hash("alicepass1");
+        assert!(stored.starts_with(PREFIX));
+        assert!(matches(&group, &stored, "alicepass1"));
+        assert!(!matches(&group, &stored, "alicepass2"));
+        assert_ne!(stored, hash("alicepass1"), "each hash has its own salt");
+        assert!(!matches(&group, "alicepass1", "alicepass1"), "a plaintext value never matches once migrated");
+        assert!(matches(&Group::default(), "alicepass1", "alicepass1"));
+    }
+
+    #[test]
+    fn legacy_hashes_still_match() {
+        let stored = format!("{}salt${}", LEGACY_PREFIX, legacy_digest("salt", "bobpass12"));
+        assert!(matches(&hashed_group(), &stored, "bobpass12"));
+        assert!(!matches(&hashed_group(), &stored, "bobpass13"));
+    }
+
+    #[test]
+    fn fewer_iterations_are_read_from_the_hash() {
+        let stored = format!("{}2$salt${}", PREFIX, pbkdf2("password", "salt", 2));
+        assert!(matches(&hashed_group(), &stored, "password"));
+        assert!(!matches(&hashed_group(), &format!("{}two$salt$00", PREFIX), "password"));
+    }
+
+    #[test]
+    fn derived_outputs_are_remembered() {
+        let first = derive_with("carolpass1", "derive test", 3);
+        assert_eq!(first, pbkdf2("carolpass1", "derive test", 3));
+        assert_eq!(derive_with("carolpass1", "derive test", 3), first);
+        assert_ne!(derive_with("carolpass1", "other salt", 3), first);
+        assert_ne!(derive_with("carolpass2", "derive test", 3), first);
+    }
+
+    #[test]
+    fn migration_keeps_fingerprints_only_with_a_key() {
+        let mut group = Group::default();
+        group.profiles.insert("Alice".to_string(), ("alicepass1".to_string(), Vec::new()));
+        group.profiles.insert("Bob".to_string(), (String::new(), Vec::new()));
+        let mut without_key = group.clone();
+
+        assert_eq!(migrate_group(&mut group, Some("key")), vec!["Bob".to_string()]);
+        assert!(group.hashed_passwords);
+        assert_eq!(group.password_fingerprints.get("Alice"), Some(&fingerprint("key", "alicepass1")));
+        assert!(group.profiles["Alice"].0.starts_with(PREFIX));
+
+        migrate_group(&mut without_key, None);
+        assert!(without_key.password_fingerprints.is_empty());
+    }
+
+    #[test]
+    fn stored_key_is_forgotten() {
+        let storage = MemoryStorage::default();
+        assert!(!forget_stored_key(&storage).expect("Failed to check the key"));
+
+        let mut group = Group::default();
+        group.password_fingerprints.insert("Alice".to_string(), fingerprint("stored", "alicepass1"));
+        storage.save_class("test", &group).expect("Failed to save");
+        storage.save_document(DOCUMENT, &serde_json::json!({ "key": "stored" })).expect("Failed to save");
+
+        assert!(forget_stored_key(&storage).expect("Failed to forget the key"));
+        assert_eq!(storage.load_document(DOCUMENT).expect("Failed to load"), Some(serde_json::json!({})));
+        let (_, group) = storage.load_classes().pop().expect("Failed to find the class");
+        assert!(group.expect("Failed to load the class").password_fingerprints.is_empty());
+    }
+}
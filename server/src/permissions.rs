@@ -0,0 +1,160 @@
+use std::path::Path;
+use common::ProfilKind;
+use common::time::format_unix_time;
+use crate::app_state::AppState;
+use crate::guests::Endpoint;
+use crate::unix_now;
+
+//the kind of a profil is its role in its class, a student may do everything there,
+//guest grants let them vote in another one and the guest policy is what visitors without a login read
+const GUEST: &str = "vote";
+
+fn kind_permission(kind: ProfilKind) -> &'static str {
+    match kind {
+        ProfilKind::Student => "propose, vote, transfer or delete own propositions, change display name",
+        ProfilKind::Teacher => "propose and vote for the students, transfer or delete own propositions, change display name, not proposed for",
+        ProfilKind::Staff => "read, not proposed for",
+    }
+}
+
+//one line of the matrix, the visitors without a login have no name nor class
+pub struct Permission {
+    pub class: String,
+    pub name: String,
+    pub role: &'static str,
+    pub permission: String,
+    pub scope: String, //the class it applies in
+    pub expires: Option<u64>,
+}
+
+impl Permission {
+    fn describe(&self, now: u64) -> String {
+        let who = match (self.name.as_str(), self.class.as_str()) {
+            ("", _) => "visitors".to_string(),
+            (name, class) => format!("{} ({})", name, class),
+        };
+        let expires = match self.expires {
+            Some(expires) if expires <= now => format!(" until {} UTC, expired", format_unix_time(expires)),
+            Some(expires) => format!(" until {} UTC", format_unix_time(expires)),
+            None => String::new(),
+        };
+        format!("{} [{}]: {} in {}{}", who, self.role, self.permission, self.scope, expires)
+    }
+}
+
+impl AppState {
+    //every participant of only, or of every class, with the grants given to them, then the guests invited into only
+    pub fn permissions(&self, only: Option<&str>, name: Option<&str>) -> Vec<Permission> {
+        let mut classes: Vec<&String> = self.classes.keys().filter(|c| only.is_none_or(|only| only == *c)).collect();
+        self.collation.sort(&mut classes);
+        let grants = self.grants.lock().expect("Failed to lock grants");
+
+        let mut permissions = Vec::new();
+        for class in classes {
+            let group = self.classes[class].read().expect("Failed to lock data");
+            let mut names: Vec<&String> = group.participants.profiles.keys().filter(|n| name.is_none_or(|name| name == *n)).collect();
+            self.collation.sort(&mut names);
+            for participant in names {
+                let kind = group.participants.kind(participant);
+                permissions.push(Permission {
+                    class: class.clone(),
+                    name: participant.clone(),
+                    role: kind.name(),
+                    permission: kind_permission(kind).to_string(),
+                    scope: class.clone(),
+                    expires: None,
+                });
+                for grant in grants.list().iter().filter(|g| g.guest.class == *class && g.guest.name == *participant) {
+                    permissions.push(Permission {
+                        class: class.clone(),
+                        name: participant.clone(),
+                        role: "guest",
+                        permission: GUEST.to_string(),
+                        scope: grant.class.clone(),
+                        expires: Some(grant.expires),
+                    });
+                }
+            }
+            //the guests invited from other classes, the whole matrix already lists them under their own
+            for grant in grants.list().iter().filter(|g| g.class == *class && name.is_none() && only.is_some()) {
+                permissions.push(Permission {
+                    class: grant.guest.class.clone(),
+                    name: grant.guest.name.clone(),
+                    role: "guest",
+                    permission: GUEST.to_string(),
+                    scope: class.clone(),
+                    expires: Some(grant.expires),
+                });
+            }
+        }
+
+        if name.is_none() {
+            for endpoint in Endpoint::ALL {
+                permissions.push(Permission {
+                    class: String::new(),
+                    name: String::new(),
+                    role: "visitor",
+                    permission: format!("{}: {}", endpoint.name(), self.guest_access(endpoint).name()),
+                    scope: only.unwrap_or("every class").to_string(),
+                    expires: None,
+                });
+            }
+        }
+        permissions
+    }
+
+    //SetKind, the propositions already made for someone who is no longer a target stay, new ones and votes are refused
+    pub fn set_kind(&self, class: &str, name: &str, kind: ProfilKind) -> Vec<String> {
+        let Some(group) = self.classes.get(class) else {
+            return vec![format!("unknown class: {}", class)];
+        };
+        let mut lock = group.write().expect("Failed to lock data");
+        if !lock.participants.profiles.contains_key(name) {
+            return vec![format!("{} not found in {}", name, class)];
+        }
+        if kind == ProfilKind::Student {
+            lock.participants.kinds.remove(name);
+        } else {
+            lock.participants.kinds.insert(name.to_string(), kind);
+        }
+        lock.save();
+        vec![format!("{} ({}) is now {}", name, class, kind.name())]
+    }
+
+    pub fn view_permissions(&self, class: Option<&str>, name: Option<&str>) -> Vec<String> {
+        if let Some(class) = class.filter(|c| !self.classes.contains_key(*c)) {
+            return vec![format!("unknown class: {}", class)];
+        }
+        let permissions = self.permissions(class, name);
+        if permissions.is_empty() {
+            return vec![format!("no participant named {}", name.unwrap_or_default())];
+        }
+        let now = unix_now();
+        permissions.iter().map(|p| p.describe(now)).collect()
+    }
+
+    //the whole matrix for a review in a spreadsheet
+    pub fn export_permissions(&self, path: &Path) -> Vec<String> {
+        let permissions = self.permissions(None, None);
+        let mut csv = String::from("class,name,role,permission,scope,expires\n");
+        for p in &permissions {
+            let expires = p.expires.map(|e| format!("{} UTC", format_unix_time(e))).unwrap_or_default();
+            let fields = [p.class.as_str(), &p.name, p.role, &p.permission, &p.scope, &expires];
+            csv.push_str(&fields.map(csv_field).join(","));
+            csv.push('\n');
+        }
+        match std::fs::write(path, csv) {
+            Ok(()) => vec![format!("{} permissions written to {}", permissions.len(), path.display())],
+            Err(e) => vec![format!("Failed to write {}: {:?}", path.display(), e)],
+        }
+    }
+}
+
+//quoted when it holds a separator, names with commas are common enough
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
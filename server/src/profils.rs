@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, BTreeSet};
+use common::{Group, Nickname};
+use common::packets::c2s::{AskForClassSummary, AskForPersonProfile, AskForVoteSummary, AskForWhoAmI, RequestKind};
+use common::packets::s2c::{ClassSummary, CommentView, PersonProfileResponse, VoteCount, VoteReceipt, VoteSummary, WhoAmI};
+use crate::anonymity::author_key;
+use crate::app_state::AppState;
+use crate::decay::{score, weighted_votes};
+use crate::guests::{Endpoint, GuestAccess};
+use crate::passwords;
+use crate::unix_now;
+
+//true when name is a participant of the group and password is theirs
+pub fn is_allowed(group: &Group, name: &str, password: &str) -> bool {
+    group.profiles.get(name).is_some_and(|(p, _)| passwords::matches(group, p, password))
+}
+
+//names chosen by the participants, sent with every list of profils
+pub fn display_names(group: &Group) -> BTreeMap<String, String> {
+    group.display_names.iter().map(|(name, (shown, _))| (name.clone(), shown.clone())).collect()
+}
+
+//what a projected view may show of a proposition
+pub fn shown_in_public(group: &Group, nickname: &Nickname) -> bool {
+    !nickname.internal_joke && nickname.votes.len() >= group.public_min_votes
+}
+
+//guests allowed to see the propositions but not their votes
+fn hide_counts(response: &mut PersonProfileResponse) {
+    for vote in response.profiles.values_mut().flat_map(|nicknames| nicknames.values_mut()).chain(response.board.values_mut()) {
+        vote.count = 0;
+        vote.weight = None;
+    }
+    response.counts_hidden = true;
+}
+
+//where each proposition is among the choices of editor from 1, the ones deleted since are skipped
+fn ranks<'a>(rankings: Option<&'a BTreeMap<String, Vec<String>>>, nicknames: &[Nickname], editor_name: &str) -> BTreeMap<&'a str, usize> {
+    let ranking = rankings.and_then(|r| r.get(editor_name)).map_or(&[][..], |r| r.as_slice());
+    ranking.iter()
+        .filter(|n| nicknames.iter().any(|x| x.nickname == **n && x.votes.iter().any(|v| v == editor_name)))
+        .enumerate()
+        .map(|(i, n)| (n.as_str(), i + 1))
+        .collect()
+}
+
+//what voter supports for name right now, sent back with each vote so the client can show what was recorded
+pub fn receipt(group: &Group, name: &str, voter: &str) -> VoteReceipt {
+    let nicknames = group.profiles.get(name).map_or(&[][..], |(_, nicknames)| nicknames.as_slice());
+    let ranks = ranks(group.rankings.get(name), nicknames, voter);
+    let mut supported: Vec<&Nickname> = nicknames.iter().filter(|n| n.votes.iter().any(|v| v == voter)).collect();
+    supported.sort_by_key(|n| ranks.get(n.nickname.as_str()).copied().unwrap_or(usize::MAX));
+    VoteReceipt {
+        name: name.to_string(),
+        nicknames: supported.into_iter().map(|n| n.nickname.clone()).collect(),
+        time: unix_now(),
+    }
+}
+
+//the comments as the editor recorded as you sees them, none for the views without a login
+fn comments(nickname: &Nickname, you: &str) -> Vec<CommentView> {
+    if you.is_empty() {
+        return Vec::new();
+    }
+    nickname.comments.iter().map(|c| CommentView {
+        id: c.id.clone(),
+        author: c.author.clone(),
+        time: c.time,
+        text: c.text.clone(),
+        yours: c.author == you,
+    }).collect()
+}
+
+impl AppState {
+    //you is the author key of the editor, empty without a login
+    fn make_nickname_map<'a>(group: &Group, nickname_list: impl IntoIterator<Item = &'a Nickname>, editor_name: &str, you: &str, ranks: &BTreeMap<&str, usize>) -> BTreeMap<String, VoteCount> {
+        let mut map = BTreeMap::new();
+        let now = unix_now();
+        for nickname in nickname_list {
+            map.insert(nickname.nickname.clone(), VoteCount {
+                count: nickname.votes.len(),
+                contain_you: nickname.votes.iter().any(|v| *v == editor_name),
+                protection: nickname.protection,
+                your_rank: ranks.get(nickname.nickname.as_str()).copied(),
+                comments: comments(nickname, you),
+                yours: false,
+                weight: weighted_votes(group, nickname, now),
+            });
+        }
+        map
+    }
+
+    //sent whole with every response, there are few of them
+    fn convert_board(group: &Group, editor_name: &str, you: &str) -> BTreeMap<String, VoteCount> {
+        let nicknames = &group.board.nicknames;
+        let mut map = Self::make_nickname_map(group, nicknames, editor_name, you, &ranks(Some(&group.board.rankings), nicknames, editor_name));
+        if !you.is_empty() {
+            for nickname in nicknames.iter().filter(|n| n.author() == Some(you)) {
+                if let Some(vote) = map.get_mut(&nickname.nickname) {
+                    vote.yours = true;
+                }
+            }
+        }
+        map
+    }
+
+    fn convert_group(group: &Group, editor_name: &str, you: &str) -> BTreeMap<String, BTreeMap<String, VoteCount>> {
+        let mut map = BTreeMap::new();
+        for (name, (_, nicknames)) in &group.profiles {
+            map.insert(name.clone(), Self::make_nickname_map(group, nicknames, editor_name, you, &ranks(group.rankings.get(name), nicknames, editor_name)));
+        }
+        map
+    }
+
+    fn convert_group_custom(group: &Group, editor_name: &str, you: &str, requested: &Vec<String>) -> BTreeMap<String, BTreeMap<String, VoteCount>> {
+        let mut map = BTreeMap::new();
+        for requested_name in requested {
+            if let Some(( _,nicknames)) = group.profiles.get(requested_name) {
+                map.insert(requested_name.clone(), Self::make_nickname_map(group, nicknames, editor_name, you, &ranks(group.rankings.get(requested_name), nicknames, editor_name)));
+            }
+        }
+        map
+    }
+
+    fn convert_group_top(group: &Group, editor_name: &str, you: &str, count: usize) -> BTreeMap<String, BTreeMap<String, VoteCount>> {
+        let mut map = BTreeMap::new();
+        for (name, (_, nicknames)) in &group.profiles {
+            let mut top: Vec<&Nickname> = nicknames.iter().collect();
+            let now = unix_now();
+            top.sort_by(|a, b| score(group, b, now).total_cmp(&score(group, a, now)));
+            top.truncate(count);
+            map.insert(name.clone(), Self::make_nickname_map(group, top, editor_name, you, &ranks(group.rankings.get(name), nicknames, editor_name)));
+        }
+        map
+    }
+
+    //what a projected view may show, editor rights are never given with it
+    fn public_response(group: &Group) -> PersonProfileResponse {
+        let mut profiles = BTreeMap::new();
+        for (name, (_, nicknames)) in &group.profiles {
+            let shown = nicknames.iter().filter(|n| shown_in_public(group, n));
+            profiles.insert(name.clone(), Self::make_nickname_map(group, shown, "", "", &BTreeMap::new()));
+        }
+        PersonProfileResponse {
+            partial_response: false,
+            allowed_to_modify: false,
+            profiles,
+            error: None,
+            counts_hidden: false,
+            display_names: display_names(group),
+            avatars: group.avatars.clone(),
+            prompts: group.prompts.clone(),
+            request_id: None,
+            receipts: Vec::new(),
+            board: Self::make_nickname_map(group, group.board.nicknames.iter().filter(|n| shown_in_public(group, n)), "", "", &BTreeMap::new()),
+            kinds: group.kinds.clone(),
+        }
+    }
+
+    pub fn group_to_response(group: &Group, editor_name: &str, password: &str) -> PersonProfileResponse {
+        let allowed_to_modify = is_allowed(group, editor_name, password);
+        let editor_name = if allowed_to_modify { editor_name } else { "" };
+        let you = if allowed_to_modify { author_key(group, editor_name, password) } else { String::new() };
+        PersonProfileResponse {
+            partial_response: false,
+            allowed_to_modify,
+            profiles: Self::convert_group(group, editor_name, &you),
+            error: None,
+            counts_hidden: false,
+            display_names: display_names(group),
+            avatars: group.avatars.clone(),
+            prompts: group.prompts.clone(),
+            request_id: None,
+            receipts: Vec::new(),
+            board: Self::convert_board(group, editor_name, &you),
+            kinds: group.kinds.clone(),
+        }
+    }
+
+    pub fn group_to_response_custom(group: &Group, editor_name: &str, password: &str, requested: &Vec<String>) -> PersonProfileResponse {
+        let allowed_to_modify = is_allowed(group, editor_name, password);
+        let editor_name = if allowed_to_modify { editor_name } else { "" };
+        let you = if allowed_to_modify { author_key(group, editor_name, password) } else { String::new() };
+        PersonProfileResponse {
+            partial_response: true,
+            allowed_to_modify,
+            profiles: Self::convert_group_custom(group, editor_name, &you, requested),
+            error: None,
+            counts_hidden: false,
+            display_names: display_names(group),
+            avatars: group.avatars.clone(),
+            prompts: group.prompts.clone(),
+            request_id: None,
+            receipts: Vec::new(),
+            board: Self::convert_board(group, editor_name, &you),
+            kinds: group.kinds.clone(),
+        }
+    }
+
+    pub fn group_to_response_top(group: &Group, editor_name: &str, password: &str, count: usize) -> PersonProfileResponse {
+        let allowed_to_modify = is_allowed(group, editor_name, password);
+        let editor_name = if allowed_to_modify { editor_name } else { "" };
+        let you = if allowed_to_modify { author_key(group, editor_name, password) } else { String::new() };
+        PersonProfileResponse {
+            partial_response: false,
+            allowed_to_modify,
+            profiles: Self::convert_group_top(group, editor_name, &you, count),
+            error: None,
+            counts_hidden: false,
+            display_names: display_names(group),
+            avatars: group.avatars.clone(),
+            prompts: group.prompts.clone(),
+            request_id: None,
+            receipts: Vec::new(),
+            board: Self::convert_board(group, editor_name, &you),
+            kinds: group.kinds.clone(),
+        }
+    }
+
+    //a guest invited from another class sees their own votes but can't propose
+    pub fn group_to_response_guest(group: &Group, voter_key: &str, requested: &Vec<String>) -> PersonProfileResponse {
+        PersonProfileResponse {
+            partial_response: true,
+            allowed_to_modify: false,
+            profiles: Self::convert_group_custom(group, voter_key, "", requested),
+            error: None,
+            counts_hidden: false,
+            display_names: display_names(group),
+            avatars: group.avatars.clone(),
+            prompts: group.prompts.clone(),
+            request_id: None,
+            receipts: Vec::new(),
+            board: Self::convert_board(group, voter_key, ""),
+            kinds: group.kinds.clone(),
+        }
+    }
+
+    pub fn person_profiles(&self, asked: &AskForPersonProfile) -> PersonProfileResponse {
+        let Some(class) = self.classes.get(&asked.class) else {
+            return PersonProfileResponse::default();
+        };
+        let lock = class.read().expect("Failed to lock data");
+        let access = self.access(Endpoint::PersonProfile, &asked.class, &lock.participants, &asked.editor, &asked.password);
+        Self::profiles_with(&lock.participants, asked, access)
+    }
+
+    //the pushes of a subscription, its login was counted once when it came, see push::run_session
+    pub fn pushed_profiles(&self, asked: &AskForPersonProfile) -> PersonProfileResponse {
+        let Some(class) = self.classes.get(&asked.class) else {
+            return PersonProfileResponse::default();
+        };
+        let lock = class.read().expect("Failed to lock data");
+        let access = self.access_if(Endpoint::PersonProfile, is_allowed(&lock.participants, &asked.editor, &asked.password));
+        Self::profiles_with(&lock.participants, asked, access)
+    }
+
+    fn profiles_with(group: &Group, asked: &AskForPersonProfile, access: GuestAccess) -> PersonProfileResponse {
+        let mut response = match (access, &asked.kind) {
+            (GuestAccess::Closed, _) => return PersonProfileResponse::default(),
+            //without counts the cut of Top would still tell which propositions lead
+            (GuestAccess::Propositions, RequestKind::Top(_)) | (_, RequestKind::All) => Self::group_to_response(group, &asked.editor, &asked.password),
+            (_, RequestKind::Custom(requested)) => Self::group_to_response_custom(group, &asked.editor, &asked.password, requested),
+            (_, RequestKind::Top(count)) => Self::group_to_response_top(group, &asked.editor, &asked.password, *count),
+            (_, RequestKind::Public) => Self::public_response(group),
+        };
+        if access == GuestAccess::Propositions {
+            hide_counts(&mut response);
+        }
+        response
+    }
+
+    pub fn who_am_i(&self, asked: &AskForWhoAmI) -> Option<WhoAmI> {
+        let lock = self.classes.get(&asked.class)?.read().expect("Failed to lock data");
+        if !self.check_login(&asked.class, &lock.participants, &asked.editor, &asked.password) {
+            return None;
+        }
+        Some(WhoAmI {
+            class: asked.class.clone(),
+            name: asked.editor.clone(),
+            uuid: lock.participants.uuids.get(&asked.editor).cloned(),
+            must_change_password: lock.participants.must_change_password.contains(&asked.editor),
+        })
+    }
+
+    pub fn vote_summary(&self, asked: &AskForVoteSummary) -> VoteSummary {
+        let AskForVoteSummary {
+            class,
+            editor,
+            password,
+        } = asked;
+
+        match self.classes.get(class) {
+            None => VoteSummary::default(),
+            Some(class) => {
+                let lock = class.read().expect("Failed to lock data");
+                let allowed_to_modify = self.check_login(&asked.class, &lock.participants, editor, password);
+                if !allowed_to_modify {
+                    return VoteSummary::default();
+                }
+
+                let voted = lock.participants.profiles.iter()
+                    .filter(|(_, (_, nicknames))| nicknames.iter().any(|n| n.votes.contains(editor)))
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                VoteSummary { voted }
+            }
+        }
+    }
+
+    pub fn class_summary(&self, asked: &AskForClassSummary) -> ClassSummary {
+        let Some(class) = self.classes.get(&asked.class) else {
+            return ClassSummary::default();
+        };
+        let lock = class.read().expect("Failed to lock data");
+        match self.access(Endpoint::ClassSummary, &asked.class, &lock.participants, &asked.editor, &asked.password) {
+            GuestAccess::Closed => ClassSummary::default(),
+            GuestAccess::Propositions => ClassSummary {
+                voters: 0,
+                leaders: BTreeMap::new(),
+                counts_hidden: true,
+                ..summarize(&asked.class, &lock.participants)
+            },
+            GuestAccess::Counts => summarize(&asked.class, &lock.participants),
+        }
+    }
+}
+
+//the whole summary, whoever asks for it
+pub fn summarize(class: &str, group: &Group) -> ClassSummary {
+    let profiles = &group.profiles;
+    let now = unix_now();
+
+    let votes: BTreeSet<&String> = profiles.values()
+        .flat_map(|(_, nicknames)| nicknames.iter().flat_map(|n| &n.votes))
+        .collect();
+    let voters = profiles.keys().filter(|name| votes.contains(name)).count();
+    let leaders = profiles.iter()
+        .filter_map(|(name, (_, nicknames))| {
+            let leader = nicknames.iter().filter(|n| !n.votes.is_empty()).max_by(|a, b| score(group, a, now).total_cmp(&score(group, b, now)))?;
+            Some((name.clone(), (leader.nickname.clone(), leader.votes.len())))
+        })
+        .collect();
+    ClassSummary {
+        class: class.to_string(),
+        participants: profiles.len(),
+        voters,
+        propositions: profiles.values().map(|(_, nicknames)| nicknames.len()).sum(),
+        leaders,
+        counts_hidden: false,
+    }
+}
@@ -0,0 +1,693 @@
+use std::net::IpAddr;
+use std::collections::{BTreeMap, BTreeSet};
+use common::{Group, Nickname, NicknameEvent, NicknameEventKind, Overflow, ProfilKind, Protection, Target, VoteMode};
+use common::packets::c2s::{AddNickname, AskForNicknameHistory, BatchVotes, DeleteNickname, TransferNickname, VoteNickname};
+use common::packets::s2c::{NicknameHistory, PersonProfileResponse};
+use crate::anonymity::author_key;
+use crate::app_state::AppState;
+use crate::guests::{Endpoint, GuestAccess};
+use crate::links::ProfilRef;
+use crate::messages::Message;
+use crate::notifications::Event;
+use crate::classes::new_uuid;
+use crate::filter::Severity;
+use crate::grants::guest_key;
+use crate::profils::receipt;
+use crate::request_id;
+use crate::schedule::{voting_closed, voting_not_open};
+use crate::unix_now;
+
+impl AppState {
+    pub fn nickname_history(&self, asked: &AskForNicknameHistory) -> NicknameHistory {
+        let Some(class) = self.classes.get(&asked.class) else {
+            return NicknameHistory::default();
+        };
+        let lock = class.read().expect("Failed to lock data");
+        let access = self.access(Endpoint::NicknameHistory, &asked.class, &lock.participants, &asked.editor, &asked.password);
+        let events = lock.participants.profiles.get(&asked.name)
+            .and_then(|(_, nicknames)| nicknames.iter().find(|n| n.nickname == asked.nickname))
+            .filter(|_| access != GuestAccess::Closed)
+            .map(|n| n.history.iter()
+                .filter(|e| access == GuestAccess::Counts || !matches!(e.kind, NicknameEventKind::VoteCount { .. }))
+                .cloned()
+                .collect())
+            .unwrap_or_default();
+        NicknameHistory {
+            name: asked.name.clone(),
+            nickname: asked.nickname.clone(),
+            events,
+            error: None,
+            request_id: None,
+        }
+    }
+
+    pub fn add_nickname(&self, add: &AddNickname, address: Option<IpAddr>) -> PersonProfileResponse {
+        let AddNickname {
+            class,
+            editor,
+            password,
+            nickname,
+            ..
+        } = add;
+        let target = add.target();
+        println!("[{}] add_nickname: {} to {} by {} in class {}", request_id::current(), nickname, target, editor, class);
+
+        let class_name = class;
+        let requested: Vec<String> = target.profil().map(str::to_string).into_iter().collect();
+        match self.classes.get(class) {
+            _ if is_other_class(&target, class) => PersonProfileResponse::default(),
+            None => PersonProfileResponse::default(),
+            Some(class) => { //class exists
+                //check if editor is allowed to modify
+                let mut lock = class.write().expect("Failed to lock data");
+                let allowed_to_modify = self.check_login(class_name, &lock.participants, editor, password);
+                if !allowed_to_modify {
+                    return PersonProfileResponse::default();
+                }
+                if let Some(refused) = Self::refuse_until_changed(&lock.participants, editor, password, &requested) {
+                    return refused;
+                }
+                let refused = voting_closed(&lock.participants, unix_now())
+                    .or_else(|| refused_kind(&lock.participants, editor, &target).map(|m| m.localized()));
+                if let Some(error) = refused {
+                    let mut response = Self::group_to_response_custom(&lock.participants, editor, password, &requested);
+                    response.error = Some(error);
+                    return response;
+                }
+                self.record_address(class_name, editor, address);
+
+                let author = author_key(&lock.participants, editor, password);
+                let nicknames = lock.participants.nicknames_mut(&target).expect("Failed to find name");
+
+                //check if nickname is not already present and add it
+                let trim = nickname.trim();
+                if !trim.is_empty() && nicknames.iter().find(|n| n.nickname == trim).is_none() { //add only if not already present
+                    let filtered = self.filter.lock().expect("Failed to lock filter").check(trim);
+                    if let Some((Severity::Severe, _)) = filtered {
+                        println!("[{}] add_nickname: {} refused by the content filter", request_id::current(), trim);
+                        let mut response = Self::group_to_response_custom(&lock.participants, editor, password, &requested);
+                        response.error = Some(Message::ForbiddenTerm.localized());
+                        return response;
+                    }
+
+                    let cap = self.settings.read().expect("Failed to lock settings").proposition_cap;
+                    if cap.is_reached(nicknames.len()) {
+                        match lowest_deletable(nicknames).filter(|_| cap.overflow == Overflow::ReplaceLowest) {
+                            Some(position) => {
+                                let replaced = nicknames.remove(position);
+                                println!("[{}] add_nickname: {} replaced by {} for {}, proposition cap reached", request_id::current(), replaced.nickname, trim, target);
+                                self.trash.lock().expect("Failed to lock trash").bury(class_name, &target, position, replaced, "proposition cap".to_string());
+                            }
+                            None => {
+                                let mut response = Self::group_to_response_custom(&lock.participants, editor, password, &requested);
+                                response.error = Some(Message::TooManyPropositions(cap.max).localized());
+                                return response;
+                            }
+                        }
+                    }
+
+                    nicknames.push(Nickname {
+                        nickname: trim.to_string(),
+                        votes: Vec::new(),
+                        protection: Protection::Open,
+                        history: vec![NicknameEvent { time: unix_now(), kind: NicknameEventKind::Created { by: author.clone() } }],
+                        uuid: self.stable_ids.then(new_uuid),
+                        internal_joke: false,
+                        comments: Vec::new(),
+                        voted_at: BTreeMap::new(),
+                        announced_votes: BTreeSet::new(),
+                    });
+
+                    if let Some((Severity::Mild, term)) = filtered {
+                        let mut abuse = self.abuse.lock().expect("Failed to lock abuse detector");
+                        abuse.flag_content(class_name, &author, &target.to_string(), trim, &term);
+                        if abuse.auto_freeze() {
+                            Self::set_protection(&mut lock.participants, &target, trim, Protection::Locked, "content filter");
+                        }
+                    }
+
+                    lock.save();
+                    self.notify(class_name, target.profil().unwrap_or_default());
+                    if let Some(proposed) = lock.participants.nicknames(&target).and_then(|n| n.last()) {
+                        self.announce(class_name, &lock.participants, &target, proposed, Event::Proposed);
+                    }
+                }
+
+                Self::group_to_response_custom(&lock.participants, editor, password, &requested)
+            }
+        }
+    }
+
+    pub fn vote_nickname(&self, vote: &VoteNickname, address: Option<IpAddr>) -> PersonProfileResponse {
+        let VoteNickname {
+            class,
+            name,
+            nickname,
+            voter,
+            password,
+            voter_class,
+            withdraw,
+            rank,
+            ..
+        } = vote;
+        let target = vote.target();
+        println!("[{}] vote_nickname: name: {}, nickname: {}, voter: {}", request_id::current(), target, nickname, voter);
+
+        //a guest logs in with their own class, checked before locking this one, the class board is for its members
+        let guest = voter_class.as_ref().filter(|c| *c != class).map(|c| ProfilRef { class: c.clone(), name: voter.clone() });
+        if guest.as_ref().is_some_and(|guest| target.profil().is_none() || !self.is_allowed_between(guest, password, class)) || is_other_class(&target, class) {
+            return PersonProfileResponse::default();
+        }
+        let requested: Vec<String> = target.profil().map(str::to_string).into_iter().collect();
+        let voter_key = guest.as_ref().map_or_else(|| voter.clone(), guest_key);
+
+        let class_name = class;
+        match self.classes.get(class) {
+            None => PersonProfileResponse::default(),
+            Some(class) => { //class exists
+                //check if editor is allowed to modify
+                let mut lock = class.write().expect("Failed to lock data");
+                let allowed_to_modify = guest.is_some() || self.check_login(class_name, &lock.participants, voter, password);
+                if !allowed_to_modify {
+                    return PersonProfileResponse::default();
+                }
+                if let Some(refused) = Self::refuse_until_changed(&lock.participants, voter, password, &requested).filter(|_| guest.is_none()) {
+                    return refused;
+                }
+                self.record_address(guest.as_ref().map_or(class_name, |g| &g.class), voter, address);
+
+                let mode = self.vote_mode(&lock.participants);
+                let change = if *withdraw { VoteChange::Withdraw(nickname) } else { VoteChange::Cast { nickname, rank: *rank } };
+                //the kind of a guest is the one of their own class, they were invited to vote
+                let voter_kind = if guest.is_some() { "" } else { voter.as_str() };
+                let refused = voting_not_open(&lock.participants)
+                    .or_else(|| refused_kind(&lock.participants, voter_kind, &target).map(|m| m.localized()));
+                let nicknames = lock.participants.nicknames(&target).expect("Failed to find name");
+                if refused.is_none() && !touches_locked(nicknames, &voter_key, mode, change) {
+                    if cast_vote(&mut lock.participants, mode, &target, &voter_key, change) {
+                        //the abuse detection follows the votes for the participants only
+                        if target.profil().is_some() {
+                            self.record_vote(&mut lock.participants, class_name, &voter_key, name, nickname, address);
+                        }
+                        self.announce_votes(class_name, &mut lock.participants, &target, nickname);
+                    }
+                    lock.save();
+                    self.notify(class_name, target.profil().unwrap_or_default());
+                }
+
+                let mut response = match guest {
+                    Some(_) => Self::group_to_response_guest(&lock.participants, &voter_key, &requested),
+                    None => Self::group_to_response_custom(&lock.participants, voter, password, &requested),
+                };
+                response.error = refused;
+                response.receipts = requested.iter().map(|name| receipt(&lock.participants, name, &voter_key)).collect();
+                response
+            }
+        }
+    }
+
+    pub fn batch_votes(&self, batch: &BatchVotes, address: Option<IpAddr>) -> PersonProfileResponse {
+        let BatchVotes {
+            class,
+            voter,
+            password,
+            operations,
+        } = batch;
+        println!("[{}] batch_votes: {} operations by {} in class {}", request_id::current(), operations.len(), voter, class);
+
+        let class_name = class;
+        let Some(class) = self.classes.get(class) else {
+            return PersonProfileResponse::default();
+        };
+        let mut lock = class.write().expect("Failed to lock data");
+        if !self.check_login(class_name, &lock.participants, voter, password) {
+            return PersonProfileResponse::default();
+        }
+        let mut names: Vec<String> = operations.iter().map(|o| o.name.clone()).collect();
+        names.sort();
+        names.dedup();
+        if let Some(refused) = Self::refuse_until_changed(&lock.participants, voter, password, &names) {
+            return refused;
+        }
+        let refused = voting_not_open(&lock.participants)
+            .or_else(|| names.iter().find_map(|name| refused_kind(&lock.participants, voter, &Target::Profil(name.clone()))).map(|m| m.localized()));
+        if let Some(error) = refused {
+            let mut response = Self::group_to_response_custom(&lock.participants, voter, password, &names);
+            response.error = Some(error);
+            return response;
+        }
+        self.record_address(class_name, voter, address);
+
+        //all or nothing, checked on the state before the batch since protection levels can't change during it
+        let mode = self.vote_mode(&lock.participants);
+        let applicable = operations.iter().all(|o| {
+            lock.participants.profiles.get(&o.name).is_some_and(|(_, nicknames)| {
+                let exists = o.nickname.as_ref().is_none_or(|n| nicknames.iter().any(|x| x.nickname == *n));
+                exists && !touches_locked(nicknames, voter, mode, VoteChange::of_batch(o.nickname.as_deref()))
+            })
+        });
+        if !applicable {
+            let mut response = Self::group_to_response_custom(&lock.participants, voter, password, &names);
+            response.error = Some(Message::BatchRefused.localized());
+            return response;
+        }
+
+        for operation in operations {
+            let change = VoteChange::of_batch(operation.nickname.as_deref());
+            let target = Target::Profil(operation.name.clone());
+            if let (true, Some(nickname)) = (cast_vote(&mut lock.participants, mode, &target, voter, change), &operation.nickname) {
+                self.record_vote(&mut lock.participants, class_name, voter, &operation.name, nickname, address);
+                self.announce_votes(class_name, &mut lock.participants, &target, nickname);
+            }
+        }
+        lock.save();
+        for name in &names {
+            self.notify(class_name, name);
+        }
+
+        let mut response = Self::group_to_response_custom(&lock.participants, voter, password, &names);
+        response.receipts = names.iter().map(|name| receipt(&lock.participants, name, voter)).collect();
+        response
+    }
+
+    //after a vote landed, a webhook posts when the count is its threshold, once per proposition and threshold
+    fn announce_votes(&self, class: &str, group: &mut Group, target: &Target, nickname: &str) {
+        let find = |n: &Nickname| n.nickname == nickname;
+        let Some(voted) = group.nicknames(target).and_then(|n| n.iter().find(|n| find(n))) else {
+            return;
+        };
+        let count = voted.votes.len();
+        if voted.announced_votes.contains(&count) || !self.announce(class, group, target, voted, Event::Votes(count)) {
+            return;
+        }
+        if let Some(voted) = group.nicknames_mut(target).and_then(|n| n.iter_mut().find(|n| find(n))) {
+            voted.announced_votes.insert(count);
+        }
+    }
+
+    pub fn vote_mode(&self, group: &Group) -> VoteMode {
+        group.vote_mode.unwrap_or_else(|| self.settings.read().expect("Failed to lock settings").vote_mode)
+    }
+
+    //feeds the abuse detection and freezes what it flags when auto_freeze is set
+    fn record_vote(&self, group: &mut Group, class: &str, voter: &str, name: &str, nickname: &str, address: Option<IpAddr>) {
+        let mut abuse = self.abuse.lock().expect("Failed to lock abuse detector");
+        let flags = abuse.record_vote(class, voter, name, nickname, address);
+        if abuse.auto_freeze() {
+            for flag in &flags {
+                for (name, nickname) in &flag.propositions {
+                    Self::set_protection(group, &Target::Profil(name.clone()), nickname, Protection::Locked, "abuse detection");
+                }
+            }
+        }
+    }
+
+    pub fn delete_nickname(&self, delete: &DeleteNickname, address: Option<IpAddr>) -> PersonProfileResponse {
+        let DeleteNickname {
+            class,
+            editor,
+            password,
+            nickname,
+            ..
+        } = delete;
+        let target = delete.target();
+
+        println!("[{}] delete_nickname: name: {}, nickname: {}", request_id::current(), target, nickname);
+
+        let class_name = class;
+        let requested: Vec<String> = target.profil().map(str::to_string).into_iter().collect();
+        match self.classes.get(class) {
+            _ if is_other_class(&target, class) || target.profil().is_some_and(|name| name != editor) => PersonProfileResponse::default(),
+            None => PersonProfileResponse::default(),
+            Some(class) => { //class exists
+                let mut lock = class.write().expect("Failed to lock data");
+                let allowed_to_modify = self.check_login(class_name, &lock.participants, editor, password);
+                if !allowed_to_modify {
+                    return PersonProfileResponse::default();
+                }
+                if let Some(refused) = Self::refuse_until_changed(&lock.participants, editor, password, &requested) {
+                    return refused;
+                }
+                if let Some(error) = voting_not_open(&lock.participants) {
+                    let mut response = Self::group_to_response_custom(&lock.participants, editor, password, &requested);
+                    response.error = Some(error);
+                    return response;
+                }
+                self.record_address(class_name, editor, address);
+
+                let deleted_by = author_key(&lock.participants, editor, password);
+                let nicknames = lock.participants.nicknames_mut(&target).expect("Failed to find name");
+                let may_delete = |n: &Nickname| n.protection.can_delete() && (target.profil().is_some() || n.author() == Some(deleted_by.as_str()));
+                if let Some(position) = nicknames.iter().position(|n| n.nickname == *nickname && may_delete(n)) {
+                    let deleted = nicknames.remove(position);
+                    self.announce(class_name, &lock.participants, &target, &deleted, Event::Deleted);
+                    let id = self.trash.lock().expect("Failed to lock trash").bury(class_name, &target, position, deleted, deleted_by);
+                    println!("[{}] delete_nickname: kept as {}, UndoDelete {} to restore it", request_id::current(), id, id);
+                    lock.save();
+                    self.notify(class_name, target.profil().unwrap_or_default());
+                }
+
+                Self::group_to_response_custom(&lock.participants, editor, password, &requested)
+            }
+        }
+    }
+
+    //only the current author can hand a proposition over, the answer is the updated history
+    pub fn transfer_nickname(&self, transfer: &TransferNickname, address: Option<IpAddr>) -> NicknameHistory {
+        let TransferNickname {
+            class,
+            editor,
+            password,
+            name,
+            nickname,
+            to,
+        } = transfer;
+        println!("[{}] transfer_nickname: {} for {} from {} to {}", request_id::current(), nickname, name, editor, to);
+
+        let asked = AskForNicknameHistory { class: class.clone(), name: name.clone(), nickname: nickname.clone(), editor: editor.clone(), password: password.clone() };
+        let Some(group) = self.classes.get(class) else {
+            return NicknameHistory::default();
+        };
+        let refused = {
+            let mut lock = group.write().expect("Failed to lock data");
+            if !self.check_login(class, &lock.participants, editor, password) {
+                return NicknameHistory::default();
+            }
+            self.record_address(class, editor, address);
+
+            let author = author_key(&lock.participants, editor, password);
+            let is_author = lock.participants.profiles.get(name)
+                .and_then(|(_, nicknames)| nicknames.iter().find(|n| n.nickname == *nickname))
+                .is_some_and(|n| n.author() == Some(author.as_str()));
+            let waiting = lock.participants.must_change_password.contains(editor); //see refuse_until_changed
+            if waiting {
+                Some(Message::ChangePasswordFirst)
+            } else if lock.participants.anonymous_authors {
+                Some(Message::TransferAnonymous)
+            } else if is_author && Self::transfer(&mut lock.participants, name, nickname, to, &author) {
+                lock.save();
+                self.notify(class, name);
+                None
+            } else {
+                Some(Message::TransferRefused)
+            }
+        };
+        let mut history = self.nickname_history(&asked);
+        history.error = refused.map(|m| m.localized());
+        history
+    }
+
+    //false when the proposition or the new author doesn't exist in the group, or when the class is anonymized:
+    //the key of the new author would need their password
+    pub fn transfer(group: &mut Group, name: &str, nickname: &str, to: &str, by: &str) -> bool {
+        if !group.profiles.contains_key(to) || group.anonymous_authors {
+            return false;
+        }
+        let found = group.profiles.get_mut(name)
+            .and_then(|(_, nicknames)| nicknames.iter_mut().find(|n| n.nickname == nickname));
+        match found {
+            Some(found) => {
+                if found.author() != Some(to) {
+                    found.history.push(NicknameEvent { time: unix_now(), kind: NicknameEventKind::Transferred { by: by.to_string(), to: to.to_string() } });
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_protection(group: &mut Group, target: &Target, nickname: &str, level: Protection, by: &str) -> bool {
+        let found = group.nicknames_mut(target)
+            .and_then(|nicknames| nicknames.iter_mut().find(|n| n.nickname == nickname));
+        match found {
+            Some(found) => {
+                if found.protection != level {
+                    found.history.push(NicknameEvent { time: unix_now(), kind: NicknameEventKind::Protection { by: by.to_string(), level } });
+                }
+                found.protection = level;
+                true
+            }
+            None => false,
+        }
+    }
+
+    //folds the proposition from into the one called into: votes are moved, the earlier creation and the stricter
+    //protection are kept, gives the number of votes moved, none if either is missing
+    pub fn merge(group: &mut Group, name: &str, from: &str, into: &str, by: &str) -> Option<usize> {
+        let (_, nicknames) = group.profiles.get_mut(name)?;
+        let from_index = nicknames.iter().position(|n| n.nickname == from && from != into)?;
+        nicknames.iter().position(|n| n.nickname == into)?;
+        let from = nicknames.remove(from_index);
+        let into = nicknames.iter_mut().find(|n| n.nickname == into)?;
+
+        let mut moved = 0;
+        for voter in from.votes {
+            if !into.votes.contains(&voter) {
+                if let Some(time) = from.voted_at.get(&voter) {
+                    into.voted_at.insert(voter.clone(), *time);
+                }
+                into.votes.push(voter);
+                moved += 1;
+            }
+        }
+
+        let created = |history: &[NicknameEvent]| history.iter().find(|e| matches!(e.kind, NicknameEventKind::Created { .. })).cloned();
+        if let Some(from_created) = created(&from.history) {
+            match into.history.iter_mut().find(|e| matches!(e.kind, NicknameEventKind::Created { .. })) {
+                Some(into_created) if into_created.time > from_created.time => *into_created = from_created,
+                Some(_) => {}
+                None => into.history.insert(0, from_created),
+            }
+        }
+        if strictness(from.protection) > strictness(into.protection) {
+            into.protection = from.protection;
+        }
+        into.internal_joke |= from.internal_joke;
+        into.comments.extend(from.comments);
+        into.comments.sort_by_key(|c| c.time);
+
+        let now = unix_now();
+        into.history.push(NicknameEvent { time: now, kind: NicknameEventKind::Merged { by: by.to_string(), from: from.nickname } });
+        if moved > 0 {
+            into.history.push(NicknameEvent { time: now, kind: NicknameEventKind::VoteCount { count: into.votes.len() } });
+        }
+        Some(moved)
+    }
+}
+
+//staff only reads and only the students are proposed for, an empty editor is not checked
+fn refused_kind(group: &Group, editor: &str, target: &Target) -> Option<Message> {
+    let kind = group.kind(editor);
+    if !kind.can_vote() {
+        return Some(Message::KindCannotVote(kind));
+    }
+    let target_kind = target.profil().map_or(ProfilKind::Student, |name| group.kind(name));
+    (!target_kind.is_target()).then_some(Message::NotATarget(target_kind))
+}
+
+//a class board can only be reached through its own class
+fn is_other_class(target: &Target, class: &str) -> bool {
+    matches!(target, Target::Class(c) if c != class)
+}
+
+//the least voted proposition that may be deleted, the oldest of them on a tie
+fn lowest_deletable(nicknames: &[Nickname]) -> Option<usize> {
+    nicknames.iter().enumerate()
+        .filter(|(_, n)| n.protection.can_delete())
+        .min_by_key(|(position, n)| (n.votes.len(), *position))
+        .map(|(position, _)| position)
+}
+
+fn strictness(level: Protection) -> u8 {
+    match level {
+        Protection::Open => 0,
+        Protection::VotesLocked => 1,
+        Protection::Locked => 2,
+    }
+}
+
+//what a vote request asks for, in any mode
+#[derive(Clone, Copy)]
+enum VoteChange<'a> {
+    Cast { nickname: &'a str, rank: Option<usize> },
+    Withdraw(&'a str),
+    Clear, //every vote of the voter for this participant
+}
+
+impl<'a> VoteChange<'a> {
+    fn of_batch(nickname: Option<&'a str>) -> Self {
+        nickname.map_or(Self::Clear, |nickname| Self::Cast { nickname, rank: None })
+    }
+
+    //the propositions whose votes of voter may change
+    fn touches(&self, mode: VoteMode, nickname: &Nickname, voter: &str) -> bool {
+        let voted = nickname.votes.iter().any(|v| v == voter);
+        match self {
+            Self::Cast { nickname: n, .. } if mode.is_single() => nickname.nickname == *n || voted,
+            Self::Cast { nickname: n, .. } | Self::Withdraw(n) => nickname.nickname == *n,
+            Self::Clear => voted,
+        }
+    }
+}
+
+//a locked proposition can't gain the vote nor lose it
+fn touches_locked(nicknames: &[Nickname], voter: &str, mode: VoteMode, change: VoteChange<'_>) -> bool {
+    nicknames.iter().any(|n| !n.protection.can_vote() && change.touches(mode, n, voter))
+}
+
+//the counts that changed since counts_before go in the history
+fn record_counts(nicknames: &mut [Nickname], counts_before: Vec<usize>) {
+    let now = unix_now();
+    for (nickname, before) in nicknames.iter_mut().zip(counts_before) {
+        if nickname.votes.len() != before {
+            nickname.history.push(NicknameEvent { time: now, kind: NicknameEventKind::VoteCount { count: nickname.votes.len() } });
+        }
+    }
+}
+
+//moves the vote of voter to nickname, true if the vote landed there
+fn move_vote(nicknames: &mut [Nickname], voter: &str, nickname: &str) -> bool {
+    let counts_before: Vec<usize> = nicknames.iter().map(|n| n.votes.len()).collect();
+
+    //remove from all other nicknames
+    for nickname in nicknames.iter_mut() {
+        nickname.remove_vote(voter);
+    }
+
+    if let Some(nickname) = nicknames.iter_mut().find(|n| n.nickname == nickname) {
+        nickname.add_vote(voter, unix_now());
+    }
+
+    record_counts(nicknames, counts_before);
+    nicknames.iter().any(|n| n.nickname == nickname && n.votes.iter().any(|v| v == voter))
+}
+
+//applies change to the votes of voter for target the way mode counts them, true if a vote was cast
+fn cast_vote(group: &mut Group, mode: VoteMode, target: &Target, voter: &str, change: VoteChange<'_>) -> bool {
+    let Group { profiles, rankings, board, .. } = group;
+    let landed = match target {
+        Target::Profil(name) => match profiles.get_mut(name) {
+            Some((_, nicknames)) => cast_among(nicknames, rankings.entry(name.clone()).or_default(), mode, voter, change),
+            None => return false,
+        },
+        Target::Class(_) => cast_among(&mut board.nicknames, &mut board.rankings, mode, voter, change),
+    };
+    if let Target::Profil(name) = target {
+        if rankings.get(name).is_some_and(|r| r.is_empty()) {
+            rankings.remove(name);
+        }
+    }
+    landed
+}
+
+//rankings are those of the same participant, voter -> choices
+fn cast_among(nicknames: &mut [Nickname], rankings: &mut BTreeMap<String, Vec<String>>, mode: VoteMode, voter: &str, change: VoteChange<'_>) -> bool {
+    let mut ranking = rankings.remove(voter).unwrap_or_default();
+
+    let landed = match (mode, change) {
+        (VoteMode::Single, VoteChange::Cast { nickname, .. }) => move_vote(nicknames, voter, nickname),
+        (_, change) => {
+            let counts_before: Vec<usize> = nicknames.iter().map(|n| n.votes.len()).collect();
+            let mut landed = false;
+            for nickname in nicknames.iter_mut().filter(|n| change.touches(mode, n, voter)) {
+                nickname.remove_vote(voter);
+                if let VoteChange::Cast { .. } = change {
+                    nickname.add_vote(voter, unix_now());
+                    landed = true;
+                }
+            }
+            record_counts(nicknames, counts_before);
+
+            match change {
+                VoteChange::Cast { nickname, rank } if landed => {
+                    ranking.retain(|n| n != nickname);
+                    let at = rank.map_or(ranking.len(), |r| r.saturating_sub(1)).min(ranking.len());
+                    ranking.insert(at, nickname.to_string());
+                }
+                VoteChange::Withdraw(nickname) => ranking.retain(|n| n != nickname),
+                _ => {}
+            }
+            landed
+        }
+    };
+
+    //what was deleted or merged since, and everything once the class left ranked mode
+    ranking.retain(|n| nicknames.iter().any(|x| x.nickname == *n && x.votes.iter().any(|v| v == voter)));
+    if mode == VoteMode::Ranked && !ranking.is_empty() {
+        rankings.insert(voter.to_string(), ranking);
+    }
+    landed
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{Group, Nickname, Protection, Target, VoteMode};
+    use super::*;
+
+    fn bob() -> Target {
+        Target::Profil("Bob".to_string())
+    }
+
+    //Bob with two propositions nobody voted for yet
+    fn class() -> Group {
+        let mut group = Group::default();
+        let nicknames = ["Bobby", "Robert"].map(|n| Nickname { nickname: n.to_string(), ..Nickname::default() }).to_vec();
+        group.profiles.insert("Bob".to_string(), ("bobpass12".to_string(), nicknames));
+        group
+    }
+
+    fn votes(group: &Group) -> Vec<Vec<String>> {
+        group.profiles["Bob"].1.iter().map(|n| n.votes.clone()).collect()
+    }
+
+    fn cast(group: &mut Group, mode: VoteMode, nickname: &str, rank: Option<usize>) -> bool {
+        cast_vote(group, mode, &bob(), "Alice", VoteChange::Cast { nickname, rank })
+    }
+
+    #[test]
+    fn single_moves_the_vote() {
+        let mut group = class();
+        assert!(cast(&mut group, VoteMode::Single, "Bobby", None));
+        assert!(cast(&mut group, VoteMode::Single, "Robert", None));
+        assert_eq!(votes(&group), vec![Vec::<String>::new(), vec!["Alice".to_string()]]);
+        assert!(group.rankings.is_empty());
+    }
+
+    #[test]
+    fn multi_keeps_every_vote() {
+        let mut group = class();
+        cast(&mut group, VoteMode::Multi, "Bobby", None);
+        cast(&mut group, VoteMode::Multi, "Robert", None);
+        assert_eq!(votes(&group), vec![vec!["Alice".to_string()], vec!["Alice".to_string()]]);
+
+        assert!(!cast_vote(&mut group, VoteMode::Multi, &bob(), "Alice", VoteChange::Withdraw("Bobby")));
+        assert_eq!(votes(&group)[0], Vec::<String>::new());
+        cast_vote(&mut group, VoteMode::Multi, &bob(), "Alice", VoteChange::Clear);
+        assert_eq!(votes(&group), vec![Vec::<String>::new(); 2]);
+    }
+
+    #[test]
+    fn ranked_orders_the_choices() {
+        let mut group = class();
+        cast(&mut group, VoteMode::Ranked, "Bobby", None);
+        cast(&mut group, VoteMode::Ranked, "Robert", Some(1));
+        assert_eq!(group.rankings["Bob"]["Alice"], vec!["Robert".to_string(), "Bobby".to_string()]);
+
+        cast_vote(&mut group, VoteMode::Ranked, &bob(), "Alice", VoteChange::Withdraw("Robert"));
+        assert_eq!(group.rankings["Bob"]["Alice"], vec!["Bobby".to_string()]);
+        cast_vote(&mut group, VoteMode::Ranked, &bob(), "Alice", VoteChange::Clear);
+        assert!(group.rankings.is_empty());
+    }
+
+    #[test]
+    fn locked_propositions_keep_their_votes() {
+        let mut group = class();
+        cast(&mut group, VoteMode::Single, "Bobby", None);
+        group.profiles.get_mut("Bob").expect("Failed to find Bob").1[0].protection = Protection::Locked;
+        let nicknames = &group.profiles["Bob"].1;
+        assert!(touches_locked(nicknames, "Alice", VoteMode::Single, VoteChange::Cast { nickname: "Robert", rank: None }),
+            "moving the vote would take it from the locked one");
+        assert!(!touches_locked(nicknames, "Alice", VoteMode::Multi, VoteChange::Cast { nickname: "Robert", rank: None }));
+        assert!(touches_locked(nicknames, "Alice", VoteMode::Multi, VoteChange::Clear));
+    }
+}
@@ -0,0 +1,92 @@
+use actix_ws::{Message, Session};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use common::packets::c2s::{AskForPersonProfile, RequestKind, Subscribe};
+use common::packets::s2c::Push;
+use crate::app_state::AppState;
+use crate::State;
+
+const CAPACITY: usize = 256; //changes a slow connection may fall behind before it misses some
+
+//a profil whose propositions or votes just changed
+#[derive(Debug, Clone)]
+pub struct ProfilChange {
+    pub class: String,
+    pub name: String,
+}
+
+pub fn channel() -> broadcast::Sender<ProfilChange> {
+    broadcast::channel(CAPACITY).0
+}
+
+impl AppState {
+    //nobody listening is the usual case, not an error
+    pub fn notify(&self, class: &str, name: &str) {
+        let _ = self.changes.send(ProfilChange { class: class.to_string(), name: name.to_string() });
+    }
+
+    //the login of a subscription goes through the rate limit and the lockout like the one of a request
+    fn subscription_allowed(&self, subscribe: &Subscribe) -> bool {
+        self.classes.get(&subscribe.class)
+            .is_some_and(|group| self.check_login(&subscribe.class, &group.read().expect("Failed to lock data").participants, &subscribe.editor, &subscribe.password))
+    }
+}
+
+async fn send(session: &mut Session, push: &Push) -> bool {
+    let text = serde_json::to_string(push).expect("Failed to serialize push");
+    session.text(text).await.is_ok()
+}
+
+//one per /ws connection, the profile is computed with the login of the subscription so guests
+//get what the guest policy lets them read, like they would by asking
+pub async fn run_session(state: State, mut session: Session, mut messages: actix_ws::MessageStream) {
+    let mut changes = state.changes.subscribe();
+    let mut subscription: Option<Subscribe> = None;
+    if !send(&mut session, &Push::Classes(state.list_classes())).await {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            message = messages.recv() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<Subscribe>(&text) {
+                    Ok(mut subscribe) => {
+                        if !subscribe.editor.is_empty() && !state.subscription_allowed(&subscribe) {
+                            println!("push subscription of {} in {} refused, it follows the class as a guest", subscribe.editor, subscribe.class);
+                            subscribe.editor.clear();
+                            subscribe.password.clear();
+                        }
+                        subscription = Some(subscribe);
+                    }
+                    Err(e) => println!("Failed to parse subscription: {:?}", e),
+                },
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            change = changes.recv() => match change {
+                Ok(change) => {
+                    let Some(subscribe) = subscription.as_ref().filter(|s| s.class == change.class) else {
+                        continue;
+                    };
+                    let profiles = state.pushed_profiles(&AskForPersonProfile {
+                        class: change.class,
+                        editor: subscribe.editor.clone(),
+                        password: subscribe.password.clone(),
+                        kind: RequestKind::Custom(vec![change.name]),
+                    });
+                    if !send(&mut session, &Push::Profiles(profiles)).await {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => println!("push connection fell behind, {} changes not sent", missed),
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+    let _ = session.close(None).await;
+}
@@ -0,0 +1,31 @@
+use qrcode::QrCode;
+use qrcode::render::svg;
+use serde::Deserialize;
+use common::deep_link::DeepLink;
+
+//optional deep link embedded in the code, so a teacher can project the page of a given class
+#[derive(Deserialize, Debug)]
+pub struct QrQuery {
+    pub class: Option<String>,
+    pub profil: Option<String>,
+}
+
+impl QrQuery {
+    pub fn url(&self, base: &str) -> String {
+        match &self.class {
+            Some(class) => {
+                let link = DeepLink { class: class.clone(), profil: self.profil.clone() };
+                format!("{}/{}", base, link.to_fragment())
+            }
+            None => format!("{}/", base),
+        }
+    }
+}
+
+pub fn render_svg(url: &str) -> anyhow::Result<String> {
+    let code = QrCode::new(url.as_bytes())?;
+    Ok(code.render::<svg::Color<'_>>()
+        .min_dimensions(512, 512)
+        .quiet_zone(true)
+        .build())
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use crate::config::{Bucket, RateLimitConfig};
+use crate::State;
+
+//every route checking a password or an admin token, and the one anybody can fill the log with
+const LIMITED: [&str; 26] = ["/person_profile", "/whoami", "/my_vote_summary", "/class_summary", "/word_stats", "/leaderboard", "/suggest",
+    "/profil_history", "/nickname_history", "/add_nickname", "/add_comment", "/delete_comment", "/vote_nickname", "/batch_votes",
+    "/delete_nickname", "/transfer_nickname", "/change_display_name", "/avatar/upload", "/change_password", "/client_error", "/ws",
+    "/admin/as_of", "/admin/restore_nickname", "/admin/unarchive_nickname", "/admin/cmd_input", "/admin/log_level"];
+const LIMITED_PREFIXES: [&str; 1] = ["/job/"]; //the routes with the id in the path
+
+fn is_limited(path: &str) -> bool {
+    LIMITED.contains(&path) || LIMITED_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+}
+const MAX_TRACKED: usize = 10_000; //buckets kept before the full ones are dropped
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+//token buckets per address and per profil, a request goes through when both have a token left
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    by_address: HashMap<IpAddr, TokenBucket>,
+    by_profil: HashMap<String, TokenBucket>,
+}
+
+impl Bucket {
+    fn refill(&self, bucket: &mut TokenBucket, now: Instant) {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_minute as f64 / 60.0).min(self.burst as f64);
+        bucket.updated = now;
+    }
+
+    //time until the next token, zero when one is there
+    fn wait(&self, bucket: &TokenBucket) -> Duration {
+        if bucket.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        if self.per_minute == 0 {
+            return Duration::from_secs(60);
+        }
+        Duration::from_secs_f64((1.0 - bucket.tokens) * 60.0 / self.per_minute as f64)
+    }
+}
+
+fn bucket<'a, K: Hash + Eq>(buckets: &'a mut HashMap<K, TokenBucket>, config: &Bucket, key: K, now: Instant) -> &'a mut TokenBucket {
+    if buckets.len() >= MAX_TRACKED {
+        buckets.retain(|_, b| {
+            config.refill(b, now);
+            b.tokens < config.burst as f64
+        });
+    }
+    let bucket = buckets.entry(key).or_insert(TokenBucket { tokens: config.burst as f64, updated: now });
+    config.refill(bucket, now);
+    bucket
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, by_address: HashMap::new(), by_profil: HashMap::new() }
+    }
+
+    //none when the request may go, how long to wait otherwise, a refused request takes no token
+    pub fn check(&mut self, address: Option<IpAddr>, profil: Option<String>, now: Instant) -> Option<Duration> {
+        let (per_address, per_profil) = (self.config.per_address, self.config.per_profil);
+        let mut address = address.map(|a| bucket(&mut self.by_address, &per_address, a, now));
+        let address_wait = address.as_ref().map_or(Duration::ZERO, |b| per_address.wait(b));
+        let mut profil = profil.map(|p| bucket(&mut self.by_profil, &per_profil, p, now));
+        let profil_wait = profil.as_ref().map_or(Duration::ZERO, |b| per_profil.wait(b));
+
+        let wait = address_wait.max(profil_wait);
+        if !wait.is_zero() {
+            return Some(wait);
+        }
+        for bucket in address.iter_mut().chain(profil.iter_mut()) {
+            bucket.tokens -= 1.0;
+        }
+        None
+    }
+}
+
+//answers 429 with Retry-After on the LIMITED routes for their address, the profil has its own bucket in AppState::check_login
+pub async fn limit(request: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let state = request.app_data::<web::Data<State>>().cloned();
+    let Some(state) = state.filter(|s| is_limited(request.path()) && s.settings.read().expect("Failed to lock settings").rate_limit_enabled) else {
+        return Ok(next.call(request).await?.map_into_left_body());
+    };
+
+    let address = state.client_address(request.request());
+    let wait = state.rate_limiter.lock().expect("Failed to lock rate limiter").check(address, None, Instant::now());
+    if let Some(wait) = wait {
+        println!("rate limited: {}, retry in {:.1?}", request.path(), wait); //no address, see IpLogConfig
+        let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+        let response = HttpResponse::TooManyRequests().insert_header((header::RETRY_AFTER, retry_after.to_string())).finish();
+        return Ok(request.into_response(response).map_into_right_body());
+    }
+    Ok(next.call(request).await?.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::{Duration, Instant};
+    use crate::config::{Bucket, RateLimitConfig};
+    use super::{is_limited, RateLimiter, LIMITED};
+
+    const ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    fn limiter(address_burst: u32, profil_burst: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            per_address: Bucket { burst: address_burst, per_minute: 60 },
+            per_profil: Bucket { burst: profil_burst, per_minute: 60 },
+        })
+    }
+
+    #[test]
+    fn burst_then_refill() {
+        let mut limiter = limiter(2, 10);
+        let now = Instant::now();
+        assert_eq!(limiter.check(Some(ADDRESS), None, now), None);
+        assert_eq!(limiter.check(Some(ADDRESS), None, now), None);
+        assert_eq!(limiter.check(Some(ADDRESS), None, now), Some(Duration::from_secs(1)));
+        assert_eq!(limiter.check(Some(IpAddr::V4(Ipv4Addr::LOCALHOST)), None, now), None, "another address has its own bucket");
+        assert_eq!(limiter.check(Some(ADDRESS), None, now + Duration::from_secs(1)), None, "one token back each second");
+    }
+
+    #[test]
+    fn refused_request_takes_no_token() {
+        let mut limiter = limiter(1, 1);
+        let now = Instant::now();
+        let profil = || Some("test/Alice".to_string());
+        assert_eq!(limiter.check(None, profil(), now), None);
+        //the profil is empty, the address keeps its only token for another profil
+        assert!(limiter.check(Some(ADDRESS), profil(), now).is_some());
+        assert_eq!(limiter.check(Some(ADDRESS), Some("test/Bob".to_string()), now), None);
+    }
+
+    #[test]
+    fn no_refill_waits_a_minute() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            per_address: Bucket { burst: 1, per_minute: 0 },
+            per_profil: Bucket { burst: 1, per_minute: 0 },
+        });
+        let now = Instant::now();
+        assert_eq!(limiter.check(Some(ADDRESS), None, now), None);
+        assert_eq!(limiter.check(Some(ADDRESS), None, now + Duration::from_secs(3600)), Some(Duration::from_secs(60)));
+    }
+
+    //the type between the brackets following start, when parameters have one
+    fn packet<'a>(parameters: &'a str, start: &str) -> Option<&'a str> {
+        let rest = &parameters[parameters.find(start)? + start.len()..];
+        rest.split('>').next()
+    }
+
+    fn carries_password(packets: &str, packet: &str) -> bool {
+        let Some(at) = packets.find(&format!("pub struct {} {{", packet)) else {
+            return false;
+        };
+        let body = &packets[at..];
+        body[..body.find('}').expect("Failed to find the end of the struct")].contains("password:")
+    }
+
+    //walks the services main::routes registers, a route whose packet carries a password must be limited
+    #[test]
+    fn every_login_route_is_limited() {
+        let main = include_str!("main.rs");
+        let packets = include_str!("../../common/src/packets.rs");
+        let routes = &main[main.find("fn routes(").expect("Failed to find routes")..];
+        let routes = &routes[..routes.find("\n}").expect("Failed to find the end of routes")];
+        let handlers: Vec<&str> = routes.lines().filter_map(|l| l.trim().strip_prefix("cfg.service(")?.strip_suffix(");")).collect();
+        assert!(handlers.len() > 20, "routes not found, main.rs changed shape");
+
+        for handler in handlers {
+            let signature = format!("async fn {}(", handler);
+            let at = main.find(&signature).unwrap_or_else(|| panic!("{} not found in main.rs", handler));
+            let path = main[..at].rsplit("(\"").next().and_then(|p| p.split('"').next()).expect("Failed to find the path");
+            let parameters = &main[at + signature.len()..];
+            let parameters = &parameters[..parameters.find(')').expect("Failed to find the parameters")];
+            let login = packet(parameters, "web::Json<").or_else(|| packet(parameters, "web::Query<"))
+                .is_some_and(|packet| carries_password(packets, packet));
+            if login {
+                assert!(LIMITED.contains(&path), "{} takes a login but isn't rate limited", path);
+            }
+        }
+        //their login isn't in a json packet: a multipart form and the messages of the websocket
+        for path in ["/avatar/upload", "/ws"] {
+            assert!(LIMITED.contains(&path), "{} takes a login but isn't rate limited", path);
+        }
+        assert!(is_limited("/job/3"), "/job/{{id}} takes the admin token but isn't rate limited");
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use serde_json::Value;
+use common::{PropositionCap, VoteMode};
+use crate::app_state::AppState;
+use crate::config::{config_path, ServerConfig};
+use crate::guests::{Endpoint, GuestAccess};
+use crate::rate_limit::RateLimiter;
+
+//fields of the config ReloadConfig applies to the running server, the others are read once at start
+const RELOADABLE: [&str; 7] = ["slow_request_ms", "guest_access", "guest_endpoints", "display_name_cooldown_secs", "vote_mode",
+    "proposition_cap", "rate_limit"];
+
+//the part of the config read on every request, replaced as a whole by a reload
+pub struct Settings {
+    pub slow_request_ms: u64,
+    pub guest_access: GuestAccess,
+    pub guest_endpoints: BTreeMap<Endpoint, GuestAccess>,
+    pub display_name_cooldown_secs: u64,
+    pub vote_mode: VoteMode, //of the classes without their own
+    pub proposition_cap: PropositionCap,
+    pub rate_limit_enabled: bool,
+}
+
+impl Settings {
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            slow_request_ms: config.slow_request_ms,
+            guest_access: config.guest_access,
+            guest_endpoints: config.guest_endpoints.clone(),
+            display_name_cooldown_secs: config.display_name_cooldown_secs,
+            vote_mode: config.vote_mode,
+            proposition_cap: config.proposition_cap,
+            rate_limit_enabled: config.rate_limit.enabled,
+        }
+    }
+}
+
+impl AppState {
+    //ReloadConfig and SIGHUP, a file with errors changes nothing, the fields that need a restart keep their running value
+    //so the next reload still lists them
+    pub fn reload_config(&self) -> Vec<String> {
+        let path = config_path();
+        let (loaded, report) = ServerConfig::check(path, true);
+        let mut lines: Vec<String> = report.warnings.iter().map(|w| format!("{}: {}", path.display(), w)).collect();
+        if !report.errors.is_empty() {
+            lines.extend(report.errors.iter().map(|e| format!("{}: {}", path.display(), e)));
+            lines.push(format!("{} not reloaded", path.display()));
+            return lines;
+        }
+
+        let mut running = self.config.lock().expect("Failed to lock config");
+        let (Value::Object(mut current), Value::Object(loaded)) = (to_value(&running), to_value(&loaded)) else {
+            return vec!["Failed to compare the configs".to_string()];
+        };
+        let mut applied = Vec::new();
+        for (field, value) in loaded {
+            if current.get(&field) == Some(&value) {
+                continue;
+            }
+            if RELOADABLE.contains(&field.as_str()) {
+                current.insert(field.clone(), value);
+                applied.push(field);
+            } else {
+                lines.push(format!("{} changed, restart the server to apply it", field));
+            }
+        }
+        if applied.is_empty() {
+            lines.push(format!("nothing to apply from {}", path.display()));
+            return lines;
+        }
+
+        *running = serde_json::from_value(Value::Object(current)).expect("Failed to read back the config");
+        *self.settings.write().expect("Failed to lock settings") = Settings::new(&running);
+        if applied.iter().any(|f| f == "rate_limit") {
+            *self.rate_limiter.lock().expect("Failed to lock rate limiter") = RateLimiter::new(running.rate_limit.clone());
+        }
+        lines.push(format!("applied {}", applied.join(", ")));
+        lines
+    }
+}
+
+fn to_value(config: &ServerConfig) -> Value {
+    serde_json::to_value(config).expect("Failed to serialize config")
+}
+
+//kill -HUP reloads like ReloadConfig, the lines go to the standard output
+#[cfg(unix)]
+pub fn spawn_on_hangup(state: crate::State) {
+    use tokio::signal::unix::{signal, SignalKind};
+    actix_web::rt::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                println!("Failed to listen for SIGHUP: {:?}", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            for line in state.reload_config() {
+                println!("{}", line);
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn spawn_on_hangup(_state: crate::State) {}
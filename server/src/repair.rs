@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+use common::Group;
+use crate::app_state::AppState;
+use crate::classes::new_uuid;
+use crate::integrity::{dedupe_names, drop_orphans, targets_mut};
+use crate::links::ProfilRef;
+use crate::timing::TimedMutex;
+use crate::trash::Trash;
+
+//the subcommands of Repair, each one fixes a part of what CheckData reports
+#[derive(Debug, Clone, Copy)]
+pub enum Fix {
+    DedupeNames, //duplicated propositions, votes, rankings and display names
+    DropOrphanNicknames, //votes, rankings and tombstones left by profils or classes that are gone
+    ReindexClasses, //the uuids, and the links and guest grants naming what is gone
+}
+
+impl Fix {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "dedupe-names" | "dedupenames" => Some(Fix::DedupeNames),
+            "drop-orphan-nicknames" | "droporphannicknames" => Some(Fix::DropOrphanNicknames),
+            "reindex-classes" | "reindexclasses" => Some(Fix::ReindexClasses),
+            _ => None,
+        }
+    }
+}
+
+//a profil without uuid gets one when stable_ids wants it, an uuid already met in this or an earlier class is renewed
+fn reindex(group: &mut Group, stable_ids: bool, seen: &mut BTreeSet<String>) -> Vec<String> {
+    let mut diff = Vec::new();
+    let names: Vec<String> = group.profiles.keys().cloned().collect();
+    for name in names {
+        match group.uuids.get(&name) {
+            Some(uuid) if !seen.insert(uuid.clone()) => {
+                diff.push(format!("~ uuid of {} was also another one's, renewed", name));
+                group.uuids.insert(name, new_uuid());
+            }
+            None if stable_ids => {
+                diff.push(format!("+ uuid of {}", name));
+                group.uuids.insert(name, new_uuid());
+            }
+            _ => {}
+        }
+    }
+    for (target, nicknames) in targets_mut(group) {
+        for nickname in nicknames {
+            match &nickname.uuid {
+                Some(uuid) if !seen.insert(uuid.clone()) => {
+                    diff.push(format!("~ uuid of \"{}\" ({}) was also another one's, renewed", nickname.nickname, target));
+                    nickname.uuid = Some(new_uuid());
+                }
+                None if stable_ids => {
+                    diff.push(format!("+ uuid of \"{}\" ({})", nickname.nickname, target));
+                    nickname.uuid = Some(new_uuid());
+                }
+                _ => {}
+            }
+        }
+    }
+    diff
+}
+
+fn section(title: &str, diff: Vec<String>) -> Vec<String> {
+    if diff.is_empty() {
+        return diff;
+    }
+    std::iter::once(format!("{}:", title)).chain(diff.into_iter().map(|line| format!("  {}", line))).collect()
+}
+
+impl AppState {
+    fn profil_exists(&self, profil: &ProfilRef) -> bool {
+        self.classes.get(&profil.class)
+            .is_some_and(|group| group.read().expect("Failed to lock data").participants.profiles.contains_key(&profil.name))
+    }
+
+    //Repair, on every class when none is given; a dry run prints the same diff and saves nothing
+    pub fn repair(&self, fix: Fix, class: Option<&str>, dry_run: bool) -> Vec<String> {
+        if let Some(class) = class.filter(|class| !self.classes.contains_key(*class)) {
+            return vec![format!("unknown class: {}", class)];
+        }
+        let mut classes: Vec<&String> = self.classes.keys().filter(|name| class.is_none_or(|class| class == name.as_str())).collect();
+        self.collation.sort(&mut classes);
+
+        let mut lines = Vec::new();
+        let mut seen_uuids = BTreeSet::new();
+        for name in classes {
+            let mut lock = self.classes[name].write().expect("Failed to lock data");
+            let mut group = lock.participants.clone();
+            let diff = match fix {
+                Fix::DedupeNames => dedupe_names(&mut group),
+                Fix::DropOrphanNicknames => drop_orphans(&mut group),
+                Fix::ReindexClasses => reindex(&mut group, self.stable_ids, &mut seen_uuids),
+            };
+            if !diff.is_empty() && !dry_run {
+                lock.participants = group;
+                lock.save_anyway();
+            }
+            lines.extend(section(name, diff));
+        }
+        match fix {
+            Fix::DedupeNames => {}
+            Fix::DropOrphanNicknames => {
+                lines.extend(section("deleted propositions", self.drop_orphan_tombstones(&self.trash, class, dry_run)));
+                lines.extend(section("archived propositions", self.drop_orphan_tombstones(&self.archive, class, dry_run)));
+            }
+            Fix::ReindexClasses => {
+                lines.extend(section("links", self.drop_dangling_links(class, dry_run)));
+                lines.extend(section("guest grants", self.drop_dangling_grants(class, dry_run)));
+            }
+        }
+
+        let changes = lines.iter().filter(|line| line.starts_with("  ")).count();
+        lines.push(match (changes, dry_run) {
+            (0, _) => "nothing to repair".to_string(),
+            (changes, true) => format!("{} changes, dry run: nothing was saved", changes),
+            (changes, false) => format!("{} changes saved", changes),
+        });
+        lines
+    }
+
+    //the tombstones whose class or participant is gone, UndoDelete and Unarchive can't put them anywhere
+    fn drop_orphan_tombstones(&self, trash: &TimedMutex<Trash>, class: Option<&str>, dry_run: bool) -> Vec<String> {
+        let tombstones = trash.lock().expect("Failed to lock trash").tombstones().to_vec();
+        let orphans: Vec<_> = tombstones.into_iter()
+            .filter(|t| class.is_none_or(|class| class == t.class))
+            .filter(|t| match self.classes.get(&t.class) {
+                None => true,
+                Some(_) if t.board => false,
+                Some(_) => !self.profil_exists(&ProfilRef { class: t.class.clone(), name: t.name.clone() }),
+            })
+            .collect();
+        if !orphans.is_empty() && !dry_run {
+            trash.lock().expect("Failed to lock trash").forget(&orphans.iter().map(|t| t.id).collect());
+        }
+        orphans.iter()
+            .map(|t| format!("- {} \"{}\" for {} in {}, gone", t.id, t.nickname.nickname, t.target(), t.class))
+            .collect()
+    }
+
+    fn drop_dangling_links(&self, class: Option<&str>, dry_run: bool) -> Vec<String> {
+        let linked: Vec<ProfilRef> = self.links.lock().expect("Failed to lock links").groups().iter().flatten().cloned().collect();
+        let dangling: Vec<ProfilRef> = linked.into_iter()
+            .filter(|p| class.is_none_or(|class| class == p.class) && !self.profil_exists(p))
+            .collect();
+        if !dry_run {
+            let mut links = self.links.lock().expect("Failed to lock links");
+            for profil in &dangling {
+                links.unlink(profil);
+            }
+        }
+        dangling.iter().map(|p| format!("- link of {} ({}), gone", p.name, p.class)).collect()
+    }
+
+    fn drop_dangling_grants(&self, class: Option<&str>, dry_run: bool) -> Vec<String> {
+        let grants = self.grants.lock().expect("Failed to lock grants").list().to_vec();
+        let dangling: Vec<_> = grants.into_iter()
+            .filter(|g| class.is_none_or(|class| class == g.class || class == g.guest.class))
+            .filter(|g| !self.classes.contains_key(&g.class) || !self.profil_exists(&g.guest))
+            .collect();
+        if !dry_run {
+            let mut grants = self.grants.lock().expect("Failed to lock grants");
+            for grant in &dangling {
+                grants.revoke(&grant.guest, &grant.class);
+            }
+        }
+        dangling.iter().map(|g| format!("- guest access of {} ({}) in {}, gone", g.guest.name, g.guest.class, g.class)).collect()
+    }
+}
@@ -0,0 +1,92 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, ACCEPT_LANGUAGE};
+use actix_web::middleware::Next;
+use actix_web::web;
+use tracing::Instrument;
+use common::language::Language;
+use common::packets::s2c::{NicknameHistory, PasswordChange, PersonProfileResponse};
+use common::REQUEST_ID_HEADER;
+use crate::classes::new_uuid;
+use crate::State;
+
+//the request being served, its id is in the logs and the error answers so a report can be matched with them
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub id: String,
+    pub method: String,
+    pub path: String,
+    pub language: Language, //of the errors in the answer, from Accept-Language
+}
+
+tokio::task_local! {
+    static REQUEST: RequestContext;
+}
+
+//none outside of a request, the console and the jobs
+pub fn context() -> Option<RequestContext> {
+    REQUEST.try_with(|request| request.clone()).ok()
+}
+
+//"-" outside of a request
+pub fn current() -> String {
+    known().unwrap_or_else(|| "-".to_string())
+}
+
+//the language the request asked for, french outside of a request where no participant reads the errors
+pub fn language() -> Language {
+    REQUEST.try_with(|request| request.language).unwrap_or(Language::French)
+}
+
+fn known() -> Option<String> {
+    REQUEST.try_with(|request| request.id.clone()).ok()
+}
+
+//the refused modifications carry the id so the user can give it along with the message
+pub fn tag(mut response: PersonProfileResponse) -> PersonProfileResponse {
+    if response.error.is_some() {
+        response.request_id = known();
+    }
+    response
+}
+
+pub fn tag_password_change(mut change: PasswordChange) -> PasswordChange {
+    if change.error.is_some() {
+        change.request_id = known();
+    }
+    change
+}
+
+pub fn tag_history(mut history: NicknameHistory) -> NicknameHistory {
+    if history.error.is_some() {
+        history.request_id = known();
+    }
+    history
+}
+
+//gives every request a short id, sent back in the X-Request-Id header, the access log shows it from there
+pub async fn assign(request: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let id = new_uuid()[..8].to_string();
+    let method = request.method().to_string();
+    let path = request.path().to_string();
+    let span = tracing::info_span!("request", id = %id, method = %method, path = %path);
+    let default = request.app_data::<web::Data<State>>().map_or(Language::French, |state| state.language);
+    let language = request.headers().get(ACCEPT_LANGUAGE)
+        .and_then(|header| header.to_str().ok())
+        .and_then(Language::negotiate)
+        .unwrap_or(default);
+
+    let context = RequestContext { id: id.clone(), method: method.clone(), path: path.clone(), language };
+    let response = REQUEST.scope(context, next.call(request)).instrument(span).await;
+    let mut response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            println!("request {}: {} {} failed: {}", id, method, path, e);
+            return Err(e);
+        }
+    };
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(response)
+}
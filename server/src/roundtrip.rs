@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use common::Group;
+use crate::app_state::AppState;
+use crate::sqlite::SqliteStorage;
+use crate::storage::{FileStorage, MemoryStorage, SaveFormat, Storage, DOCUMENTS};
+
+//where the first difference is, "profiles.Alice[1].1[0].protection", none when both are the same
+fn first_difference(expected: &Value, found: &Value, path: &str) -> Option<String> {
+    match (expected, found) {
+        (Value::Object(a), Value::Object(b)) => a.keys().chain(b.keys().filter(|k| !a.contains_key(*k)))
+            .find_map(|key| {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => first_difference(a, b, &path),
+                    (Some(_), None) => Some(format!("{} lost", path)),
+                    _ => Some(format!("{} appeared", path)),
+                }
+            }),
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => a.iter().zip(b)
+            .enumerate()
+            .find_map(|(i, (a, b))| first_difference(a, b, &format!("{}[{}]", path, i))),
+        (Value::Array(a), Value::Array(b)) => Some(format!("{}: {} elements, {} after the round trip", path, a.len(), b.len())),
+        _ if expected == found => None,
+        _ => Some(format!("{}: {} became {}", path, expected, found)),
+    }
+}
+
+fn open(format: SaveFormat, dir: &Path) -> anyhow::Result<Box<dyn Storage>> {
+    Ok(match format {
+        SaveFormat::Json => {
+            let classes = dir.join("classes");
+            std::fs::create_dir_all(&classes)?;
+            Box::new(FileStorage::new(classes, dir.to_path_buf()))
+        }
+        SaveFormat::Memory => Box::new(MemoryStorage::default()),
+        SaveFormat::Sqlite => Box::new(SqliteStorage::open(&dir.join("check.db"))?),
+    })
+}
+
+//what a storage gave back, in the order things were saved, none for what it lost
+type ReadBack = (Vec<Option<anyhow::Result<Group>>>, Vec<Option<Value>>);
+
+//saves classes and documents into a new storage of format in dir and reads them back
+fn round_trip(format: SaveFormat, dir: &Path, classes: &[(String, Group)], documents: &[(&str, Value)]) -> anyhow::Result<ReadBack> {
+    let storage = open(format, dir)?;
+    for (name, group) in classes {
+        storage.save_class(name, group)?;
+    }
+    for (name, document) in documents {
+        storage.save_document(name, document)?;
+    }
+
+    let mut loaded = storage.load_classes();
+    let groups = classes.iter()
+        .map(|(name, _)| loaded.iter().position(|(n, _)| n == name).map(|index| loaded.swap_remove(index).1))
+        .collect();
+    let documents = documents.iter()
+        .map(|(name, _)| storage.load_document(name))
+        .collect::<anyhow::Result<_>>()?;
+    Ok((groups, documents))
+}
+
+//the round trip of classes and documents, as the differences found
+fn check_format(format: SaveFormat, dir: &Path, classes: &[(String, Group)], documents: &[(&str, Value)]) -> anyhow::Result<Vec<String>> {
+    let (groups, found_documents) = round_trip(format, dir, classes, documents)?;
+    let mut problems = Vec::new();
+    for ((name, group), found) in classes.iter().zip(groups) {
+        match found {
+            Some(Ok(found)) => {
+                if let Some(difference) = first_difference(&serde_json::to_value(group)?, &serde_json::to_value(&found)?, "") {
+                    problems.push(format!("{:?}: class {}: {}", format, name, difference));
+                }
+            }
+            Some(Err(e)) => problems.push(format!("{:?}: Failed to load class {}: {:?}", format, name, e)),
+            None => problems.push(format!("{:?}: class {} not found after the round trip", format, name)),
+        }
+    }
+    for ((name, document), found) in documents.iter().zip(found_documents) {
+        match found {
+            Some(found) => {
+                if let Some(difference) = first_difference(document, &found, "") {
+                    problems.push(format!("{:?}: document {}: {}", format, name, difference));
+                }
+            }
+            None => problems.push(format!("{:?}: document {} not found after the round trip", format, name)),
+        }
+    }
+    Ok(problems)
+}
+
+//a directory of its own for each check, removed by the caller
+fn check_dir(what: &str, format: SaveFormat) -> PathBuf {
+    std::env::temp_dir().join(format!("sweat_voter_{}_{}_{:?}", what, std::process::id(), format).to_lowercase())
+}
+
+impl AppState {
+    //CheckStorage: what is served now goes through every save format in a directory of its own,
+    //a field renamed on one side only or left out of a storage shows up as a difference
+    pub fn check_storage(&self) -> Vec<String> {
+        let mut classes: Vec<(String, Group)> = self.classes.iter()
+            .map(|(name, class)| (name.clone(), class.read().expect("Failed to lock data").participants.clone()))
+            .collect();
+        classes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let documents: Vec<(&str, Value)> = DOCUMENTS.into_iter()
+            .filter_map(|name| self.storage.load_document(name).ok().flatten().map(|document| (name, document)))
+            .collect();
+
+        let mut lines = Vec::new();
+        for format in [SaveFormat::Json, SaveFormat::Memory, SaveFormat::Sqlite] {
+            let dir = check_dir("check", format);
+            let checked = std::fs::create_dir_all(&dir).map_err(anyhow::Error::from)
+                .and_then(|()| check_format(format, &dir, &classes, &documents));
+            match checked {
+                Ok(problems) if problems.is_empty() => lines.push(format!("{:?}: {} classes and {} documents read back unchanged", format, classes.len(), documents.len())),
+                Ok(problems) => lines.extend(problems),
+                Err(e) => lines.push(format!("{:?}: Failed to check: {:?}", format, e)),
+            }
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                println!("Failed to remove {}: {:?}", dir.display(), e);
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+    use serde_json::json;
+    use common::{Board, Comment, Group, Nickname, NicknameEvent, NicknameEventKind, ProfilKind, Protection, VoteMode};
+    use super::*;
+
+    fn nickname(name: &str, votes: &[&str], protection: Protection) -> Nickname {
+        Nickname {
+            nickname: name.to_string(),
+            votes: votes.iter().map(|v| v.to_string()).collect(),
+            protection,
+            history: vec![
+                NicknameEvent { time: 1_700_000_000, kind: NicknameEventKind::Created { by: "Bob".to_string() } },
+                NicknameEvent { time: 1_700_000_100, kind: NicknameEventKind::VoteCount { count: votes.len() } },
+            ],
+            uuid: Some(format!("uuid of {}", name)),
+            internal_joke: protection == Protection::Open,
+            comments: vec![Comment { id: "c1".to_string(), author: "Bob".to_string(), time: 1_700_000_050, text: "ça lui va".to_string() }],
+            voted_at: votes.iter().map(|v| (v.to_string(), 1_700_000_100)).collect(),
+            announced_votes: BTreeSet::from([votes.len()]),
+        }
+    }
+
+    //every field away from its default, written out whole so a new field has to be added here too
+    fn populated() -> Group {
+        Group {
+            profiles: BTreeMap::from([
+                ("Alice".to_string(), ("alicepass12x".to_string(), vec![
+                    nickname("Ali", &["Bob", "Carole"], Protection::Open),
+                    nickname("Lili", &["Alice"], Protection::VotesLocked),
+                ])),
+                ("Bob".to_string(), ("bobpass12xx".to_string(), vec![nickname("Bobby", &[], Protection::Locked)])),
+                ("Carole".to_string(), ("carolepass1".to_string(), Vec::new())),
+            ]),
+            uuids: BTreeMap::from([("Alice".to_string(), "uuid of Alice".to_string())]),
+            author_salt: None, //read only, never written back
+            anonymous_authors: true,
+            public_min_votes: 2,
+            display_names: BTreeMap::from([("Alice".to_string(), ("Alicia".to_string(), 1_700_000_000))]),
+            prompts: vec!["Son plat préféré ?".to_string()],
+            hashed_passwords: true,
+            password_fingerprints: BTreeMap::from([("Bob".to_string(), "fingerprint".to_string())]),
+            password_changed: BTreeSet::from(["Alice".to_string()]),
+            must_change_password: BTreeSet::from(["Bob".to_string()]),
+            vote_mode: Some(VoteMode::Ranked),
+            rankings: BTreeMap::from([("Alice".to_string(), BTreeMap::from([("Bob".to_string(), vec!["Ali".to_string()])]))]),
+            voting_opens: Some(1_700_000_000),
+            voting_closes: Some(1_800_000_000),
+            vote_half_life_days: Some(30),
+            board: Board {
+                nicknames: vec![nickname("Les As", &["Alice", "Bob"], Protection::Open)],
+                rankings: BTreeMap::from([("Alice".to_string(), vec!["Les As".to_string()])]),
+            },
+            kinds: BTreeMap::from([("Carole".to_string(), ProfilKind::Teacher)]),
+            avatars: BTreeMap::from([("Alice".to_string(), "0".repeat(64))]),
+        }
+    }
+
+    fn assert_round_trip(format: SaveFormat) {
+        let classes = vec![("a".to_string(), populated()), ("b".to_string(), Group::default())];
+        let documents = [("links", json!({ "groups": [[{ "class": "a", "name": "Alice" }, { "class": "b", "name": "Alice" }]] }))];
+        let dir = check_dir("test", format);
+        std::fs::create_dir_all(&dir).expect("Failed to create the test directory");
+        let read_back = round_trip(format, &dir, &classes, &documents);
+        std::fs::remove_dir_all(&dir).expect("Failed to remove the test directory");
+
+        let (groups, found_documents) = read_back.expect("Failed the round trip");
+        let groups: Vec<Group> = groups.into_iter()
+            .map(|group| group.expect("class not found after the round trip").expect("Failed to load class"))
+            .collect();
+        assert_eq!(groups, classes.into_iter().map(|(_, group)| group).collect::<Vec<_>>());
+        assert_eq!(found_documents, documents.into_iter().map(|(_, document)| Some(document)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        assert_round_trip(SaveFormat::Json);
+    }
+
+    #[test]
+    fn memory_round_trip() {
+        assert_round_trip(SaveFormat::Memory);
+    }
+
+    #[test]
+    fn sqlite_round_trip() {
+        assert_round_trip(SaveFormat::Sqlite);
+    }
+}
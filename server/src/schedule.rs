@@ -0,0 +1,62 @@
+use common::Group;
+use common::time::format_unix_time;
+use crate::app_state::AppState;
+use crate::messages::Message;
+use crate::unix_now;
+
+//why the votes and deletions of group are refused now, none while its vote is open
+pub fn voting_not_open(group: &Group) -> Option<String> {
+    let now = unix_now();
+    group.voting_opens
+        .filter(|opens| *opens > now)
+        .map(|opens| Message::VotingOpens(opens).localized())
+        .or_else(|| voting_closed(group, now))
+}
+
+//the propositions are welcome before the vote opens, not once it is closed
+pub fn voting_closed(group: &Group, now: u64) -> Option<String> {
+    group.voting_closes
+        .filter(|closes| *closes <= now)
+        .map(|closes| Message::VotingClosed(closes).localized())
+}
+
+impl AppState {
+    //VotingOpens, none lets the class vote right away
+    pub fn set_voting_opens(&self, class: &str, opens: Option<u64>) -> Vec<String> {
+        let Some(group) = self.classes.get(class) else {
+            return vec![format!("unknown class: {}", class)];
+        };
+        let mut lock = group.write().expect("Failed to lock data");
+        if let (Some(opens), Some(closes)) = (opens, lock.participants.voting_closes) {
+            if opens >= closes {
+                return vec![format!("the vote of {} closes on {} UTC, it must open before", class, format_unix_time(closes))];
+            }
+        }
+        lock.participants.voting_opens = opens;
+        lock.save();
+        match opens {
+            Some(opens) if opens > unix_now() => vec![format!("the vote of {} opens on {} UTC", class, format_unix_time(opens))],
+            _ => vec![format!("the vote of {} is open", class)],
+        }
+    }
+
+    //SetDeadline, none lets the vote go on without end
+    pub fn set_deadline(&self, class: &str, closes: Option<u64>) -> Vec<String> {
+        let Some(group) = self.classes.get(class) else {
+            return vec![format!("unknown class: {}", class)];
+        };
+        let mut lock = group.write().expect("Failed to lock data");
+        if let (Some(opens), Some(closes)) = (lock.participants.voting_opens, closes) {
+            if closes <= opens {
+                return vec![format!("the vote of {} only opens on {} UTC, the deadline must come after", class, format_unix_time(opens))];
+            }
+        }
+        lock.participants.voting_closes = closes;
+        lock.save();
+        match closes {
+            Some(closes) if closes > unix_now() => vec![format!("the vote of {} closes on {} UTC", class, format_unix_time(closes))],
+            Some(closes) => vec![format!("the vote of {} closed on {} UTC, the class is read-only", class, format_unix_time(closes))],
+            None => vec![format!("the vote of {} has no deadline", class)],
+        }
+    }
+}
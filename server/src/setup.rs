@@ -0,0 +1,84 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+use common::Group;
+use crate::config::{self, ServerConfig};
+use crate::storage::{FileStorage, Storage};
+
+pub const CLASSES_DIR: &str = "./classes";
+
+fn ask(question: &str, default: &str) -> anyhow::Result<String> {
+    if default.is_empty() {
+        print!("{}: ", question);
+    } else {
+        print!("{} [{}]: ", question, default);
+    }
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+        anyhow::bail!("standard input closed");
+    }
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default.to_string() } else { answer.to_string() })
+}
+
+//8 characters, enough for a class and easy to hand out on paper
+pub fn new_password() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
+//one participant per line as "Name:password", an empty line ends the class, a missing password is generated,
+//either way they are asked for their own at the first login
+fn ask_participants(class: &str) -> anyhow::Result<Group> {
+    println!("participants of {}, one per line as \"Nom Prénom:mot de passe\", empty line to finish", class);
+    let mut group = Group::default();
+    loop {
+        let line = ask(" participant", "")?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, password) = line.split_once(':').unwrap_or((&line, ""));
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        let password = match password.trim() {
+            "" => {
+                let password = new_password();
+                println!("  password of {}: {}", name, password);
+                password
+            }
+            password => password.to_string(),
+        };
+        group.profiles.insert(name.to_string(), (password, Vec::new()));
+        group.must_change_password.insert(name.to_string()); //handed out on paper, replaced at the first login
+    }
+    Ok(group)
+}
+
+//--init: asks for what a new instance needs and writes the config and the classes, existing classes are never replaced
+pub fn run(config_path: &Path) -> anyhow::Result<()> {
+    let config = ServerConfig::load_file(config_path);
+    println!("sweat voter setup, press enter to keep the value in brackets");
+
+    let bind = ask("bind address", &config.bind)?;
+
+    std::fs::create_dir_all(CLASSES_DIR)?;
+    let storage = FileStorage::new(CLASSES_DIR.into(), ".".into());
+    let existing: Vec<String> = storage.load_classes().into_iter().map(|(name, _)| name).collect();
+
+    let classes = ask("class names, separated by commas", "")?;
+    for class in classes.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        if existing.iter().any(|e| e == class) {
+            println!("{} already exists, left untouched", class);
+            continue;
+        }
+        let group = ask_participants(class)?;
+        storage.save_class(class, &group)?;
+        println!("{} created with {} participants", class, group.profiles.len());
+    }
+
+    config::set_bind(config_path, &bind)?;
+    println!("configuration written to {}", config_path.display());
+    Ok(())
+}
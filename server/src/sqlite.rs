@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use common::Group;
+use crate::storage::{DataLock, Storage, DOCUMENTS};
+
+pub const DATABASE_PATH: &str = "./sweat_voter.db";
+
+//a profil belongs to a single class, the class column of profiles is its membership
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS classes (name TEXT PRIMARY KEY, settings TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS profiles (class TEXT NOT NULL, name TEXT NOT NULL, password TEXT NOT NULL, PRIMARY KEY (class, name));
+    CREATE TABLE IF NOT EXISTS propositions (class TEXT NOT NULL, profil TEXT NOT NULL, nickname TEXT NOT NULL,
+        position INTEGER NOT NULL, data TEXT NOT NULL, PRIMARY KEY (class, profil, nickname));
+    CREATE TABLE IF NOT EXISTS documents (name TEXT PRIMARY KEY, value TEXT NOT NULL);
+";
+
+//a class as rows, settings is everything of the group but the profiles
+#[derive(Default)]
+struct ClassRows {
+    settings: String,
+    profiles: BTreeMap<String, String>, //name -> password
+    propositions: BTreeMap<(String, String), (usize, String)>, //(profil, nickname) -> (position, json)
+}
+
+impl ClassRows {
+    //goes through a json value so a field added to Group or Nickname is stored without touching the schema
+    fn from_group(group: &Group) -> anyhow::Result<Self> {
+        let Value::Object(mut settings) = serde_json::to_value(group)? else {
+            anyhow::bail!("a group isn't serialized as an object");
+        };
+        let mut rows = ClassRows::default();
+        if let Some(Value::Object(profiles)) = settings.remove("profiles") {
+            for (name, profil) in profiles {
+                let Value::Array(mut profil) = profil else {
+                    anyhow::bail!("profil {} isn't serialized as (password, propositions)", name);
+                };
+                let nicknames = profil.pop().unwrap_or_default();
+                let password = profil.pop().and_then(|p| p.as_str().map(str::to_string)).unwrap_or_default();
+                for (position, nickname) in nicknames.as_array().into_iter().flatten().enumerate() {
+                    let text = nickname["nickname"].as_str().unwrap_or_default().to_string();
+                    rows.propositions.insert((name.clone(), text), (position, nickname.to_string()));
+                }
+                rows.profiles.insert(name, password);
+            }
+        }
+        rows.settings = Value::Object(settings).to_string();
+        Ok(rows)
+    }
+
+    fn to_group(&self) -> anyhow::Result<Group> {
+        let mut settings: serde_json::Map<String, Value> = serde_json::from_str(&self.settings)?;
+        let mut profiles = serde_json::Map::new();
+        for (name, password) in &self.profiles {
+            profiles.insert(name.clone(), Value::Array(vec![Value::String(password.clone()), Value::Array(Vec::new())]));
+        }
+        let mut ordered: Vec<(&String, &(usize, String))> = self.propositions.iter().map(|((profil, _), row)| (profil, row)).collect();
+        ordered.sort_by_key(|(profil, (position, _))| (*profil, *position));
+        for (profil, (_, data)) in ordered {
+            if let Some(Value::Array(nicknames)) = profiles.get_mut(profil).and_then(|p| p.get_mut(1)) {
+                nicknames.push(serde_json::from_str(data)?);
+            }
+        }
+        settings.insert("profiles".to_string(), Value::Object(profiles));
+        Ok(serde_json::from_value(Value::Object(settings))?)
+    }
+}
+
+//one database file instead of a json file per class, a save only writes the rows that changed since the last one
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+    written: Mutex<BTreeMap<String, ClassRows>>, //class -> rows as in the database
+    lock: DataLock,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let lock = DataLock::acquire(path.parent().unwrap_or(Path::new(".")))?;
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?; //a crash mid save leaves the previous state, not half of it
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection: Mutex::new(connection), written: Mutex::new(BTreeMap::new()), lock })
+    }
+
+    pub fn is_empty(&self) -> anyhow::Result<bool> {
+        let connection = self.connection.lock().expect("Failed to lock database");
+        Ok(connection.query_row("SELECT COUNT(*) FROM classes", [], |row| row.get::<_, i64>(0))? == 0)
+    }
+
+    //first start on sqlite, what the json storage held is copied in
+    pub fn import(&self, from: &dyn Storage) -> anyhow::Result<usize> {
+        let mut imported = 0;
+        for (name, group) in from.load_classes() {
+            match group {
+                Ok(group) => {
+                    self.save_class(&name, &group)?;
+                    imported += 1;
+                }
+                Err(e) => println!("Failed to import class {}: {:?}", name, e),
+            }
+        }
+        for name in DOCUMENTS {
+            if let Some(document) = from.load_document(name)? {
+                self.save_document(name, &document)?;
+            }
+        }
+        Ok(imported)
+    }
+
+    fn load_rows(connection: &Connection, class: &str, settings: String) -> anyhow::Result<ClassRows> {
+        let mut rows = ClassRows { settings, ..ClassRows::default() };
+        let mut profiles = connection.prepare_cached("SELECT name, password FROM profiles WHERE class = ?1")?;
+        for profil in profiles.query_map([class], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (name, password) = profil?;
+            rows.profiles.insert(name, password);
+        }
+        let mut propositions = connection.prepare_cached("SELECT profil, nickname, position, data FROM propositions WHERE class = ?1")?;
+        for proposition in propositions.query_map([class], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, i64>(2)?, row.get(3)?)))? {
+            let (profil, nickname, position, data) = proposition?;
+            rows.propositions.insert((profil, nickname), (position as usize, data));
+        }
+        Ok(rows)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load_classes(&self) -> Vec<(String, anyhow::Result<Group>)> {
+        let connection = self.connection.lock().expect("Failed to lock database");
+        let classes: Vec<(String, String)> = match connection.prepare("SELECT name, settings FROM classes")
+            .and_then(|mut s| s.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect()) {
+            Ok(classes) => classes,
+            Err(e) => {
+                println!("Failed to read the classes from {}: {:?}", DATABASE_PATH, e);
+                return Vec::new();
+            }
+        };
+
+        let mut written = self.written.lock().expect("Failed to lock database");
+        let mut loaded = Vec::new();
+        for (name, settings) in classes {
+            println!("found: {} in {}", name, DATABASE_PATH);
+            let group = Self::load_rows(&connection, &name, settings).and_then(|rows| {
+                let group = rows.to_group()?;
+                written.insert(name.clone(), rows);
+                Ok(group)
+            });
+            loaded.push((name, group));
+        }
+        loaded
+    }
+
+    fn save_class(&self, name: &str, group: &Group) -> anyhow::Result<()> {
+        self.lock.check()?;
+        let rows = ClassRows::from_group(group)?;
+        let mut written = self.written.lock().expect("Failed to lock database");
+        let empty = ClassRows::default();
+        let old = written.get(name).unwrap_or(&empty);
+
+        let mut connection = self.connection.lock().expect("Failed to lock database");
+        let transaction = connection.transaction()?;
+        if old.settings != rows.settings {
+            transaction.execute("INSERT INTO classes (name, settings) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET settings = excluded.settings",
+                params![name, rows.settings])?;
+        }
+        for (profil, password) in rows.profiles.iter().filter(|(profil, password)| old.profiles.get(*profil) != Some(*password)) {
+            transaction.execute("INSERT INTO profiles (class, name, password) VALUES (?1, ?2, ?3) ON CONFLICT (class, name) DO UPDATE SET password = excluded.password",
+                params![name, profil, password])?;
+        }
+        for profil in old.profiles.keys().filter(|profil| !rows.profiles.contains_key(*profil)) {
+            transaction.execute("DELETE FROM profiles WHERE class = ?1 AND name = ?2", params![name, profil])?;
+        }
+        for ((profil, nickname), (position, data)) in rows.propositions.iter().filter(|(key, row)| old.propositions.get(*key) != Some(*row)) {
+            transaction.execute("INSERT INTO propositions (class, profil, nickname, position, data) VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (class, profil, nickname) DO UPDATE SET position = excluded.position, data = excluded.data",
+                params![name, profil, nickname, *position as i64, data])?;
+        }
+        for (profil, nickname) in old.propositions.keys().filter(|key| !rows.propositions.contains_key(*key)) {
+            transaction.execute("DELETE FROM propositions WHERE class = ?1 AND profil = ?2 AND nickname = ?3", params![name, profil, nickname])?;
+        }
+        transaction.commit()?;
+
+        written.insert(name.to_string(), rows);
+        Ok(())
+    }
+
+    fn load_document(&self, name: &str) -> anyhow::Result<Option<Value>> {
+        let connection = self.connection.lock().expect("Failed to lock database");
+        let value: Option<String> = connection.query_row("SELECT value FROM documents WHERE name = ?1", [name], |row| row.get(0)).optional()?;
+        Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    fn save_document(&self, name: &str, document: &Value) -> anyhow::Result<()> {
+        self.lock.check()?;
+        let connection = self.connection.lock().expect("Failed to lock database");
+        connection.execute("INSERT INTO documents (name, value) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+            params![name, document.to_string()])?;
+        Ok(())
+    }
+}
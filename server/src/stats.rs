@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use common::{is_anonymous, Group};
+use common::packets::s2c::{LeaderboardEntry, ProposerEntry};
+use tokio::sync::broadcast::error::TryRecvError;
+use crate::app_state::AppState;
+use crate::decay::weighted_votes;
+use crate::profils::shown_in_public;
+use crate::word_stats::word_frequencies;
+use crate::{unix_now, State};
+
+const REFRESH: Duration = Duration::from_secs(2); //a burst of votes is recomputed once
+const FULL_REFRESH: Duration = Duration::from_secs(60); //for what changes without a ProfilChange, console commands for instance
+
+//what the stats endpoints answer from, computed away from the requests so they only take the class lock to check the login
+#[derive(Default)]
+pub struct ClassStats {
+    pub words: Vec<(String, usize)>,
+    pub nicknames: Vec<(LeaderboardEntry, bool)>, //with whether a public view shows it
+    pub proposers: Vec<ProposerEntry>,
+    pub public_proposers: Vec<ProposerEntry>, //counting only the nicknames a public view shows
+}
+
+#[derive(Default)]
+pub struct StatsCache {
+    classes: HashMap<String, Arc<ClassStats>>,
+}
+
+impl StatsCache {
+    pub fn get(&self, class: &str) -> Arc<ClassStats> {
+        self.classes.get(class).cloned().unwrap_or_default()
+    }
+}
+
+pub fn shown_name(group: &Group, name: &str) -> String {
+    group.display_names.get(name).map_or(name, |(shown, _)| shown.as_str()).to_string()
+}
+
+fn proposers(class: &str, group: &Group, public: bool) -> Vec<ProposerEntry> {
+    let mut by_author: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for (_, propositions) in group.profiles.values() {
+        for nickname in propositions.iter().filter(|n| !public || shown_in_public(group, n)) {
+            if let Some(author) = nickname.author().filter(|a| !is_anonymous(a)) {
+                let (count, votes) = by_author.entry(author).or_default();
+                *count += 1;
+                *votes += nickname.votes.len();
+            }
+        }
+    }
+    by_author.into_iter().map(|(author, (propositions, votes))| ProposerEntry {
+        class: class.to_string(),
+        name: shown_name(group, author),
+        propositions,
+        votes,
+    }).collect()
+}
+
+fn compute(class: &str, group: &Group) -> ClassStats {
+    let now = unix_now();
+    let nicknames = group.profiles.iter()
+        .flat_map(|(name, (_, propositions))| propositions.iter().map(move |n| (name, n)))
+        .map(|(name, nickname)| (LeaderboardEntry {
+            class: class.to_string(),
+            name: shown_name(group, name),
+            nickname: nickname.nickname.clone(),
+            votes: nickname.votes.len(),
+            weight: weighted_votes(group, nickname, now),
+        }, shown_in_public(group, nickname)))
+        .collect();
+    ClassStats {
+        words: word_frequencies(group),
+        nicknames,
+        proposers: proposers(class, group, false),
+        public_proposers: proposers(class, group, true),
+    }
+}
+
+impl AppState {
+    //the class is only read while cloning it, the counting happens with no lock held
+    pub fn refresh_stats(&self, class: &str) {
+        let Some(group) = self.classes.get(class) else {
+            return;
+        };
+        let group = group.read().expect("Failed to lock data").participants.clone();
+        let stats = Arc::new(compute(class, &group));
+        self.stats.lock().expect("Failed to lock stats").classes.insert(class.to_string(), stats);
+    }
+
+    pub fn class_stats(&self, class: &str) -> Arc<ClassStats> {
+        self.stats.lock().expect("Failed to lock stats").get(class)
+    }
+}
+
+//computes every class once before the server answers, then the ones the event stream names every REFRESH
+pub fn spawn_refresh(state: State) {
+    for class in state.classes.keys() {
+        state.refresh_stats(class);
+    }
+
+    let mut changes = state.changes.subscribe();
+    std::thread::spawn(move || {
+        let mut dirty = BTreeSet::new();
+        let mut last_full = Instant::now();
+        loop {
+            std::thread::sleep(REFRESH);
+            loop {
+                match changes.try_recv() {
+                    Ok(change) => { dirty.insert(change.class); }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Lagged(_)) => dirty.extend(state.classes.keys().cloned()),
+                    Err(TryRecvError::Closed) => return,
+                }
+            }
+            if last_full.elapsed() >= FULL_REFRESH {
+                dirty.extend(state.classes.keys().cloned());
+                last_full = Instant::now();
+            }
+            for class in std::mem::take(&mut dirty) {
+                state.refresh_stats(&class);
+            }
+        }
+    });
+}
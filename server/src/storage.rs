@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use common::Group;
+use crate::sqlite::{SqliteStorage, DATABASE_PATH};
+use crate::unix_now;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveFormat {
+    #[default]
+    Json, //one json file per class in ./classes, documents next to it
+    Memory, //loaded from the json files but never written back, for demos and dry runs
+    Sqlite, //one database, filled from the json files on the first start
+}
+
+//every named document the server saves, what a copy from one storage to another has to carry
+pub const DOCUMENTS: [&str; 6] = ["links", "filter", "guest_grants", "password_audit", "deleted_nicknames", "archived_nicknames"];
+
+//where AppState keeps its data, classes plus small named documents (links, filter...)
+pub trait Storage: Send + Sync {
+    fn load_classes(&self) -> Vec<(String, anyhow::Result<Group>)>;
+    fn save_class(&self, name: &str, group: &Group) -> anyhow::Result<()>;
+    fn load_document(&self, name: &str) -> anyhow::Result<Option<serde_json::Value>>;
+    fn save_document(&self, name: &str, document: &serde_json::Value) -> anyhow::Result<()>;
+}
+
+//the memory storage never writes, so it is the only one without the data lock
+pub fn open(format: SaveFormat) -> anyhow::Result<Arc<dyn Storage>> {
+    let files = FileStorage::new(PathBuf::from("./classes"), PathBuf::from("."));
+    Ok(match format {
+        SaveFormat::Json => Arc::new(files.locked()?),
+        SaveFormat::Memory => Arc::new(MemoryStorage::from_storage(&files)),
+        SaveFormat::Sqlite => {
+            let database = SqliteStorage::open(Path::new(DATABASE_PATH))?;
+            if database.is_empty()? && files.classes_dir.is_dir() {
+                let imported = database.import(&files)?;
+                println!("{} classes imported from {} into {}", imported, files.classes_dir.display(), DATABASE_PATH);
+            }
+            Arc::new(database)
+        }
+    })
+}
+
+//a file that can't be read even from its .bak would be replaced by an empty one at the next save,
+//the server refuses to start on it until --accept-data-loss moves it aside as <file>.corrupt-<unix time>
+pub fn quarantine_corrupt(format: SaveFormat, accept_data_loss: bool) -> anyhow::Result<()> {
+    match format {
+        SaveFormat::Memory => return Ok(()), //never writes back
+        SaveFormat::Sqlite if Path::new(DATABASE_PATH).is_file() => return Ok(()), //the files were only read by the first start
+        _ => {}
+    }
+    let files = FileStorage::new(PathBuf::from("./classes"), PathBuf::from("."));
+    let corrupt = files.corrupt_files();
+    if corrupt.is_empty() {
+        return Ok(());
+    }
+    for (path, e) in &corrupt {
+        println!("{} can't be read, nor its backup: {:#}", path.display(), e);
+    }
+    if !accept_data_loss {
+        anyhow::bail!("{} unreadable files, restore them or start with --accept-data-loss to move them aside and go on without their data", corrupt.len());
+    }
+    let now = unix_now();
+    for (path, _) in &corrupt {
+        for path in [path.clone(), path.with_extension("json.bak")].iter().filter(|p| p.exists()) {
+            let quarantined = PathBuf::from(format!("{}.corrupt-{}", path.display(), now));
+            std::fs::rename(path, &quarantined)?;
+            println!("{} moved to {}", path.display(), quarantined.display());
+        }
+    }
+    Ok(())
+}
+
+const LOCK_FILE: &str = "server.lock";
+
+//the lock file names another server, every save fails with it from then on
+#[derive(Debug)]
+pub struct LockLost(String);
+
+impl std::fmt::Display for LockLost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} no longer holds our pid, another server may be running, refusing to save", self.0)
+    }
+}
+
+impl std::error::Error for LockLost {}
+
+//exclusive os lock on a file holding our pid, released by the os when the process ends even if it crashes,
+//keeps a second server started on the same directory from overwriting the saves of the first
+pub struct DataLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl DataLock {
+    pub fn acquire(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(LOCK_FILE);
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                anyhow::bail!("another server (pid {}) is using {}, stop it first", pid.trim(), dir.display());
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file, path })
+    }
+
+    //on unix the file can be removed while we hold it and another server can lock a new one,
+    //windows doesn't let an open file be deleted
+    pub fn check(&self) -> anyhow::Result<()> {
+        if cfg!(unix) {
+            let pid = std::fs::read_to_string(&self.path).unwrap_or_default();
+            if pid.trim() != std::process::id().to_string() {
+                return Err(LockLost(self.path.display().to_string()).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+//written next to the file then renamed over it, a crash leaves the old file or the new one but never half of one,
+//the previous file stays as .bak
+fn write_atomic(path: &Path, value: &impl Serialize) -> anyhow::Result<()> {
+    let temp = path.with_extension("json.tmp");
+    let file = File::create(&temp)?;
+    let mut writer = BufWriter::new(&file);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writer.flush()?;
+    drop(writer);
+    file.sync_all()?;
+
+    if path.exists() {
+        let backup = path.with_extension("json.bak");
+        let _ = std::fs::remove_file(&backup);
+        //a link costs nothing, the copy is for the file systems without them
+        if std::fs::hard_link(path, &backup).is_err() {
+            std::fs::copy(path, &backup)?;
+        }
+    }
+    std::fs::rename(&temp, path)?;
+    //the rename itself is only durable once the directory is synced, windows can't open a directory for that
+    if cfg!(unix) {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+    }
+    Ok(())
+}
+
+//falls back on the .bak when the file can't be read, it can only be one save behind
+fn read_with_backup<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let read = |path: &Path| File::open(path).map_err(anyhow::Error::from)
+        .and_then(|file| Ok(serde_json::from_reader(BufReader::new(file))?));
+    read(path).or_else(|e| {
+        let backup = path.with_extension("json.bak");
+        if !backup.exists() {
+            return Err(e);
+        }
+        println!("Failed to read {}, using {}: {:?}", path.display(), backup.display(), e);
+        read(&backup)
+    })
+}
+
+pub struct FileStorage {
+    classes_dir: PathBuf,
+    documents_dir: PathBuf,
+    lock: Option<DataLock>,
+}
+
+impl FileStorage {
+    pub fn new(classes_dir: PathBuf, documents_dir: PathBuf) -> Self {
+        Self { classes_dir, documents_dir, lock: None }
+    }
+
+    //checks the lock before each save, fails if another server already holds it
+    pub fn locked(mut self) -> anyhow::Result<Self> {
+        self.lock = Some(DataLock::acquire(&self.documents_dir)?);
+        Ok(self)
+    }
+
+    fn check_lock(&self) -> anyhow::Result<()> {
+        self.lock.as_ref().map_or(Ok(()), DataLock::check)
+    }
+
+    fn class_path(&self, name: &str) -> PathBuf {
+        self.classes_dir.join(format!("{}.json", name))
+    }
+
+    fn document_path(&self, name: &str) -> PathBuf {
+        self.documents_dir.join(format!("{}.json", name))
+    }
+
+    //the classes and documents that fail to parse, what their loaders would take for missing
+    fn corrupt_files(&self) -> Vec<(PathBuf, anyhow::Error)> {
+        let mut corrupt = Vec::new();
+        let classes = std::fs::read_dir(&self.classes_dir).into_iter().flatten().flatten()
+            .map(|file| file.path())
+            .filter(|path| path.is_file() && path.extension() == Some("json".as_ref()));
+        for path in classes {
+            if let Err(e) = read_with_backup::<Group>(&path) {
+                corrupt.push((path, e));
+            }
+        }
+        for name in DOCUMENTS {
+            if let Err(e) = self.load_document(name) {
+                corrupt.push((self.document_path(name), e));
+            }
+        }
+        corrupt
+    }
+}
+
+impl Storage for FileStorage {
+    fn load_classes(&self) -> Vec<(String, anyhow::Result<Group>)> {
+        let files = std::fs::read_dir(&self.classes_dir).expect("Failed to read dir");
+        let mut classes = Vec::new();
+        for file in files.flatten() {
+            let path = file.path();
+            if path.is_file() && path.extension() == Some("json".as_ref()) {
+                let name = path.file_stem().get_or_insert("unknown".as_ref()).to_string_lossy().to_string();
+                println!("found: {} at {:?}", name, path);
+
+                classes.push((name, read_with_backup(&path)));
+            }
+        }
+        classes
+    }
+
+    fn save_class(&self, name: &str, group: &Group) -> anyhow::Result<()> {
+        self.check_lock()?;
+        write_atomic(&self.class_path(name), group)
+    }
+
+    fn load_document(&self, name: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        let path = self.document_path(name);
+        if !path.exists() && !path.with_extension("json.bak").exists() {
+            return Ok(None);
+        }
+        Ok(Some(read_with_backup(&path)?))
+    }
+
+    fn save_document(&self, name: &str, document: &serde_json::Value) -> anyhow::Result<()> {
+        self.check_lock()?;
+        write_atomic(&self.document_path(name), document)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStorage {
+    classes: Mutex<BTreeMap<String, Group>>,
+    documents: Mutex<BTreeMap<String, serde_json::Value>>,
+}
+
+impl MemoryStorage {
+    //copy of what another storage holds, classes that fail to load are left out
+    pub fn from_storage(storage: &dyn Storage) -> Self {
+        let memory = Self::default();
+        for (name, group) in storage.load_classes() {
+            match group {
+                Ok(group) => {
+                    memory.classes.lock().expect("Failed to lock memory storage").insert(name, group);
+                }
+                Err(e) => println!("Failed to load class {}: {:?}", name, e),
+            }
+        }
+        for name in DOCUMENTS {
+            if let Ok(Some(document)) = storage.load_document(name) {
+                memory.documents.lock().expect("Failed to lock memory storage").insert(name.to_string(), document);
+            }
+        }
+        memory
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load_classes(&self) -> Vec<(String, anyhow::Result<Group>)> {
+        self.classes.lock().expect("Failed to lock memory storage").iter()
+            .map(|(name, group)| (name.clone(), Ok(group.clone())))
+            .collect()
+    }
+
+    fn save_class(&self, name: &str, group: &Group) -> anyhow::Result<()> {
+        self.classes.lock().expect("Failed to lock memory storage").insert(name.to_string(), group.clone());
+        Ok(())
+    }
+
+    fn load_document(&self, name: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        Ok(self.documents.lock().expect("Failed to lock memory storage").get(name).cloned())
+    }
+
+    fn save_document(&self, name: &str, document: &serde_json::Value) -> anyhow::Result<()> {
+        self.documents.lock().expect("Failed to lock memory storage").insert(name.to_string(), document.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use common::packets::c2s::AskForSuggestions;
+use common::packets::s2c::Suggestions;
+use crate::app_state::AppState;
+use crate::guests::{Endpoint, GuestAccess};
+
+const MAX_SUGGESTIONS: usize = 5;
+const MIN_TEXT: usize = 2; //a single letter would match about everything
+
+//lowercase, accents and french ligatures folded, anything but letters and digits dropped,
+//so "Le Grand-Chef" and "le grand chef" compare equal
+pub fn normalize(text: &str) -> String {
+    let mut normalized = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match c {
+            'à' | 'â' | 'ä' | 'á' => normalized.push('a'),
+            'é' | 'è' | 'ê' | 'ë' => normalized.push('e'),
+            'î' | 'ï' | 'í' => normalized.push('i'),
+            'ô' | 'ö' | 'ó' => normalized.push('o'),
+            'ù' | 'û' | 'ü' | 'ú' => normalized.push('u'),
+            'ÿ' => normalized.push('y'),
+            'ç' => normalized.push('c'),
+            'ñ' => normalized.push('n'),
+            'œ' => normalized.push_str("oe"),
+            'æ' => normalized.push_str("ae"),
+            c if c.is_alphanumeric() => normalized.push(c),
+            _ => {}
+        }
+    }
+    normalized
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+//0 for the same spelling, then prefixes, then contained, then typos, none if too far apart
+fn distance(typed: &str, existing: &str) -> Option<usize> {
+    if typed == existing {
+        return Some(0);
+    }
+    if existing.starts_with(typed) {
+        return Some(1);
+    }
+    if existing.contains(typed) {
+        return Some(2);
+    }
+    let allowed = if typed.chars().count() < 5 { 1 } else { 2 };
+    let distance = edit_distance(typed, existing);
+    (distance <= allowed).then_some(2 + distance)
+}
+
+impl AppState {
+    pub fn suggestions(&self, asked: &AskForSuggestions) -> Suggestions {
+        let mut suggestions = Suggestions { name: asked.name.clone(), text: asked.text.clone(), nicknames: Vec::new() };
+        let typed = normalize(&asked.text);
+        if typed.chars().count() < MIN_TEXT {
+            return suggestions;
+        }
+        let Some(class) = self.classes.get(&asked.class) else {
+            return suggestions;
+        };
+
+        let lock = class.read().expect("Failed to lock data");
+        if self.access(Endpoint::Suggest, &asked.class, &lock.participants, &asked.editor, &asked.password) == GuestAccess::Closed {
+            return suggestions;
+        }
+        let Some((_, nicknames)) = lock.participants.profiles.get(&asked.name) else {
+            return suggestions;
+        };
+        let mut close: Vec<(usize, &String)> = nicknames.iter()
+            .filter_map(|n| Some((distance(&typed, &normalize(&n.nickname))?, &n.nickname)))
+            .collect();
+        close.sort_by(|(a, a_nickname), (b, b_nickname)| a.cmp(b).then_with(|| self.collation.compare(a_nickname, b_nickname)));
+        suggestions.nicknames = close.into_iter().take(MAX_SUGGESTIONS).map(|(_, n)| n.clone()).collect();
+        suggestions
+    }
+}
@@ -0,0 +1,79 @@
+use std::cell::Cell;
+use std::sync::{LockResult, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web;
+use crate::request_id;
+use crate::State;
+
+tokio::task_local! {
+    //time the current request spent waiting on TimedMutex locks
+    static LOCK_WAIT: Cell<Duration>;
+}
+
+//a Mutex that adds the time spent waiting for it to the request being served, if any
+pub struct TimedMutex<T> {
+    inner: Mutex<T>,
+}
+
+impl<T> TimedMutex<T> {
+    pub fn new(value: T) -> Self {
+        Self { inner: Mutex::new(value) }
+    }
+
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        timed(|| self.inner.lock())
+    }
+}
+
+//the same for a RwLock, the classes are read far more often than changed
+pub struct TimedRwLock<T> {
+    inner: RwLock<T>,
+}
+
+impl<T> TimedRwLock<T> {
+    pub fn new(value: T) -> Self {
+        Self { inner: RwLock::new(value) }
+    }
+
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+        timed(|| self.inner.read())
+    }
+
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        timed(|| self.inner.write())
+    }
+}
+
+fn timed<G>(acquire: impl FnOnce() -> G) -> G {
+    let start = Instant::now();
+    let guard = acquire();
+    let waited = start.elapsed();
+    let _ = LOCK_WAIT.try_with(|total| total.set(total.get() + waited));
+    guard
+}
+
+//logs the requests that took longer than slow_request_ms, with their share of lock waiting
+pub async fn log_slow_requests(request: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let threshold = request.app_data::<web::Data<State>>()
+        .map_or(0, |state| state.settings.read().expect("Failed to lock settings").slow_request_ms);
+    if threshold == 0 {
+        return next.call(request).await;
+    }
+
+    let method = request.method().clone();
+    let path = request.path().to_string();
+    let start = Instant::now();
+    let (response, lock_wait) = LOCK_WAIT.scope(Cell::new(Duration::ZERO), async {
+        let response = next.call(request).await;
+        (response, LOCK_WAIT.with(|total| total.get()))
+    }).await;
+
+    let elapsed = start.elapsed();
+    if elapsed >= Duration::from_millis(threshold) {
+        println!("slow request {}: {} {} took {:.2?}, {:.2?} waiting on locks", request_id::current(), method, path, elapsed, lock_wait);
+    }
+    response
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use common::{Nickname, NicknameEvent, NicknameEventKind, Target, VoteMode};
+use common::time::format_unix_time;
+use crate::app_state::AppState;
+use crate::storage::Storage;
+use crate::timing::TimedMutex;
+use crate::unix_now;
+
+const DOCUMENT: &str = "deleted_nicknames";
+const MAX_KEPT: usize = 1000; //the oldest deletions can't be undone past that
+
+//a deleted proposition as it was, votes and history included, until someone restores it
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Tombstone {
+    pub id: u64,
+    pub class: String,
+    pub name: String, //the participant it was proposed for, empty for the class board
+    pub position: usize, //among the propositions of name, to put it back where it was
+    pub nickname: Nickname,
+    pub deleted_by: String, //hashed like the authors in anonymized classes
+    pub time: u64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub board: bool,
+}
+
+impl Tombstone {
+    pub fn target(&self) -> Target {
+        match self.board {
+            true => Target::Class(self.class.clone()),
+            false => Target::Profil(self.name.clone()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+struct TombstoneList {
+    next_id: u64,
+    tombstones: Vec<Tombstone>,
+}
+
+//the deleted propositions, and the same for the archived ones
+pub struct Trash {
+    storage: Arc<dyn Storage>,
+    document: &'static str,
+    max_kept: usize,
+    next_id: u64,
+    tombstones: Vec<Tombstone>,
+}
+
+//body of /admin/restore_nickname and /admin/unarchive_nickname
+#[derive(Deserialize)]
+pub struct RestoreRequest {
+    pub token: String,
+    pub id: u64,
+}
+
+impl Trash {
+    pub fn deleted(storage: Arc<dyn Storage>) -> Self {
+        Self::load(storage, DOCUMENT, MAX_KEPT)
+    }
+
+    pub fn load(storage: Arc<dyn Storage>, document: &'static str, max_kept: usize) -> Self {
+        let list = storage.load_document(document)
+            .and_then(|d| Ok(d.map(serde_json::from_value::<TombstoneList>).transpose()?));
+        let list = match list {
+            Ok(list) => list.unwrap_or_default(),
+            Err(e) => {
+                println!("Failed to load {}: {:?}", document, e);
+                TombstoneList::default()
+            }
+        };
+        Self { storage, document, max_kept, next_id: list.next_id, tombstones: list.tombstones }
+    }
+
+    fn save(&self) {
+        let document = serde_json::to_value(TombstoneList { next_id: self.next_id, tombstones: self.tombstones.clone() })
+            .expect("Failed to serialize tombstones");
+        self.storage.save_document(self.document, &document)
+            .unwrap_or_else(|e| panic!("Failed to save {}: {:?}", self.document, e));
+    }
+
+    pub fn bury(&mut self, class: &str, target: &Target, position: usize, nickname: Nickname, deleted_by: String) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let name = target.profil().unwrap_or_default().to_string();
+        let board = target.profil().is_none();
+        self.tombstones.push(Tombstone { id, class: class.to_string(), name, position, nickname, deleted_by, time: unix_now(), board });
+        if self.tombstones.len() > self.max_kept {
+            self.tombstones.remove(0);
+        }
+        self.save();
+        id
+    }
+
+    fn take(&mut self, id: u64) -> Option<Tombstone> {
+        let index = self.tombstones.iter().position(|t| t.id == id)?;
+        let tombstone = self.tombstones.remove(index);
+        self.save();
+        Some(tombstone)
+    }
+
+    //a restore that failed, the deletion can still be undone later
+    fn put_back(&mut self, tombstone: Tombstone) {
+        let index = self.tombstones.partition_point(|t| t.id < tombstone.id);
+        self.tombstones.insert(index, tombstone);
+        self.save();
+    }
+
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &Tombstone> {
+        self.tombstones.iter().rev().take(count)
+    }
+
+    pub fn tombstones(&self) -> &[Tombstone] {
+        &self.tombstones
+    }
+
+    //Repair drop-orphan-nicknames, for the tombstones nothing could be restored into
+    pub fn forget(&mut self, ids: &BTreeSet<u64>) {
+        self.tombstones.retain(|t| !ids.contains(&t.id));
+        self.save();
+    }
+}
+
+impl AppState {
+    //puts a deleted proposition back with the votes it had, except in single mode those of the voters who voted
+    //for another one since, they would have two votes otherwise
+    pub fn restore_nickname(&self, id: u64, by: &str) -> Result<String, String> {
+        self.restore_from(&self.trash, id, by).map_err(|e| e.unwrap_or_else(|| format!("no deleted proposition {}", id)))
+    }
+
+    //none when trash has no such id
+    pub fn restore_from(&self, trash: &TimedMutex<Trash>, id: u64, by: &str) -> Result<String, Option<String>> {
+        let tombstone = trash.lock().expect("Failed to lock trash").take(id).ok_or(None)?;
+        match self.put_back(&tombstone, by) {
+            Ok(message) => Ok(message),
+            Err(e) => {
+                trash.lock().expect("Failed to lock trash").put_back(tombstone);
+                Err(Some(e))
+            }
+        }
+    }
+
+    fn put_back(&self, tombstone: &Tombstone, by: &str) -> Result<String, String> {
+        let Tombstone { class, position, nickname, deleted_by, time, .. } = tombstone;
+        let name = &tombstone.target();
+        let group = self.classes.get(class).ok_or_else(|| format!("unknown class: {}", class))?;
+        let mut lock = group.write().expect("Failed to lock data");
+        let mode = self.vote_mode(&lock.participants);
+        let nicknames = lock.participants.nicknames_mut(name).ok_or_else(|| format!("{} not found in {}", name, class))?;
+        if nicknames.iter().any(|n| n.nickname == nickname.nickname) {
+            return Err(format!("\"{}\" was proposed again for {} since, merge them instead", nickname.nickname, name));
+        }
+
+        let mut restored = nickname.clone();
+        if mode == VoteMode::Single {
+            restored.votes.retain(|voter| !nicknames.iter().any(|n| n.votes.contains(voter)));
+            restored.prune_vote_times();
+        }
+        let now = unix_now();
+        restored.history.push(NicknameEvent { time: *time, kind: NicknameEventKind::Deleted { by: deleted_by.clone() } });
+        restored.history.push(NicknameEvent { time: now, kind: NicknameEventKind::Restored { by: by.to_string() } });
+        if restored.votes.len() != nickname.votes.len() {
+            restored.history.push(NicknameEvent { time: now, kind: NicknameEventKind::VoteCount { count: restored.votes.len() } });
+        }
+        let message = format!("\"{}\" restored for {} in {} with {} of its {} votes", nickname.nickname, name, class, restored.votes.len(), nickname.votes.len());
+        nicknames.insert((*position).min(nicknames.len()), restored);
+        lock.save();
+        self.notify(class, name.profil().unwrap_or_default());
+        Ok(message)
+    }
+
+    pub fn list_deleted(&self) -> Vec<String> {
+        let trash = self.trash.lock().expect("Failed to lock trash");
+        let mut lines: Vec<String> = trash.recent(20)
+            .map(|t| format!("{}: \"{}\" for {} in {}, {} votes, deleted by {} {} UTC", t.id, t.nickname.nickname, t.target(), t.class,
+                             t.nickname.votes.len(), t.deleted_by, format_unix_time(t.time)))
+            .collect();
+        if lines.is_empty() {
+            lines.push("no deleted proposition".to_string());
+        }
+        lines
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use common::Group;
+use common::packets::c2s::AskForWordStats;
+use common::packets::s2c::WordStats;
+use crate::app_state::AppState;
+use crate::guests::{Endpoint, GuestAccess};
+use crate::profils::shown_in_public;
+
+const MAX_WORDS: usize = 100; //more would not fit in the cloud anyway
+
+//short french and english words that would fill the cloud without saying anything
+const STOPWORDS: &[&str] = &[
+    "le", "la", "les", "un", "une", "des", "du", "de", "et", "ou", "en", "au", "aux", "à", "ce", "se", "sa", "son",
+    "ses", "mon", "ma", "mes", "ton", "ta", "tes", "qui", "que", "est", "pas", "par", "pour", "sur", "dans", "avec",
+    "the", "of", "and", "to", "in", "is",
+];
+
+//lowercase words, split on anything that isn't a letter or a digit, one letter words dropped
+fn tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|t| t.chars().count() > 1 && !STOPWORDS.contains(&t.as_str()))
+}
+
+//same filter as the public views, the cloud is meant to be projected
+pub fn word_frequencies(group: &Group) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (_, nicknames) in group.profiles.values() {
+        for nickname in nicknames.iter().filter(|n| shown_in_public(group, n)) {
+            for token in tokens(&nickname.nickname) {
+                *counts.entry(token).or_default() += 1;
+            }
+        }
+    }
+
+    let mut words: Vec<(String, usize)> = counts.into_iter().collect();
+    words.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    words.truncate(MAX_WORDS);
+    words
+}
+
+impl AppState {
+    //from the stats cache, a few seconds behind the votes
+    pub fn word_stats(&self, asked: &AskForWordStats) -> WordStats {
+        let Some(class) = self.classes.get(&asked.class) else {
+            return WordStats::default();
+        };
+        let access = self.access(Endpoint::WordStats, &asked.class, &class.read().expect("Failed to lock data").participants, &asked.editor, &asked.password);
+        if access == GuestAccess::Closed {
+            return WordStats::default();
+        }
+        WordStats {
+            class: asked.class.clone(),
+            words: self.class_stats(&asked.class).words.clone(),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Serialize;
+use common::time::format_unix_time;
+use crate::app_state::AppState;
+use crate::profils::{shown_in_public, summarize};
+use crate::unix_now;
+
+#[derive(Serialize, Debug)]
+pub struct YearbookEntry {
+    pub name: String,
+    pub nickname: Option<String>, //none when nothing got a vote
+    pub votes: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct YearbookStats {
+    pub participants: usize,
+    pub voters: usize,
+    pub propositions: usize,
+}
+
+//one class as it goes to print, the public view filter applies since it ends up in every family
+#[derive(Serialize, Debug)]
+pub struct YearbookPage {
+    pub class: String,
+    pub generated: u64,
+    pub entries: Vec<YearbookEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<YearbookStats>,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+//self-contained page, whoever does the layout can restyle it or copy the table into their tool
+pub fn render_html(page: &YearbookPage) -> String {
+    let mut html = String::new();
+    let class = escape_html(&page.class);
+    let _ = writeln!(html, "<!DOCTYPE html>\n<html lang=\"fr\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>", class);
+    let _ = writeln!(html, "<style>body {{ font-family: serif; }} td, th {{ padding: 4px 12px; text-align: left; }} .votes {{ color: #666; }}</style>\n</head>\n<body>");
+    let _ = writeln!(html, "<h1>{}</h1>\n<table>\n<tr><th>Nom</th><th>Surnom</th><th>Votes</th></tr>", class);
+    for entry in &page.entries {
+        let nickname = entry.nickname.as_deref().map(escape_html).unwrap_or_default();
+        let _ = writeln!(html, "<tr><td>{}</td><td>{}</td><td class=\"votes\">{}</td></tr>", escape_html(&entry.name), nickname, entry.votes);
+    }
+    let _ = writeln!(html, "</table>");
+    if let Some(stats) = &page.stats {
+        let _ = writeln!(html, "<p>{} participants, {} ont voté, {} surnoms proposés</p>", stats.participants, stats.voters, stats.propositions);
+    }
+    let _ = writeln!(html, "<p class=\"votes\">généré le {} UTC</p>\n</body>\n</html>", format_unix_time(page.generated));
+    html
+}
+
+impl AppState {
+    pub fn yearbook_page(&self, class: &str, with_stats: bool) -> Option<YearbookPage> {
+        let group = self.classes.get(class)?;
+        let mut entries: Vec<YearbookEntry> = {
+            let lock = group.read().expect("Failed to lock data");
+            lock.participants.profiles.iter()
+                .map(|(name, (_, nicknames))| {
+                    let winner = nicknames.iter()
+                        .filter(|n| !n.votes.is_empty() && shown_in_public(&lock.participants, n))
+                        .max_by_key(|n| n.votes.len());
+                    YearbookEntry {
+                        name: name.clone(),
+                        nickname: winner.map(|n| n.nickname.clone()),
+                        votes: winner.map_or(0, |n| n.votes.len()),
+                    }
+                })
+                .collect()
+        };
+        entries.sort_by(|a, b| self.collation.compare(&a.name, &b.name));
+
+        let stats = with_stats.then(|| {
+            let summary = summarize(class, &group.read().expect("Failed to lock data").participants);
+            YearbookStats { participants: summary.participants, voters: summary.voters, propositions: summary.propositions }
+        });
+        Some(YearbookPage { class: class.to_string(), generated: unix_now(), entries, stats })
+    }
+
+    //writes <class>.json and <class>.html in dir for every class, cancel stops it before the next class
+    pub fn export_yearbook(&self, dir: &Path, with_stats: bool, cancel: &AtomicBool) -> Vec<String> {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            return vec![format!("Failed to create {}: {:?}", dir.display(), e)];
+        }
+
+        let mut classes: Vec<&String> = self.classes.keys().collect();
+        self.collation.sort(&mut classes);
+        let mut lines = Vec::new();
+        for class in classes {
+            if cancel.load(Ordering::Relaxed) {
+                lines.push(format!("cancelled, {} and the classes after it not exported", class));
+                break;
+            }
+            let Some(page) = self.yearbook_page(class, with_stats) else {
+                continue;
+            };
+            let json = std::fs::File::create(dir.join(format!("{}.json", class)))
+                .map_err(anyhow::Error::from)
+                .and_then(|file| Ok(serde_json::to_writer_pretty(file, &page)?));
+            let html = std::fs::write(dir.join(format!("{}.html", class)), render_html(&page));
+            match (json, html) {
+                (Ok(()), Ok(())) => lines.push(format!("{}: {} participants exported", class, page.entries.len())),
+                (Err(e), _) => lines.push(format!("Failed to export {}: {:?}", class, e)),
+                (_, Err(e)) => lines.push(format!("Failed to export {}: {:?}", class, e)),
+            }
+        }
+        lines.push(format!("yearbook written to {}", dir.display()));
+        lines
+    }
+}